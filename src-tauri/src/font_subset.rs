@@ -0,0 +1,308 @@
+use std::collections::BTreeSet;
+
+// 子集字体保留的表; PDF 中的 CID 字体按字形编号取字,不需要 cmap,排版用的 GSUB/GPOS 等也用不到
+const KEPT_TABLES: [&[u8; 4]; 13] = [
+    b"OS/2", b"cvt ", b"fpgm", b"gasp", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp", b"name", b"post",
+    b"prep",
+];
+
+// 组合字形的标志位
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+struct TableRecord<'a> {
+    tag: [u8; 4],
+    data: &'a [u8],
+}
+
+// 精简 TrueType 字体: 只保留指定字形 (以及组合字形引用的部件),其余字形清空。
+// 字形编号保持不变,已经写入页面的文本不需要改写
+pub fn subset_glyphs(font: &[u8], glyphs: &BTreeSet<u16>) -> Result<Vec<u8>, String> {
+    let tables = read_tables(font).ok_or("Invalid font data")?;
+    let find = |tag: &[u8; 4]| tables.iter().find(|table| &table.tag == tag).map(|table| table.data);
+    let required = |tag: &[u8; 4]| {
+        find(tag).ok_or_else(|| format!("Font is missing the {} table", String::from_utf8_lossy(tag)))
+    };
+
+    let head = required(b"head")?;
+    let maxp = required(b"maxp")?;
+    let hhea = required(b"hhea")?;
+    let hmtx = required(b"hmtx")?;
+    let loca = required(b"loca")?;
+    let glyf = required(b"glyf")?;
+
+    let long_loca = read_u16(head, 50).ok_or("Invalid head table")? != 0;
+    let glyph_count = read_u16(maxp, 4).ok_or("Invalid maxp table")? as usize;
+    let metric_count = read_u16(hhea, 34).ok_or("Invalid hhea table")? as usize;
+
+    let offsets: Vec<usize> = (0..=glyph_count)
+        .map(|i| {
+            if long_loca {
+                read_u32(loca, i * 4).map(|offset| offset as usize)
+            } else {
+                read_u16(loca, i * 2).map(|offset| offset as usize * 2)
+            }
+        })
+        .collect::<Option<_>>()
+        .ok_or("Invalid loca table")?;
+    let glyph_data = |gid: usize| glyf.get(offsets[gid]..offsets[gid + 1]).unwrap_or(&[]);
+
+    // 加入组合字形引用的部件,字形 0 (.notdef) 始终保留
+    let mut keep: BTreeSet<usize> = glyphs.iter().map(|&gid| gid as usize).filter(|&gid| gid < glyph_count).collect();
+    keep.insert(0);
+    let mut pending: Vec<usize> = keep.iter().copied().collect();
+    while let Some(gid) = pending.pop() {
+        for component in composite_components(glyph_data(gid)) {
+            if component < glyph_count && keep.insert(component) {
+                pending.push(component);
+            }
+        }
+    }
+
+    let mut new_glyf = Vec::new();
+    let mut new_offsets = Vec::with_capacity(glyph_count + 1);
+    for gid in 0..glyph_count {
+        new_offsets.push(new_glyf.len());
+        if keep.contains(&gid) {
+            new_glyf.extend_from_slice(glyph_data(gid));
+            pad4(&mut new_glyf);
+        }
+    }
+    new_offsets.push(new_glyf.len());
+
+    // 字形数据足够小时使用短格式 loca
+    let short_loca = new_glyf.len() / 2 <= u16::MAX as usize;
+    let mut new_loca = Vec::new();
+    for offset in new_offsets {
+        if short_loca {
+            new_loca.extend_from_slice(&((offset / 2) as u16).to_be_bytes());
+        } else {
+            new_loca.extend_from_slice(&(offset as u32).to_be_bytes());
+        }
+    }
+
+    let mut new_head = head.to_vec();
+    new_head[8..12].copy_from_slice(&[0; 4]);
+    new_head[50..52].copy_from_slice(&(!short_loca as u16).to_be_bytes());
+
+    // 清空未使用字形的度量,便于压缩 (PDF 中的宽度由 W 数组给出)
+    let mut new_hmtx = hmtx.to_vec();
+    for gid in (0..glyph_count).filter(|gid| !keep.contains(gid)) {
+        let range = if gid < metric_count {
+            gid * 4..gid * 4 + 4
+        } else {
+            let start = metric_count * 4 + (gid - metric_count) * 2;
+            start..start + 2
+        };
+        if let Some(entry) = new_hmtx.get_mut(range) {
+            entry.fill(0);
+        }
+    }
+
+    // post 表改为 3.0 版,去掉字形名称
+    let mut new_post = find(b"post").map(|post| post.get(..32).unwrap_or(post).to_vec()).unwrap_or_default();
+    new_post.resize(32, 0);
+    new_post[0..4].copy_from_slice(&0x0003_0000u32.to_be_bytes());
+
+    let mut output_tables: Vec<([u8; 4], Vec<u8>)> = Vec::new();
+    for tag in KEPT_TABLES {
+        let data = match tag {
+            b"glyf" => std::mem::take(&mut new_glyf),
+            b"loca" => std::mem::take(&mut new_loca),
+            b"head" => std::mem::take(&mut new_head),
+            b"hmtx" => std::mem::take(&mut new_hmtx),
+            b"post" => std::mem::take(&mut new_post),
+            _ => match find(tag) {
+                Some(data) => data.to_vec(),
+                None => continue,
+            },
+        };
+        output_tables.push((*tag, data));
+    }
+
+    Ok(write_font(font, output_tables))
+}
+
+fn read_tables(font: &[u8]) -> Option<Vec<TableRecord<'_>>> {
+    let count = read_u16(font, 4)? as usize;
+    (0..count)
+        .map(|i| {
+            let record = font.get(12 + i * 16..28 + i * 16)?;
+            let offset = read_u32(record, 8)? as usize;
+            let length = read_u32(record, 12)? as usize;
+            Some(TableRecord {
+                tag: record[0..4].try_into().ok()?,
+                data: font.get(offset..offset.checked_add(length)?)?,
+            })
+        })
+        .collect()
+}
+
+// 组合字形引用的部件字形编号
+fn composite_components(glyph: &[u8]) -> Vec<usize> {
+    let mut components = Vec::new();
+    // 轮廓数小于 0 表示组合字形
+    if glyph.len() < 10 || (read_u16(glyph, 0).unwrap_or(0) as i16) >= 0 {
+        return components;
+    }
+
+    let mut offset = 10;
+    while let (Some(flags), Some(gid)) = (read_u16(glyph, offset), read_u16(glyph, offset + 2)) {
+        components.push(gid as usize);
+        offset += 4;
+        offset += if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            offset += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            offset += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            offset += 8;
+        }
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+    components
+}
+
+// 按 sfnt 格式写出字体: 表目录、4 字节对齐的表数据以及校验和
+fn write_font(original: &[u8], tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.leading_zeros() as u16;
+    let search_range = (1u16 << entry_selector) * 16;
+
+    let mut font = Vec::new();
+    font.extend_from_slice(&original[0..4]);
+    font.extend_from_slice(&count.to_be_bytes());
+    font.extend_from_slice(&search_range.to_be_bytes());
+    font.extend_from_slice(&entry_selector.to_be_bytes());
+    font.extend_from_slice(&(count * 16 - search_range).to_be_bytes());
+
+    let mut offset = 12 + tables.len() * 16;
+    let mut head_offset = None;
+    for (tag, data) in &tables {
+        font.extend_from_slice(tag);
+        font.extend_from_slice(&checksum(data).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        if tag == b"head" {
+            head_offset = Some(offset);
+        }
+        offset += (data.len() + 3) & !3;
+    }
+
+    for (_, data) in &tables {
+        font.extend_from_slice(data);
+        pad4(&mut font);
+    }
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+        font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    font
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn pad4(data: &mut Vec<u8>) {
+    while !data.len().is_multiple_of(4) {
+        data.push(0);
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf_fonts::TEST_FONT;
+    use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+    // 只统计轮廓命令的数量
+    struct SegmentCounter(usize);
+
+    impl OutlineBuilder for SegmentCounter {
+        fn move_to(&mut self, _: f32, _: f32) {
+            self.0 += 1;
+        }
+        fn line_to(&mut self, _: f32, _: f32) {
+            self.0 += 1;
+        }
+        fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {
+            self.0 += 1;
+        }
+        fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {
+            self.0 += 1;
+        }
+        fn close(&mut self) {
+            self.0 += 1;
+        }
+    }
+
+    fn segments(face: &Face, gid: GlyphId) -> usize {
+        let mut counter = SegmentCounter(0);
+        face.outline_glyph(gid, &mut counter);
+        counter.0
+    }
+
+    // 组合字形引用的部件编号
+    fn components_of(font: &[u8], gid: u16) -> Vec<usize> {
+        let tables = read_tables(font).unwrap();
+        let find = |tag: &[u8; 4]| tables.iter().find(|table| &table.tag == tag).unwrap().data;
+        let long_loca = read_u16(find(b"head"), 50).unwrap() != 0;
+        let offset = |i: usize| {
+            if long_loca {
+                read_u32(find(b"loca"), i * 4).unwrap() as usize
+            } else {
+                read_u16(find(b"loca"), i * 2).unwrap() as usize * 2
+            }
+        };
+        let gid = gid as usize;
+        composite_components(&find(b"glyf")[offset(gid)..offset(gid + 1)])
+    }
+
+    #[test]
+    fn subset_keeps_outlines_and_advances_of_kept_glyphs() {
+        let font = std::fs::read(TEST_FONT).unwrap();
+        let face = Face::parse(&font, 0).unwrap();
+        let gid = |c: char| face.glyph_index(c).unwrap();
+
+        // é、ñ 是组合字形,部件不在保留列表中
+        let kept: BTreeSet<u16> = ['A', 'é', 'ñ'].map(|c| gid(c).0).into();
+        let components: Vec<u16> = kept.iter().flat_map(|&g| components_of(&font, g)).map(|c| c as u16).collect();
+        assert!(!components.is_empty());
+        assert!(components.iter().all(|c| !kept.contains(c)));
+
+        let subset = subset_glyphs(&font, &kept).unwrap();
+        assert!(subset.len() < font.len());
+        let subset_face = Face::parse(&subset, 0).unwrap();
+        assert_eq!(subset_face.number_of_glyphs(), face.number_of_glyphs());
+
+        for g in kept.iter().chain(&components).map(|&g| GlyphId(g)) {
+            assert!(segments(&face, g) > 0);
+            assert_eq!(segments(&subset_face, g), segments(&face, g));
+            assert_eq!(subset_face.glyph_hor_advance(g), face.glyph_hor_advance(g));
+        }
+
+        // 其他字形被清空
+        let dropped = gid('B');
+        assert_eq!(segments(&subset_face, dropped), 0);
+        assert_eq!(subset_face.glyph_hor_advance(dropped), Some(0));
+    }
+}
@@ -1,16 +1,21 @@
 use base64::{engine::general_purpose, Engine as _};
-use image::{ImageFormat, ImageReader, DynamicImage};
+use image::{ImageFormat, DynamicImage, RgbImage};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 use std::io::Write;
 use zip::write::FileOptions;
 
+use crate::image_edit::{self, TransformOp};
+use crate::image_quality::{self, QualityMetrics};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompressResult {
     pub original_size: u64,
     pub compressed_size: u64,
     pub compressed_data: String,
+    // 原图与压缩结果的质量对比
+    pub quality: Option<QualityMetrics>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,7 +24,7 @@ pub struct ImageData {
     pub data: String,
 }
 
-pub fn compress_image<F>(path: &str, max_size_kb: u32, mut progress_callback: F) -> Result<CompressResult, String> 
+pub fn compress_image<F>(path: &str, max_size_kb: u32, with_heatmap: bool, mut progress_callback: F) -> Result<CompressResult, String> 
 where
     F: FnMut(u8),
 {
@@ -43,68 +48,136 @@ where
             original_size,
             compressed_size: original_size,
             compressed_data: base64_data,
+            quality: Some(QualityMetrics::identical()),
         });
     }
 
     // 解码图像
     progress_callback(20);
-    let img = ImageReader::open(path)
-        .map_err(|e| format!("Failed to open image: {}", e))?
-        .decode()
-        .map_err(|e| format!("Failed to decode image: {}", e))?;
-    
+    let img = image_edit::open_image(path)?;
     progress_callback(30);
     println!("图像尺寸: {}x{}", img.width(), img.height());
 
     // 获取原始格式
     let format = get_image_format(path)?;
-    
+    compress_decoded(&img, format, original_size, Some(original_data), target_size, with_heatmap, progress_callback)
+}
+
+// 先应用编辑操作再压缩,直接使用解码后的图像,不经过 base64 中转
+pub fn compress_transformed_image<F>(
+    path: &str,
+    ops: &[TransformOp],
+    max_size_kb: u32,
+    with_heatmap: bool,
+    mut progress_callback: F,
+) -> Result<CompressResult, String>
+where
+    F: FnMut(u8),
+{
+    if ops.is_empty() {
+        return compress_image(path, max_size_kb, with_heatmap, progress_callback);
+    }
+
+    println!("编辑并压缩图片: {}, 操作数: {}, 目标大小: {}KB", path, ops.len(), max_size_kb);
+    progress_callback(0);
+
+    let original_size = get_file_size(path)?;
+    let format = get_image_format(path)?;
+    progress_callback(10);
+
+    let img = image_edit::open_and_transform(path, ops)?;
+    progress_callback(30);
+    println!("编辑后尺寸: {}x{}", img.width(), img.height());
+
+    // 图像已被修改,即使原文件满足大小也需要重新编码;质量与编辑后的图像比较,只反映压缩带来的损失
+    let target_size = (max_size_kb as u64) * 1024;
+    compress_decoded(&img, format, original_size, None, target_size, with_heatmap, progress_callback)
+}
+
+// 按目标大小编码解码后的图像并评估质量 (进度 40-100);
+// 提供原文件数据时,压缩后反而更大则使用原始数据
+fn compress_decoded<F>(
+    img: &DynamicImage,
+    format: ImageFormat,
+    original_size: u64,
+    original_data: Option<Vec<u8>>,
+    target_size: u64,
+    with_heatmap: bool,
+    mut progress_callback: F,
+) -> Result<CompressResult, String>
+where
+    F: FnMut(u8),
+{
     // 尝试压缩
     progress_callback(40);
-    let compressed_data = match format {
-        ImageFormat::Jpeg => compress_jpeg(&img, target_size, |p| progress_callback(40 + p / 2))?,
-        ImageFormat::Png => compress_png(&img, target_size, |p| progress_callback(40 + p / 2))?,
-        _ => {
-            // 其他格式转为 JPEG 压缩
-            println!("不支持的格式,转为 JPEG 压缩");
-            compress_jpeg(&img, target_size, |p| progress_callback(40 + p / 2))?
-        }
-    };
-    
-    progress_callback(90);
-    
+    let compressed_data = encode_for_format(img, format, target_size, |p| progress_callback(40 + p / 2))?;
+
     progress_callback(90);
-    
-    // 如果压缩后反而更大,使用原始数据
+
     let compressed_size = compressed_data.len() as u64;
-    let final_data = if compressed_size > original_size {
-        println!("压缩后更大 ({:.2} KB > {:.2} KB),使用原始数据", 
-            compressed_size as f64 / 1024.0, 
-            original_size as f64 / 1024.0
-        );
-        original_data
-    } else {
-        let reduction = (1.0 - compressed_size as f64 / original_size as f64) * 100.0;
-        println!("压缩成功: {:.2} KB -> {:.2} KB, 减少 {:.1}%", 
-            original_size as f64 / 1024.0,
-            compressed_size as f64 / 1024.0,
-            reduction
-        );
-        compressed_data
+    let (final_data, quality) = match original_data {
+        Some(original_data) if compressed_size > original_size => {
+            println!("压缩后更大 ({:.2} KB > {:.2} KB),使用原始数据",
+                compressed_size as f64 / 1024.0,
+                original_size as f64 / 1024.0
+            );
+            (original_data, Some(QualityMetrics::identical()))
+        }
+        _ => {
+            let reduction = (1.0 - compressed_size as f64 / original_size as f64) * 100.0;
+            println!("压缩成功: {:.2} KB -> {:.2} KB, 减少 {:.1}%",
+                original_size as f64 / 1024.0,
+                compressed_size as f64 / 1024.0,
+                reduction
+            );
+            let quality = measure_quality(img, &compressed_data, with_heatmap);
+            (compressed_data, quality)
+        }
     };
-    
+    progress_callback(95);
+
     let final_size = final_data.len() as u64;
     let base64_data = general_purpose::STANDARD.encode(&final_data);
-    
+
     progress_callback(100);
-    
+
     Ok(CompressResult {
         original_size,
         compressed_size: final_size,
         compressed_data: base64_data,
+        quality,
     })
 }
 
+// 质量评估失败不影响压缩结果
+fn measure_quality(original: &DynamicImage, compressed_data: &[u8], with_heatmap: bool) -> Option<QualityMetrics> {
+    match image_quality::compare_with_encoded(original, compressed_data, with_heatmap) {
+        Ok(metrics) => {
+            println!("  质量评估: PSNR {:.2} dB, SSIM {:.4}", metrics.psnr, metrics.ssim);
+            Some(metrics)
+        }
+        Err(e) => {
+            println!("  质量评估失败: {}", e);
+            None
+        }
+    }
+}
+
+pub(crate) fn encode_for_format<F>(img: &DynamicImage, format: ImageFormat, target_size: u64, progress_callback: F) -> Result<Vec<u8>, String>
+where
+    F: FnMut(u8),
+{
+    match format {
+        ImageFormat::Jpeg => compress_jpeg(img, target_size, progress_callback),
+        ImageFormat::Png => compress_png(img, target_size, progress_callback),
+        _ => {
+            // 其他格式转为 JPEG 压缩
+            println!("不支持的格式,转为 JPEG 压缩");
+            compress_jpeg(img, target_size, progress_callback)
+        }
+    }
+}
+
 fn compress_jpeg<F>(img: &DynamicImage, target_size: u64, mut progress_callback: F) -> Result<Vec<u8>, String>
 where
     F: FnMut(u8),
@@ -112,8 +185,6 @@ where
     println!("使用 mozjpeg 压缩 JPEG");
     
     let rgb_img = img.to_rgb8();
-    let width = rgb_img.width() as usize;
-    let height = rgb_img.height() as usize;
     
     // 使用二分查找策略,从中间质量开始尝试
     let qualities = [25, 35, 45, 55, 65, 75, 85];
@@ -135,19 +206,7 @@ where
         
         println!("尝试质量: {}", quality);
         
-        let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
-        comp.set_size(width, height);
-        comp.set_quality(quality as f32);
-        comp.set_optimize_scans(true);
-        
-        let mut comp = comp.start_compress(Vec::new())
-            .map_err(|e| format!("Failed to start compression: {:?}", e))?;
-        
-        comp.write_scanlines(rgb_img.as_raw())
-            .map_err(|e| format!("Failed to write scanlines: {:?}", e))?;
-        
-        let compressed_data = comp.finish()
-            .map_err(|e| format!("Failed to finish compression: {:?}", e))?;
+        let compressed_data = encode_jpeg(&rgb_img, quality)?;
         
         let size = compressed_data.len() as u64;
         println!("  压缩后: {:.2} KB", size as f64 / 1024.0);
@@ -177,19 +236,7 @@ where
         Ok(result)
     } else {
         println!("  使用最低质量 25");
-        let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
-        comp.set_size(width, height);
-        comp.set_quality(25.0);
-        comp.set_optimize_scans(true);
-        
-        let mut comp = comp.start_compress(Vec::new())
-            .map_err(|e| format!("Failed to start compression: {:?}", e))?;
-        
-        comp.write_scanlines(rgb_img.as_raw())
-            .map_err(|e| format!("Failed to write scanlines: {:?}", e))?;
-        
-        comp.finish()
-            .map_err(|e| format!("Failed to finish compression: {:?}", e))
+        encode_jpeg(&rgb_img, 25)
     }
 }
 
@@ -272,6 +319,23 @@ where
     Ok(png_data)
 }
 
+// 使用 mozjpeg 按指定质量编码
+pub(crate) fn encode_jpeg(rgb_img: &RgbImage, quality: u8) -> Result<Vec<u8>, String> {
+    let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+    comp.set_size(rgb_img.width() as usize, rgb_img.height() as usize);
+    comp.set_quality(quality as f32);
+    comp.set_optimize_scans(true);
+
+    let mut comp = comp.start_compress(Vec::new())
+        .map_err(|e| format!("Failed to start compression: {:?}", e))?;
+
+    comp.write_scanlines(rgb_img.as_raw())
+        .map_err(|e| format!("Failed to write scanlines: {:?}", e))?;
+
+    comp.finish()
+        .map_err(|e| format!("Failed to finish compression: {:?}", e))
+}
+
 pub fn save_compressed_image(data: &str, path: &str) -> Result<(), String> {
     let decoded = general_purpose::STANDARD
         .decode(data)
@@ -305,7 +369,7 @@ pub fn get_file_size(path: &str) -> Result<u64, String> {
     Ok(metadata.len())
 }
 
-fn get_image_format(path: &str) -> Result<ImageFormat, String> {
+pub(crate) fn get_image_format(path: &str) -> Result<ImageFormat, String> {
     let path = Path::new(path);
     let extension = path
         .extension()
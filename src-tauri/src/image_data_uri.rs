@@ -0,0 +1,134 @@
+use base64::{engine::general_purpose, Engine as _};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::image_compress;
+use crate::image_edit;
+
+// 超过该大小的 Data URI 会拖慢页面解析,通常建议改用外链图片
+const DEFAULT_WARN_THRESHOLD_KB: u32 = 32;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataUriStyle {
+    #[default]
+    DataUri,
+    // CSS background-image 片段
+    CssBackground,
+    // HTML <img> 标签
+    HtmlImg,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataUriOptions {
+    #[serde(default)]
+    pub style: DataUriStyle,
+    // 等比缩小到不超过该尺寸
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    // 先压缩到目标大小
+    pub max_size_kb: Option<u32>,
+    pub warn_threshold_kb: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataUriResult {
+    pub text: String,
+    pub mime_type: String,
+    // 图片编码后的字节数
+    pub image_size: u64,
+    // Data URI 字符数
+    pub uri_length: u64,
+    pub warning: Option<String>,
+}
+
+pub fn image_to_data_uri(path: &str, options: &DataUriOptions) -> Result<DataUriResult, String> {
+    println!("生成 Data URI: {}", path);
+
+    let needs_resize = options.max_width.is_some() || options.max_height.is_some();
+    let data = if !needs_resize && options.max_size_kb.is_none() {
+        // 无需处理时直接使用原文件,避免重新编码
+        fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?
+    } else {
+        let img = image_edit::open_image(path)?;
+        let img = match (options.max_width, options.max_height) {
+            (None, None) => img,
+            (max_width, max_height) => shrink_to_fit(
+                img,
+                max_width.unwrap_or(u32::MAX),
+                max_height.unwrap_or(u32::MAX),
+            ),
+        };
+        encode_image(&img, path, options.max_size_kb)?
+    };
+
+    let format = image::guess_format(&data).map_err(|e| format!("Failed to detect image format: {}", e))?;
+    let mime_type = format.to_mime_type().to_string();
+    let uri = format!("data:{};base64,{}", mime_type, general_purpose::STANDARD.encode(&data));
+
+    let threshold_kb = options.warn_threshold_kb.unwrap_or(DEFAULT_WARN_THRESHOLD_KB);
+    let uri_length = uri.len() as u64;
+    let warning = if uri_length > threshold_kb as u64 * 1024 {
+        Some(format!(
+            "Data URI is {:.1} KB, larger than the recommended {} KB; consider resizing or compressing the image",
+            uri_length as f64 / 1024.0,
+            threshold_kb
+        ))
+    } else {
+        None
+    };
+
+    let text = match options.style {
+        DataUriStyle::DataUri => uri,
+        DataUriStyle::CssBackground => format!("background-image: url(\"{}\");", uri),
+        DataUriStyle::HtmlImg => {
+            let alt = Path::new(path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .replace('"', "&quot;");
+            format!("<img src=\"{}\" alt=\"{}\" />", uri, alt)
+        }
+    };
+
+    Ok(DataUriResult {
+        text,
+        mime_type,
+        image_size: data.len() as u64,
+        uri_length,
+        warning,
+    })
+}
+
+// 只缩小不放大
+fn shrink_to_fit(img: DynamicImage, max_width: u32, max_height: u32) -> DynamicImage {
+    if img.width() <= max_width && img.height() <= max_height {
+        img
+    } else {
+        img.resize(max_width.max(1), max_height.max(1), FilterType::Lanczos3)
+    }
+}
+
+fn encode_image(img: &DynamicImage, path: &str, max_size_kb: Option<u32>) -> Result<Vec<u8>, String> {
+    let format = image_compress::get_image_format(path)?;
+
+    // 有目标大小时复用压缩流程
+    if let Some(max_size_kb) = max_size_kb {
+        return image_compress::encode_for_format(img, format, max_size_kb as u64 * 1024, |_| {});
+    }
+
+    match format {
+        // JPEG 不限大小时取最高质量档
+        ImageFormat::Jpeg => image_compress::encode_for_format(img, format, u64::MAX, |_| {}),
+        _ => {
+            let mut data = Vec::new();
+            img.write_to(&mut Cursor::new(&mut data), format)
+                .map_err(|e| format!("Failed to encode image: {}", e))?;
+            Ok(data)
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::UNIX_EPOCH;
+
+use crate::image_edit;
+
+// 支持扫描的图片扩展名
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp", "gif"];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    // 均值哈希: 最快,对亮度变化敏感
+    Average,
+    // 差值哈希: 对渐变和缩放稳定,适合截图
+    Difference,
+    // 感知哈希 (DCT): 最稳健,对压缩和轻微修改不敏感
+    Perceptual,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateImage {
+    pub path: String,
+    pub file_size: u64,
+    pub width: u32,
+    pub height: u32,
+    // 与组内第一张 (推荐保留) 的汉明距离
+    pub distance: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    // 按分辨率、文件大小降序排列,第一张为推荐保留的图片
+    pub images: Vec<DuplicateImage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateScanResult {
+    pub scanned: usize,
+    pub groups: Vec<DuplicateGroup>,
+    pub failed: Vec<String>,
+}
+
+// 缓存条目,三种哈希一次算完,切换算法时无需重新解码
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HashCacheEntry {
+    average: u64,
+    difference: u64,
+    perceptual: u64,
+    width: u32,
+    height: u32,
+    // 文件路径和修改时间,用于清理已删除或已修改文件的条目
+    #[serde(default)]
+    path: String,
+    #[serde(default)]
+    modified: u64,
+}
+
+impl HashCacheEntry {
+    fn hash(&self, algorithm: HashAlgorithm) -> u64 {
+        match algorithm {
+            HashAlgorithm::Average => self.average,
+            HashAlgorithm::Difference => self.difference,
+            HashAlgorithm::Perceptual => self.perceptual,
+        }
+    }
+}
+
+struct ScannedFile {
+    path: PathBuf,
+    file_size: u64,
+    modified: u64,
+    cache_key: String,
+}
+
+// 获取哈希缓存文件路径
+fn get_hash_cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|cache| cache.join("leap").join("image_hashes.json"))
+}
+
+// 缓存键: 路径 + 修改时间,文件被修改后自动失效
+fn get_cache_key(path: &Path, modified: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update(modified.to_le_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn load_hash_cache() -> HashMap<String, HashCacheEntry> {
+    get_hash_cache_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 清理缓存: 本次扫描到的条目保留,其他条目的文件已删除或已修改时移除 (旧格式条目没有路径,一并移除)
+fn prune_hash_cache(cache: &mut HashMap<String, HashCacheEntry>, files: &[ScannedFile]) -> usize {
+    let scanned: HashSet<&str> = files.iter().map(|file| file.cache_key.as_str()).collect();
+    let before = cache.len();
+    cache.retain(|key, entry| {
+        scanned.contains(key.as_str())
+            || (!entry.path.is_empty()
+                && fs::metadata(&entry.path).ok().map(|metadata| modified_secs(&metadata)) == Some(entry.modified))
+    });
+    before - cache.len()
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn save_hash_cache(cache: &HashMap<String, HashCacheEntry>) -> Result<(), String> {
+    let cache_path = get_hash_cache_path().ok_or("Cache directory not found")?;
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    }
+
+    let content = serde_json::to_string(cache).map_err(|e| format!("Failed to serialize hash cache: {}", e))?;
+    fs::write(cache_path, content).map_err(|e| format!("Failed to write hash cache: {}", e))
+}
+
+fn collect_images(dir: &Path, recursive: bool, files: &mut Vec<ScannedFile>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read directory {:?}: {}", dir, e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            if recursive {
+                // 子目录读取失败时跳过,不影响整体扫描
+                let _ = collect_images(&path, recursive, files);
+            }
+            continue;
+        }
+
+        let is_image = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            .unwrap_or(false);
+        if !is_image {
+            continue;
+        }
+
+        let modified = modified_secs(&metadata);
+        files.push(ScannedFile {
+            cache_key: get_cache_key(&path, modified),
+            file_size: metadata.len(),
+            modified,
+            path,
+        });
+    }
+
+    Ok(())
+}
+
+fn compute_hashes(file: &ScannedFile) -> Result<HashCacheEntry, String> {
+    let path = file.path.to_string_lossy().to_string();
+    let img = image_edit::open_image(&path)?;
+
+    Ok(HashCacheEntry {
+        average: average_hash(&img),
+        difference: difference_hash(&img),
+        perceptual: perceptual_hash(&img),
+        width: img.width(),
+        height: img.height(),
+        path,
+        modified: file.modified,
+    })
+}
+
+fn to_gray(img: &DynamicImage, width: u32, height: u32) -> GrayImage {
+    img.resize_exact(width, height, FilterType::Triangle).to_luma8()
+}
+
+fn average_hash(img: &DynamicImage) -> u64 {
+    let gray = to_gray(img, 8, 8);
+    let mean = gray.pixels().map(|p| p[0] as u32).sum::<u32>() / 64;
+
+    gray.pixels()
+        .enumerate()
+        .fold(0u64, |hash, (i, p)| if p[0] as u32 > mean { hash | (1 << i) } else { hash })
+}
+
+fn difference_hash(img: &DynamicImage) -> u64 {
+    let gray = to_gray(img, 9, 8);
+    let mut hash = 0u64;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            if gray.get_pixel(x, y)[0] < gray.get_pixel(x + 1, y)[0] {
+                hash |= 1 << (y * 8 + x);
+            }
+        }
+    }
+    hash
+}
+
+fn perceptual_hash(img: &DynamicImage) -> u64 {
+    const SIZE: usize = 32;
+    const LOW: usize = 8;
+
+    let gray = to_gray(img, SIZE as u32, SIZE as u32);
+    let pixels: Vec<f64> = gray.pixels().map(|p| p[0] as f64).collect();
+
+    // 二维 DCT-II,只计算左上角低频 8x8 部分
+    let cosines: Vec<f64> = (0..LOW)
+        .flat_map(|u| {
+            (0..SIZE).map(move |x| {
+                (std::f64::consts::PI * (2 * x + 1) as f64 * u as f64 / (2 * SIZE) as f64).cos()
+            })
+        })
+        .collect();
+
+    let mut coefficients = [0.0f64; LOW * LOW];
+    for v in 0..LOW {
+        for u in 0..LOW {
+            let mut sum = 0.0;
+            for y in 0..SIZE {
+                let cy = cosines[v * SIZE + y];
+                for x in 0..SIZE {
+                    sum += pixels[y * SIZE + x] * cosines[u * SIZE + x] * cy;
+                }
+            }
+            coefficients[v * LOW + u] = sum;
+        }
+    }
+
+    // 直流分量不参与中位数计算
+    let mut sorted: Vec<f64> = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+
+    coefficients
+        .iter()
+        .enumerate()
+        .fold(0u64, |hash, (i, &c)| if c > median { hash | (1 << i) } else { hash })
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+pub fn find_duplicate_images<F>(
+    dir: &str,
+    algorithm: HashAlgorithm,
+    threshold: u32,
+    recursive: bool,
+    mut progress_callback: F,
+) -> Result<DuplicateScanResult, String>
+where
+    F: FnMut(u8),
+{
+    println!("扫描重复图片: {}, 算法: {:?}, 阈值: {}", dir, algorithm, threshold);
+    progress_callback(0);
+
+    let mut files = Vec::new();
+    collect_images(Path::new(dir), recursive, &mut files)?;
+    println!("找到 {} 张图片", files.len());
+    progress_callback(5);
+
+    let mut cache = load_hash_cache();
+    let mut entries: Vec<Option<HashCacheEntry>> = files.iter().map(|f| cache.get(&f.cache_key).cloned()).collect();
+    let missing: Vec<usize> = (0..files.len()).filter(|&i| entries[i].is_none()).collect();
+    println!("缓存命中 {} 张,需计算 {} 张", files.len() - missing.len(), missing.len());
+
+    // 多线程计算未命中缓存的哈希
+    let mut failed = Vec::new();
+    if !missing.is_empty() {
+        let workers = num_cpus::get().clamp(1, missing.len());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::scope(|scope| {
+            for worker in 0..workers {
+                let tx = tx.clone();
+                let files = &files;
+                let missing = &missing;
+                scope.spawn(move || {
+                    for &index in missing.iter().skip(worker).step_by(workers) {
+                        let _ = tx.send((index, compute_hashes(&files[index])));
+                    }
+                });
+            }
+            drop(tx);
+
+            for (done, (index, result)) in rx.into_iter().enumerate() {
+                match result {
+                    Ok(entry) => {
+                        cache.insert(files[index].cache_key.clone(), entry.clone());
+                        entries[index] = Some(entry);
+                    }
+                    Err(e) => {
+                        println!("  计算哈希失败 {:?}: {}", files[index].path, e);
+                        failed.push(files[index].path.to_string_lossy().to_string());
+                    }
+                }
+                progress_callback((5 + (done + 1) * 85 / missing.len()) as u8);
+            }
+        });
+
+    }
+
+    let pruned = prune_hash_cache(&mut cache, &files);
+    if !missing.is_empty() || pruned > 0 {
+        if pruned > 0 {
+            println!("清理哈希缓存 {} 条", pruned);
+        }
+        if let Err(e) = save_hash_cache(&cache) {
+            println!("保存哈希缓存失败: {}", e);
+        }
+    }
+    progress_callback(90);
+
+    // 并查集按汉明距离聚类
+    let hashed: Vec<usize> = (0..files.len()).filter(|&i| entries[i].is_some()).collect();
+    let hashes: Vec<u64> = hashed.iter().map(|&i| entries[i].as_ref().unwrap().hash(algorithm)).collect();
+    let mut parents: Vec<usize> = (0..hashed.len()).collect();
+
+    for a in 0..hashed.len() {
+        for b in (a + 1)..hashed.len() {
+            if (hashes[a] ^ hashes[b]).count_ones() <= threshold {
+                let root_a = find_root(&mut parents, a);
+                let root_b = find_root(&mut parents, b);
+                if root_a != root_b {
+                    parents[root_b] = root_a;
+                }
+            }
+        }
+    }
+
+    let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..hashed.len() {
+        let root = find_root(&mut parents, i);
+        clusters.entry(root).or_default().push(i);
+    }
+
+    let mut groups: Vec<DuplicateGroup> = clusters
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|mut members| {
+            members.sort_by_key(|&m| {
+                let entry = entries[hashed[m]].as_ref().unwrap();
+                std::cmp::Reverse((entry.width as u64 * entry.height as u64, files[hashed[m]].file_size))
+            });
+
+            let best_hash = hashes[members[0]];
+            let images = members
+                .iter()
+                .map(|&m| {
+                    let file = &files[hashed[m]];
+                    let entry = entries[hashed[m]].as_ref().unwrap();
+                    DuplicateImage {
+                        path: file.path.to_string_lossy().to_string(),
+                        file_size: file.file_size,
+                        width: entry.width,
+                        height: entry.height,
+                        distance: (hashes[m] ^ best_hash).count_ones(),
+                    }
+                })
+                .collect();
+
+            DuplicateGroup { images }
+        })
+        .collect();
+
+    // 组内图片多的排在前面
+    groups.sort_by(|a, b| b.images.len().cmp(&a.images.len()).then_with(|| a.images[0].path.cmp(&b.images[0].path)));
+    println!("找到 {} 组重复图片", groups.len());
+
+    progress_callback(100);
+
+    Ok(DuplicateScanResult {
+        scanned: files.len(),
+        groups,
+        failed,
+    })
+}
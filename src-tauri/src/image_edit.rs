@@ -0,0 +1,267 @@
+use image::{imageops, DynamicImage, ImageReader, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// 单个图片变换操作,按顺序依次应用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransformOp {
+    // 裁剪矩形 (像素,左上角为原点)
+    Crop {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    // 顺时针旋转,90/180/270 为无损快速路径,其他角度会扩展画布并用 fill 填充空白
+    Rotate {
+        degrees: f32,
+        #[serde(default)]
+        fill: Option<String>,
+    },
+    Flip {
+        direction: FlipDirection,
+    },
+    // 填充画布到指定宽高比,图片居中
+    Pad {
+        aspect_width: u32,
+        aspect_height: u32,
+        #[serde(default)]
+        fill: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlipDirection {
+    Horizontal,
+    Vertical,
+}
+
+// 默认填充色: 白色
+const DEFAULT_FILL: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+pub fn open_image(path: &str) -> Result<DynamicImage, String> {
+    ImageReader::open(path)
+        .map_err(|e| format!("Failed to open image: {}", e))?
+        .with_guessed_format()
+        .map_err(|e| format!("Failed to detect image format: {}", e))?
+        .decode()
+        .map_err(|e| format!("Failed to decode image: {}", e))
+}
+
+// 读取图片并应用变换,供压缩和 PDF 流程直接使用
+pub fn open_and_transform(path: &str, ops: &[TransformOp]) -> Result<DynamicImage, String> {
+    let img = open_image(path)?;
+    apply_operations(img, ops)
+}
+
+pub fn apply_operations(mut img: DynamicImage, ops: &[TransformOp]) -> Result<DynamicImage, String> {
+    for op in ops {
+        img = apply_operation(img, op)?;
+    }
+    Ok(img)
+}
+
+fn apply_operation(img: DynamicImage, op: &TransformOp) -> Result<DynamicImage, String> {
+    match op {
+        TransformOp::Crop { x, y, width, height } => crop(img, *x, *y, *width, *height),
+        TransformOp::Rotate { degrees, fill } => {
+            let fill = parse_fill(fill.as_deref())?;
+            Ok(rotate(img, *degrees, fill))
+        }
+        TransformOp::Flip { direction } => Ok(match direction {
+            FlipDirection::Horizontal => img.fliph(),
+            FlipDirection::Vertical => img.flipv(),
+        }),
+        TransformOp::Pad { aspect_width, aspect_height, fill } => {
+            let fill = parse_fill(fill.as_deref())?;
+            pad_to_aspect(img, *aspect_width, *aspect_height, fill)
+        }
+    }
+}
+
+fn crop(img: DynamicImage, x: u32, y: u32, width: u32, height: u32) -> Result<DynamicImage, String> {
+    if width == 0 || height == 0 {
+        return Err("Crop width and height must be greater than 0".to_string());
+    }
+    if x >= img.width() || y >= img.height() {
+        return Err(format!(
+            "Crop origin ({}, {}) is outside the image ({}x{})",
+            x,
+            y,
+            img.width(),
+            img.height()
+        ));
+    }
+
+    // 超出部分截断到图片边界
+    let width = width.min(img.width() - x);
+    let height = height.min(img.height() - y);
+    Ok(img.crop_imm(x, y, width, height))
+}
+
+fn rotate(img: DynamicImage, degrees: f32, fill: Rgba<u8>) -> DynamicImage {
+    let normalized = degrees.rem_euclid(360.0);
+
+    // 直角旋转走无损路径
+    if normalized.abs() < f32::EPSILON {
+        return img;
+    }
+    if (normalized - 90.0).abs() < f32::EPSILON {
+        return img.rotate90();
+    }
+    if (normalized - 180.0).abs() < f32::EPSILON {
+        return img.rotate180();
+    }
+    if (normalized - 270.0).abs() < f32::EPSILON {
+        return img.rotate270();
+    }
+
+    let had_alpha = img.color().has_alpha();
+    let rotated = rotate_arbitrary(&img.to_rgba8(), normalized, fill);
+
+    // 原图无透明通道且填充色不透明时,保持 RGB 以便后续 JPEG 编码
+    if !had_alpha && fill[3] == 255 {
+        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(rotated).to_rgb8())
+    } else {
+        DynamicImage::ImageRgba8(rotated)
+    }
+}
+
+// 任意角度旋转: 扩展画布以容纳整张图,反向映射 + 双线性插值
+pub(crate) fn rotate_arbitrary(src: &RgbaImage, degrees: f32, fill: Rgba<u8>) -> RgbaImage {
+    let (src_w, src_h) = (src.width() as f32, src.height() as f32);
+    let theta = degrees.to_radians();
+    let (sin, cos) = theta.sin_cos();
+
+    let dst_w = (src_w * cos.abs() + src_h * sin.abs()).ceil().max(1.0) as u32;
+    let dst_h = (src_w * sin.abs() + src_h * cos.abs()).ceil().max(1.0) as u32;
+
+    let (src_cx, src_cy) = (src_w / 2.0, src_h / 2.0);
+    let (dst_cx, dst_cy) = (dst_w as f32 / 2.0, dst_h as f32 / 2.0);
+
+    RgbaImage::from_fn(dst_w, dst_h, |x, y| {
+        // 以像素中心为采样点,逆时针旋转回原图坐标 (图像坐标系 y 轴向下,即顺时针旋转)
+        let dx = x as f32 + 0.5 - dst_cx;
+        let dy = y as f32 + 0.5 - dst_cy;
+        let sx = dx * cos + dy * sin + src_cx - 0.5;
+        let sy = -dx * sin + dy * cos + src_cy - 0.5;
+        sample_bilinear(src, sx, sy, fill)
+    })
+}
+
+pub(crate) fn sample_bilinear(src: &RgbaImage, x: f32, y: f32, fill: Rgba<u8>) -> Rgba<u8> {
+    let (w, h) = (src.width() as i64, src.height() as i64);
+    if x < -1.0 || y < -1.0 || x > w as f32 || y > h as f32 {
+        return fill;
+    }
+
+    let x0 = x.floor() as i64;
+    let y0 = y.floor() as i64;
+    let fx = x - x0 as f32;
+    let fy = y - y0 as f32;
+
+    let pixel = |px: i64, py: i64| -> Rgba<u8> {
+        if px < 0 || py < 0 || px >= w || py >= h {
+            fill
+        } else {
+            *src.get_pixel(px as u32, py as u32)
+        }
+    };
+
+    let p00 = pixel(x0, y0);
+    let p10 = pixel(x0 + 1, y0);
+    let p01 = pixel(x0, y0 + 1);
+    let p11 = pixel(x0 + 1, y0 + 1);
+
+    let mut out = [0u8; 4];
+    for c in 0..4 {
+        let top = p00[c] as f32 * (1.0 - fx) + p10[c] as f32 * fx;
+        let bottom = p01[c] as f32 * (1.0 - fx) + p11[c] as f32 * fx;
+        out[c] = (top * (1.0 - fy) + bottom * fy).round().clamp(0.0, 255.0) as u8;
+    }
+    Rgba(out)
+}
+
+fn pad_to_aspect(
+    img: DynamicImage,
+    aspect_width: u32,
+    aspect_height: u32,
+    fill: Rgba<u8>,
+) -> Result<DynamicImage, String> {
+    if aspect_width == 0 || aspect_height == 0 {
+        return Err("Aspect ratio must be greater than 0".to_string());
+    }
+
+    let (w, h) = (img.width() as u64, img.height() as u64);
+    let (aw, ah) = (aspect_width as u64, aspect_height as u64);
+
+    // 只扩展不缩小: 当前比例更宽则增加高度,否则增加宽度
+    let (canvas_w, canvas_h) = if w * ah >= h * aw {
+        (w, (w * ah).div_ceil(aw))
+    } else {
+        ((h * aw).div_ceil(ah), h)
+    };
+
+    if canvas_w == w && canvas_h == h {
+        return Ok(img);
+    }
+
+    let had_alpha = img.color().has_alpha();
+    let mut canvas = RgbaImage::from_pixel(canvas_w as u32, canvas_h as u32, fill);
+    let offset_x = ((canvas_w - w) / 2) as i64;
+    let offset_y = ((canvas_h - h) / 2) as i64;
+    imageops::overlay(&mut canvas, &img.to_rgba8(), offset_x, offset_y);
+
+    if !had_alpha && fill[3] == 255 {
+        Ok(DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(canvas).to_rgb8()))
+    } else {
+        Ok(DynamicImage::ImageRgba8(canvas))
+    }
+}
+
+fn parse_fill(fill: Option<&str>) -> Result<Rgba<u8>, String> {
+    match fill {
+        Some(color) => parse_hex_color(color),
+        None => Ok(DEFAULT_FILL),
+    }
+}
+
+// 解析 #RGB / #RRGGBB / #RRGGBBAA 格式的颜色
+pub fn parse_hex_color(color: &str) -> Result<Rgba<u8>, String> {
+    let hex = color.trim().trim_start_matches('#');
+    let invalid = || format!("Invalid color: {}", color);
+
+    let expanded: String = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex.to_string(),
+        _ => return Err(invalid()),
+    };
+
+    let channel = |i: usize| u8::from_str_radix(&expanded[i..i + 2], 16).map_err(|_| invalid());
+    let alpha = if expanded.len() == 8 { channel(6)? } else { 255 };
+
+    Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, alpha]))
+}
+
+// 应用变换后保存到文件,格式由输出路径扩展名决定
+pub fn edit_image(path: &str, ops: &[TransformOp], output_path: &str) -> Result<(), String> {
+    let img = open_and_transform(path, ops)?;
+
+    let is_jpeg = Path::new(output_path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|ext| matches!(ext.to_lowercase().as_str(), "jpg" | "jpeg"))
+        .unwrap_or(false);
+
+    // JPEG 不支持透明通道
+    let img = if is_jpeg {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+    } else {
+        img
+    };
+
+    img.save(output_path)
+        .map_err(|e| format!("Failed to save image: {}", e))
+}
@@ -0,0 +1,207 @@
+use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::image_edit;
+
+// 提取前先缩小图片,颜色分布基本不变,速度快很多
+const SAMPLE_MAX_SIDE: u32 = 256;
+
+// 透明度低于该值的像素不计入占比
+const MIN_ALPHA: u8 = 128;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaletteColor {
+    pub hex: String,
+    pub rgb: [u8; 3],
+    // 色相 (0-360), 饱和度 (0-100), 亮度 (0-100)
+    pub hsl: [f64; 3],
+    // 占比 (0-100)
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteExportFormat {
+    // CSS 自定义属性
+    Css,
+    // GIMP 调色板
+    Gpl,
+    // Adobe 色板交换文件
+    Ase,
+}
+
+pub fn extract_palette(path: &str, count: u32) -> Result<Vec<PaletteColor>, String> {
+    if !(1..=256).contains(&count) {
+        return Err(format!("Color count must be between 1 and 256, got {}", count));
+    }
+
+    println!("提取调色板: {}, 颜色数: {}", path, count);
+    let img = image_edit::open_image(path)?;
+    let img = if img.width() > SAMPLE_MAX_SIDE || img.height() > SAMPLE_MAX_SIDE {
+        img.resize(SAMPLE_MAX_SIDE, SAMPLE_MAX_SIDE, FilterType::Triangle)
+    } else {
+        img
+    };
+
+    let rgba_img = img.to_rgba8();
+    let width = rgba_img.width() as usize;
+    let height = rgba_img.height() as usize;
+
+    // 与 compress_png 相同,使用 imagequant 做颜色量化
+    let mut liq = imagequant::new();
+    liq.set_speed(5).map_err(|e| format!("Failed to set speed: {:?}", e))?;
+    liq.set_quality(0, 100).map_err(|e| format!("Failed to set quality: {:?}", e))?;
+    // imagequant 至少量化为 2 色,只要 1 色时取占比最大的一个
+    liq.set_max_colors(count.max(2)).map_err(|e| format!("Failed to set max colors: {:?}", e))?;
+
+    let rgba_pixels: Vec<imagequant::RGBA> = rgba_img
+        .as_raw()
+        .chunks_exact(4)
+        .map(|chunk| imagequant::RGBA::new(chunk[0], chunk[1], chunk[2], chunk[3]))
+        .collect();
+
+    let mut img_data = liq.new_image(
+        rgba_pixels.into_boxed_slice(),
+        width,
+        height,
+        0.0
+    ).map_err(|e| format!("Failed to create image: {:?}", e))?;
+
+    let mut res = liq.quantize(&mut img_data)
+        .map_err(|e| format!("Failed to quantize: {:?}", e))?;
+
+    // 统计占比时不需要抖动
+    res.set_dithering_level(0.0)
+        .map_err(|e| format!("Failed to set dithering: {:?}", e))?;
+
+    let (palette, pixels) = res.remapped(&mut img_data)
+        .map_err(|e| format!("Failed to remap: {:?}", e))?;
+
+    let mut counts = vec![0u64; palette.len()];
+    for &index in &pixels {
+        if let Some(color) = palette.get(index as usize) {
+            if color.a >= MIN_ALPHA {
+                counts[index as usize] += 1;
+            }
+        }
+    }
+
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        return Err("Image has no opaque pixels".to_string());
+    }
+
+    let mut colors: Vec<(u64, PaletteColor)> = palette
+        .iter()
+        .zip(counts)
+        .filter(|(_, n)| *n > 0)
+        .map(|(c, n)| {
+            let percentage = (n as f64 * 10000.0 / total as f64).round() / 100.0;
+            (n, palette_color(c.r, c.g, c.b, percentage))
+        })
+        .collect();
+
+    colors.sort_by_key(|(n, _)| std::cmp::Reverse(*n));
+    Ok(colors.into_iter().take(count as usize).map(|(_, color)| color).collect())
+}
+
+fn palette_color(r: u8, g: u8, b: u8, percentage: f64) -> PaletteColor {
+    PaletteColor {
+        hex: format!("#{:02x}{:02x}{:02x}", r, g, b),
+        rgb: [r, g, b],
+        hsl: rgb_to_hsl(r, g, b),
+        percentage,
+    }
+}
+
+fn rgb_to_hsl(r: u8, g: u8, b: u8) -> [f64; 3] {
+    let r = r as f64 / 255.0;
+    let g = g as f64 / 255.0;
+    let b = b as f64 / 255.0;
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+
+    let (hue, saturation) = if delta == 0.0 {
+        (0.0, 0.0)
+    } else {
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, saturation)
+    };
+
+    [
+        (hue * 10.0).round() / 10.0,
+        (saturation * 1000.0).round() / 10.0,
+        (lightness * 1000.0).round() / 10.0,
+    ]
+}
+
+pub fn export_palette(colors: &[PaletteColor], format: PaletteExportFormat, output_path: &str) -> Result<(), String> {
+    let data = match format {
+        PaletteExportFormat::Css => palette_to_css(colors).into_bytes(),
+        PaletteExportFormat::Gpl => palette_to_gpl(colors).into_bytes(),
+        PaletteExportFormat::Ase => palette_to_ase(colors),
+    };
+
+    fs::write(output_path, data).map_err(|e| format!("Failed to write palette file: {}", e))
+}
+
+fn palette_to_css(colors: &[PaletteColor]) -> String {
+    let mut css = String::from(":root {\n");
+    for (i, color) in colors.iter().enumerate() {
+        css.push_str(&format!("  --color-{}: {};\n", i + 1, color.hex));
+    }
+    css.push_str("}\n");
+    css
+}
+
+fn palette_to_gpl(colors: &[PaletteColor]) -> String {
+    let mut gpl = String::from("GIMP Palette\nName: Leap Palette\nColumns: 8\n#\n");
+    for (i, color) in colors.iter().enumerate() {
+        let [r, g, b] = color.rgb;
+        gpl.push_str(&format!("{:3} {:3} {:3}\tcolor-{} ({})\n", r, g, b, i + 1, color.hex));
+    }
+    gpl
+}
+
+// ASE 格式: 大端序,每个颜色一个块,名称为带结尾 0 的 UTF-16
+fn palette_to_ase(colors: &[PaletteColor]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(b"ASEF");
+    data.extend_from_slice(&1u16.to_be_bytes());
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&(colors.len() as u32).to_be_bytes());
+
+    for color in colors {
+        let name: Vec<u16> = color.hex.encode_utf16().chain(std::iter::once(0)).collect();
+
+        let mut block = Vec::new();
+        block.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        for unit in &name {
+            block.extend_from_slice(&unit.to_be_bytes());
+        }
+        block.extend_from_slice(b"RGB ");
+        for channel in color.rgb {
+            block.extend_from_slice(&(channel as f32 / 255.0).to_be_bytes());
+        }
+        // 颜色类型: 2 = 普通色
+        block.extend_from_slice(&2u16.to_be_bytes());
+
+        // 块类型: 0x0001 = 颜色条目
+        data.extend_from_slice(&1u16.to_be_bytes());
+        data.extend_from_slice(&(block.len() as u32).to_be_bytes());
+        data.extend_from_slice(&block);
+    }
+
+    data
+}
@@ -0,0 +1,204 @@
+use base64::{engine::general_purpose, Engine as _};
+use image::{DynamicImage, GrayImage, ImageFormat, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+// 完全相同时 PSNR 为无穷大,JSON 无法表示,封顶为 100 dB
+const MAX_PSNR: f64 = 100.0;
+
+// SSIM 窗口大小与步长
+const SSIM_WINDOW: u32 = 8;
+const SSIM_STRIDE: u32 = 4;
+
+// SSIM 稳定常数 (L = 255)
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+// 热力图放大倍数,让细微差异也能看清
+const HEATMAP_GAIN: f64 = 4.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityMetrics {
+    // 峰值信噪比 (dB),越高越好
+    pub psnr: f64,
+    // 结构相似度 (0-1),越接近 1 越好
+    pub ssim: f64,
+    // 面向用户的质量百分比
+    pub quality_percent: f64,
+    // 差异热力图 (PNG base64),仅在请求时生成
+    pub heatmap: Option<String>,
+}
+
+impl QualityMetrics {
+    // 数据未改变时直接返回满分,无需解码比较
+    pub fn identical() -> Self {
+        QualityMetrics {
+            psnr: MAX_PSNR,
+            ssim: 1.0,
+            quality_percent: 100.0,
+            heatmap: None,
+        }
+    }
+}
+
+// 比较原图与压缩后的编码数据
+pub fn compare_with_encoded(
+    original: &DynamicImage,
+    compressed_data: &[u8],
+    with_heatmap: bool,
+) -> Result<QualityMetrics, String> {
+    let compressed = image::load_from_memory(compressed_data)
+        .map_err(|e| format!("Failed to decode compressed image: {}", e))?;
+    compare_images(original, &compressed, with_heatmap)
+}
+
+pub fn compare_images(
+    original: &DynamicImage,
+    compressed: &DynamicImage,
+    with_heatmap: bool,
+) -> Result<QualityMetrics, String> {
+    if original.width() != compressed.width() || original.height() != compressed.height() {
+        return Err(format!(
+            "Image dimensions differ: {}x{} vs {}x{}",
+            original.width(),
+            original.height(),
+            compressed.width(),
+            compressed.height()
+        ));
+    }
+
+    let original_rgb = original.to_rgb8();
+    let compressed_rgb = compressed.to_rgb8();
+
+    let psnr = compute_psnr(&original_rgb, &compressed_rgb);
+    let ssim = compute_ssim(&original.to_luma8(), &compressed.to_luma8());
+    let heatmap = if with_heatmap {
+        Some(render_heatmap(&original_rgb, &compressed_rgb)?)
+    } else {
+        None
+    };
+
+    Ok(QualityMetrics {
+        psnr: (psnr * 100.0).round() / 100.0,
+        ssim: (ssim * 10000.0).round() / 10000.0,
+        quality_percent: (ssim.clamp(0.0, 1.0) * 1000.0).round() / 10.0,
+        heatmap,
+    })
+}
+
+fn compute_psnr(a: &RgbImage, b: &RgbImage) -> f64 {
+    let sum: f64 = a
+        .as_raw()
+        .iter()
+        .zip(b.as_raw())
+        .map(|(&x, &y)| {
+            let d = x as f64 - y as f64;
+            d * d
+        })
+        .sum();
+
+    let mse = sum / a.as_raw().len().max(1) as f64;
+    if mse == 0.0 {
+        MAX_PSNR
+    } else {
+        (10.0 * (255.0 * 255.0 / mse).log10()).min(MAX_PSNR)
+    }
+}
+
+// 基于亮度通道的滑动窗口 SSIM 均值
+fn compute_ssim(a: &GrayImage, b: &GrayImage) -> f64 {
+    let (width, height) = a.dimensions();
+
+    // 图片小于窗口时退化为整图一个窗口
+    let window_w = SSIM_WINDOW.min(width);
+    let window_h = SSIM_WINDOW.min(height);
+
+    let mut total = 0.0;
+    let mut count = 0u64;
+
+    let mut y = 0;
+    while y + window_h <= height {
+        let mut x = 0;
+        while x + window_w <= width {
+            total += window_ssim(a, b, x, y, window_w, window_h);
+            count += 1;
+            x += SSIM_STRIDE;
+        }
+        y += SSIM_STRIDE;
+    }
+
+    if count == 0 {
+        1.0
+    } else {
+        total / count as f64
+    }
+}
+
+fn window_ssim(a: &GrayImage, b: &GrayImage, x0: u32, y0: u32, w: u32, h: u32) -> f64 {
+    let n = (w * h) as f64;
+    let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+
+    for y in y0..y0 + h {
+        for x in x0..x0 + w {
+            let pa = a.get_pixel(x, y)[0] as f64;
+            let pb = b.get_pixel(x, y)[0] as f64;
+            sum_a += pa;
+            sum_b += pb;
+            sum_aa += pa * pa;
+            sum_bb += pb * pb;
+            sum_ab += pa * pb;
+        }
+    }
+
+    let mean_a = sum_a / n;
+    let mean_b = sum_b / n;
+    let var_a = sum_aa / n - mean_a * mean_a;
+    let var_b = sum_bb / n - mean_b * mean_b;
+    let covariance = sum_ab / n - mean_a * mean_b;
+
+    ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+        / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2))
+}
+
+// 差异热力图: 无差异为深蓝,差异越大越接近红色
+fn render_heatmap(a: &RgbImage, b: &RgbImage) -> Result<String, String> {
+    let heatmap = RgbImage::from_fn(a.width(), a.height(), |x, y| {
+        let pa = a.get_pixel(x, y);
+        let pb = b.get_pixel(x, y);
+        let diff = (0..3)
+            .map(|c| (pa[c] as f64 - pb[c] as f64).abs())
+            .fold(0.0, f64::max);
+        heat_color((diff * HEATMAP_GAIN / 255.0).min(1.0))
+    });
+
+    let mut png_data = Vec::new();
+    DynamicImage::ImageRgb8(heatmap)
+        .write_to(&mut Cursor::new(&mut png_data), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode heatmap: {}", e))?;
+
+    Ok(general_purpose::STANDARD.encode(&png_data))
+}
+
+// 蓝 -> 青 -> 黄 -> 红 的渐变
+fn heat_color(t: f64) -> image::Rgb<u8> {
+    let stops: [(f64, [f64; 3]); 4] = [
+        (0.0, [0.0, 0.0, 64.0]),
+        (0.33, [0.0, 200.0, 255.0]),
+        (0.66, [255.0, 230.0, 0.0]),
+        (1.0, [255.0, 0.0, 0.0]),
+    ];
+
+    for pair in stops.windows(2) {
+        let (t0, c0) = pair[0];
+        let (t1, c1) = pair[1];
+        if t <= t1 {
+            let f = (t - t0) / (t1 - t0);
+            return image::Rgb([
+                (c0[0] + (c1[0] - c0[0]) * f) as u8,
+                (c0[1] + (c1[1] - c0[1]) * f) as u8,
+                (c0[2] + (c1[2] - c0[2]) * f) as u8,
+            ]);
+        }
+    }
+    image::Rgb([255, 0, 0])
+}
@@ -0,0 +1,544 @@
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage, Luma, Rgba, RgbaImage, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::image_edit;
+
+// 检测纸张边缘和文字倾斜时使用的缩略图最长边 (像素)
+const PAGE_DETECT_SIZE: u32 = 600;
+const SKEW_DETECT_SIZE: u32 = 1000;
+// 纸张至少占画面的比例,且轮廓填满四个角围成的四边形的比例
+const MIN_PAGE_AREA: f32 = 0.2;
+const MIN_QUAD_FILL: f32 = 0.85;
+// 四个角都在画面角落附近时不裁剪 (占边长的比例)
+const EDGE_MARGIN: f32 = 0.02;
+// 四个角向中心收缩的比例,避免边缘混入背景
+const PAGE_INSET: f32 = 0.005;
+// 倾斜角度的搜索范围和步长 (度),小于最小角度时不旋转
+const MAX_SKEW_DEGREES: f32 = 10.0;
+const SKEW_STEP: f32 = 0.5;
+const SKEW_FINE_STEP: f32 = 0.1;
+const MIN_SKEW_DEGREES: f32 = 0.2;
+// 文字像素占比超出这个范围时不是正常的文档页面,不做倾斜校正
+const SKEW_INK_RANGE: (f32, f32) = (0.001, 0.5);
+// 估计纸张亮度时沿长边划分的块数
+const BACKGROUND_BLOCKS: u32 = 100;
+// 纸张亮度的下限,避免暗处的噪点被过度放大
+const MIN_BACKGROUND: f32 = 64.0;
+// 最暗的 1% 像素映射为纯黑
+const BLACK_PERCENTILE: f32 = 0.01;
+// 黑白阈值的范围,几乎没有文字的页面上 Otsu 阈值会落在纸张的噪点中
+const THRESHOLD_RANGE: (u8, u8) = (64, 200);
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+// 文档扫描预处理: 先校正几何,再处理颜色
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    // 检测纸张边缘,裁掉背景并校正透视
+    pub auto_crop: bool,
+    // 按文字行方向校正倾斜
+    pub deskew: bool,
+    pub color_mode: ScanColorMode,
+    // 去除光照不均并拉伸亮度,纸张变白、文字变黑。黑白模式始终启用
+    pub enhance_contrast: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            auto_crop: true,
+            deskew: true,
+            color_mode: ScanColorMode::default(),
+            enhance_contrast: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanColorMode {
+    Color,
+    #[default]
+    Grayscale,
+    // 纯黑白,嵌入 PDF 时按 1 位深度保存
+    BlackWhite,
+}
+
+pub fn process_scan(img: &DynamicImage, options: &ScanOptions) -> DynamicImage {
+    let mut page = img.to_rgba8();
+
+    if options.auto_crop {
+        if let Some(corners) = detect_page(&page) {
+            if let Some(warped) = warp_perspective(&page, corners) {
+                println!("裁剪到纸张边缘: {}x{}", warped.width(), warped.height());
+                page = warped;
+            }
+        }
+    }
+
+    if options.deskew {
+        let angle = detect_skew(&page);
+        if angle.abs() >= MIN_SKEW_DEGREES {
+            println!("校正倾斜: {:.1}°", angle);
+            page = image_edit::rotate_arbitrary(&page, -angle, WHITE);
+        }
+    }
+
+    match options.color_mode {
+        ScanColorMode::Color if options.enhance_contrast => DynamicImage::ImageRgb8(enhance_color(&page)),
+        ScanColorMode::Color => DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(page).to_rgb8()),
+        ScanColorMode::Grayscale if options.enhance_contrast => {
+            DynamicImage::ImageLuma8(enhance_gray(&imageops::grayscale(&page)))
+        }
+        ScanColorMode::Grayscale => DynamicImage::ImageLuma8(imageops::grayscale(&page)),
+        ScanColorMode::BlackWhite => DynamicImage::ImageLuma8(black_white(&imageops::grayscale(&page))),
+    }
+}
+
+// 缩小后的灰度图,以及原图与缩略图的尺寸比例
+fn thumbnail(img: &RgbaImage, max_size: u32) -> (GrayImage, f32, f32) {
+    let (width, height) = img.dimensions();
+    let scale = (max_size as f32 / width.max(height) as f32).min(1.0);
+    let small_width = ((width as f32 * scale).round() as u32).max(1);
+    let small_height = ((height as f32 * scale).round() as u32).max(1);
+    let small = imageops::resize(&imageops::grayscale(img), small_width, small_height, FilterType::Triangle);
+    (small, width as f32 / small_width as f32, height as f32 / small_height as f32)
+}
+
+// 检测纸张的四个角 (左上、右上、右下、左下,原图坐标)。
+// 纸张通常比背景亮: 按 Otsu 阈值二值化后取最大的亮色连通区域,用坐标和与差的极值确定四个角
+fn detect_page(img: &RgbaImage) -> Option<[(f32, f32); 4]> {
+    let (small, scale_x, scale_y) = thumbnail(img, PAGE_DETECT_SIZE);
+    let small = imageops::blur(&small, 1.5);
+    let (width, height) = small.dimensions();
+    let threshold = otsu_threshold(&histogram(small.pixels().map(|p| p[0])));
+
+    let bright: Vec<bool> = small.pixels().map(|p| p[0] > threshold).collect();
+    let mut labels = vec![0u32; bright.len()];
+    let mut best: Option<(u32, Vec<usize>)> = None;
+    let mut label = 0;
+    for start in 0..bright.len() {
+        if !bright[start] || labels[start] != 0 {
+            continue;
+        }
+        label += 1;
+        let region = flood_fill(width, height, [start], |i| {
+            let enter = bright[i] && labels[i] == 0;
+            if enter {
+                labels[i] = label;
+            }
+            enter
+        });
+        if best.as_ref().map(|(_, best)| region.len() > best.len()).unwrap_or(true) {
+            best = Some((label, region));
+        }
+    }
+    let (page_label, region) = best?;
+
+    // 纸张上的文字和图片在二值图中是空洞: 从画面边缘填充背景,剩下的都属于纸张
+    let border = (0..width)
+        .flat_map(|x| [x, (height - 1) * width + x])
+        .chain((0..height).flat_map(|y| [y * width, y * width + width - 1]))
+        .map(|i| i as usize);
+    let mut outside = vec![false; bright.len()];
+    let outside_count = flood_fill(width, height, border, |i| {
+        let enter = labels[i] != page_label && !outside[i];
+        outside[i] = true;
+        enter
+    })
+    .len();
+    let page_area = (bright.len() - outside_count) as f32;
+
+    // 左上 (x+y 最小)、右上 (x-y 最大)、右下 (x+y 最大)、左下 (x-y 最小)
+    let points: Vec<(f32, f32)> =
+        region.iter().map(|&i| ((i as u32 % width) as f32 + 0.5, (i as u32 / width) as f32 + 0.5)).collect();
+    let extreme = |key: fn(&(f32, f32)) -> f32| points.iter().copied().max_by(|a, b| key(a).total_cmp(&key(b)));
+    let corners = [
+        extreme(|p| -(p.0 + p.1))?,
+        extreme(|p| p.0 - p.1)?,
+        extreme(|p| p.0 + p.1)?,
+        extreme(|p| p.1 - p.0)?,
+    ];
+    let image_area = (width * height) as f32;
+    let quad_area = polygon_area(&corners);
+    if page_area < MIN_PAGE_AREA * image_area || page_area < MIN_QUAD_FILL * quad_area || !is_convex(&corners) {
+        return None;
+    }
+
+    // 纸张占满画面时无需裁剪
+    let image_corners = [(0.0, 0.0), (width as f32, 0.0), (width as f32, height as f32), (0.0, height as f32)];
+    let margin_x = width as f32 * EDGE_MARGIN + 1.0;
+    let margin_y = height as f32 * EDGE_MARGIN + 1.0;
+    let near_corner = |(corner, image_corner): (&(f32, f32), (f32, f32))| {
+        (corner.0 - image_corner.0).abs() <= margin_x && (corner.1 - image_corner.1).abs() <= margin_y
+    };
+    if corners.iter().zip(image_corners).all(near_corner) {
+        return None;
+    }
+
+    let center_x = corners.iter().map(|c| c.0).sum::<f32>() / 4.0;
+    let center_y = corners.iter().map(|c| c.1).sum::<f32>() / 4.0;
+    Some(corners.map(|(x, y)| {
+        let x = x + (center_x - x) * PAGE_INSET;
+        let y = y + (center_y - y) * PAGE_INSET;
+        (x * scale_x, y * scale_y)
+    }))
+}
+
+// 从起点按 4 邻域扩展,enter 决定像素能否加入 (并负责标记已访问),返回加入的像素
+fn flood_fill(
+    width: u32,
+    height: u32,
+    starts: impl IntoIterator<Item = usize>,
+    mut enter: impl FnMut(usize) -> bool,
+) -> Vec<usize> {
+    let width = width as usize;
+    let len = width * height as usize;
+    let mut queue: VecDeque<usize> = starts.into_iter().filter(|&i| enter(i)).collect();
+    let mut region = Vec::new();
+    while let Some(index) = queue.pop_front() {
+        region.push(index);
+        let x = index % width;
+        let neighbors = [
+            (x > 0).then(|| index - 1),
+            (x + 1 < width).then_some(index + 1),
+            index.checked_sub(width),
+            Some(index + width).filter(|&i| i < len),
+        ];
+        for neighbor in neighbors.into_iter().flatten() {
+            if enter(neighbor) {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    region
+}
+
+fn polygon_area(points: &[(f32, f32); 4]) -> f32 {
+    let mut sum = 0.0;
+    for i in 0..4 {
+        let (x1, y1) = points[i];
+        let (x2, y2) = points[(i + 1) % 4];
+        sum += x1 * y2 - x2 * y1;
+    }
+    sum.abs() / 2.0
+}
+
+fn is_convex(points: &[(f32, f32); 4]) -> bool {
+    let cross = |i: usize| {
+        let (ax, ay) = points[i];
+        let (bx, by) = points[(i + 1) % 4];
+        let (cx, cy) = points[(i + 2) % 4];
+        (bx - ax) * (cy - by) - (by - ay) * (cx - bx)
+    };
+    (0..4).all(|i| cross(i) > 0.0) || (0..4).all(|i| cross(i) < 0.0)
+}
+
+// 把四个角围成的区域拉伸为矩形,宽高取对边中较长的一条
+fn warp_perspective(img: &RgbaImage, corners: [(f32, f32); 4]) -> Option<RgbaImage> {
+    let distance = |a: (f32, f32), b: (f32, f32)| ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt();
+    let [top_left, top_right, bottom_right, bottom_left] = corners;
+    let width = distance(top_left, top_right).max(distance(bottom_left, bottom_right)).round().max(1.0);
+    let height = distance(top_left, bottom_left).max(distance(top_right, bottom_right)).round().max(1.0);
+
+    let rectangle = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)];
+    let h = homography(rectangle, corners)?;
+
+    Some(RgbaImage::from_fn(width as u32, height as u32, |x, y| {
+        // 以像素中心为采样点
+        let (u, v) = (x as f64 + 0.5, y as f64 + 0.5);
+        let w = h[6] * u + h[7] * v + 1.0;
+        let sx = (h[0] * u + h[1] * v + h[2]) / w - 0.5;
+        let sy = (h[3] * u + h[4] * v + h[5]) / w - 0.5;
+        image_edit::sample_bilinear(img, sx as f32, sy as f32, WHITE)
+    }))
+}
+
+// 求把 from 中四个点映射到 to 的透视变换 (h8 = 1),高斯消元解 8 元线性方程组
+fn homography(from: [(f32, f32); 4], to: [(f32, f32); 4]) -> Option<[f64; 8]> {
+    let mut rows = [[0.0f64; 9]; 8];
+    for (i, ((u, v), (x, y))) in from.iter().zip(to.iter()).enumerate() {
+        let (u, v, x, y) = (*u as f64, *v as f64, *x as f64, *y as f64);
+        rows[2 * i] = [u, v, 1.0, 0.0, 0.0, 0.0, -u * x, -v * x, x];
+        rows[2 * i + 1] = [0.0, 0.0, 0.0, u, v, 1.0, -u * y, -v * y, y];
+    }
+
+    for column in 0..8 {
+        let pivot = (column..8).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))?;
+        if rows[pivot][column].abs() < 1e-9 {
+            return None;
+        }
+        rows.swap(column, pivot);
+        for row in 0..8 {
+            if row == column {
+                continue;
+            }
+            let factor = rows[row][column] / rows[column][column];
+            let pivot_row = rows[column];
+            for (value, pivot_value) in rows[row].iter_mut().zip(pivot_row).skip(column) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+
+    let mut h = [0.0; 8];
+    for (i, value) in h.iter_mut().enumerate() {
+        *value = rows[i][8] / rows[i][i];
+    }
+    Some(h)
+}
+
+// 文字行相对水平方向顺时针倾斜的角度 (度)。
+// 投影法: 把深色像素按候选角度投影到纵轴,文字行对齐时各行计数的平方和最大
+fn detect_skew(img: &RgbaImage) -> f32 {
+    let (small, _, _) = thumbnail(img, SKEW_DETECT_SIZE);
+    let (width, height) = small.dimensions();
+    let threshold = otsu_threshold(&histogram(small.pixels().map(|p| p[0])));
+
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let ink: Vec<(f32, f32)> = small
+        .enumerate_pixels()
+        .filter(|(_, _, p)| p[0] <= threshold)
+        .map(|(x, y, _)| (x as f32 - cx, y as f32 - cy))
+        .collect();
+    let ratio = ink.len() as f32 / (width * height) as f32;
+    if ratio < SKEW_INK_RANGE.0 || ratio > SKEW_INK_RANGE.1 {
+        return 0.0;
+    }
+
+    let offset = cx.hypot(cy).ceil() as usize + 1;
+    let mut rows = vec![0u32; offset * 2 + 1];
+    let mut score = |degrees: f32| -> u64 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        rows.iter_mut().for_each(|count| *count = 0);
+        for &(x, y) in &ink {
+            let row = (y * cos - x * sin).round() as i64 + offset as i64;
+            rows[row as usize] += 1;
+        }
+        rows.iter().map(|&count| count as u64 * count as u64).sum()
+    };
+
+    let search = |score: &mut dyn FnMut(f32) -> u64, center: f32, range: f32, step: f32| {
+        let steps = (range / step).round() as i32;
+        (-steps..=steps)
+            .map(|i| center + i as f32 * step)
+            .map(|angle| (score(angle), angle))
+            .max_by(|a, b| a.0.cmp(&b.0).then(b.1.abs().total_cmp(&a.1.abs())))
+            .map(|(_, angle)| angle)
+            .unwrap_or(0.0)
+    };
+    let coarse = search(&mut score, 0.0, MAX_SKEW_DEGREES, SKEW_STEP);
+    search(&mut score, coarse, SKEW_STEP, SKEW_FINE_STEP)
+}
+
+fn histogram(values: impl Iterator<Item = u8>) -> [u64; 256] {
+    let mut histogram = [0u64; 256];
+    for value in values {
+        histogram[value as usize] += 1;
+    }
+    histogram
+}
+
+// Otsu 阈值: 使两类像素的类间方差最大,小于等于阈值的为深色
+fn otsu_threshold(histogram: &[u64; 256]) -> u8 {
+    let total: u64 = histogram.iter().sum();
+    let sum: f64 = histogram.iter().enumerate().map(|(i, &count)| i as f64 * count as f64).sum();
+
+    let mut best = (0.0, 127u8);
+    let mut dark_count = 0u64;
+    let mut dark_sum = 0.0;
+    for (level, &count) in histogram.iter().enumerate() {
+        dark_count += count;
+        dark_sum += level as f64 * count as f64;
+        let light_count = total - dark_count;
+        if dark_count == 0 || light_count == 0 {
+            continue;
+        }
+        let dark_mean = dark_sum / dark_count as f64;
+        let light_mean = (sum - dark_sum) / light_count as f64;
+        let variance = dark_count as f64 * light_count as f64 * (dark_mean - light_mean).powi(2);
+        if variance > best.0 {
+            best = (variance, level as u8);
+        }
+    }
+    best.1
+}
+
+// 估计每个像素处的纸张亮度: 分块取最亮值,扩展覆盖较大的字和深色区域,再平滑放大到原尺寸
+fn background(gray: &GrayImage) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let block = (width.max(height) / BACKGROUND_BLOCKS).max(1);
+    let mut blocks = GrayImage::new(width.div_ceil(block), height.div_ceil(block));
+    for (x, y, pixel) in gray.enumerate_pixels() {
+        let block_pixel = blocks.get_pixel_mut(x / block, y / block);
+        block_pixel[0] = block_pixel[0].max(pixel[0]);
+    }
+
+    for _ in 0..2 {
+        let source = blocks.clone();
+        let (block_width, block_height) = source.dimensions();
+        for (x, y, pixel) in blocks.enumerate_pixels_mut() {
+            for ny in y.saturating_sub(1)..(y + 2).min(block_height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(block_width) {
+                    pixel[0] = pixel[0].max(source.get_pixel(nx, ny)[0]);
+                }
+            }
+        }
+    }
+
+    let blocks = imageops::blur(&blocks, 1.0);
+    imageops::resize(&blocks, width, height, FilterType::Triangle)
+}
+
+// 除以纸张亮度去除阴影和光照不均,返回每个像素的增益
+fn background_gains(gray: &GrayImage) -> Vec<f32> {
+    background(gray).pixels().map(|p| 255.0 / (p[0] as f32).max(MIN_BACKGROUND)).collect()
+}
+
+// 最暗 1% 像素的亮度作为黑点
+fn black_point(values: impl Iterator<Item = u8>) -> f32 {
+    let histogram = histogram(values);
+    let total: u64 = histogram.iter().sum();
+    let target = (total as f32 * BLACK_PERCENTILE) as u64;
+    let mut count = 0;
+    for (level, &n) in histogram.iter().enumerate() {
+        count += n;
+        if count > target {
+            return (level as f32).min(128.0);
+        }
+    }
+    0.0
+}
+
+fn stretch(value: f32, black: f32) -> u8 {
+    ((value - black) * 255.0 / (255.0 - black)).round().clamp(0.0, 255.0) as u8
+}
+
+fn normalized_gray(gray: &GrayImage) -> GrayImage {
+    let gains = background_gains(gray);
+    let mut normalized = gray.clone();
+    for (pixel, gain) in normalized.pixels_mut().zip(&gains) {
+        pixel[0] = (pixel[0] as f32 * gain).round().min(255.0) as u8;
+    }
+    normalized
+}
+
+fn enhance_gray(gray: &GrayImage) -> GrayImage {
+    let mut normalized = normalized_gray(gray);
+    let black = black_point(normalized.pixels().map(|p| p[0]));
+    for pixel in normalized.pixels_mut() {
+        pixel[0] = stretch(pixel[0] as f32, black);
+    }
+    normalized
+}
+
+// 彩色图的三个通道使用同一增益,保持色相
+fn enhance_color(img: &RgbaImage) -> RgbImage {
+    let gray = imageops::grayscale(img);
+    let gains = background_gains(&gray);
+    let black = black_point(gray.pixels().zip(&gains).map(|(p, gain)| (p[0] as f32 * gain).min(255.0) as u8));
+
+    let mut output = RgbImage::new(img.width(), img.height());
+    for ((pixel, source), gain) in output.pixels_mut().zip(img.pixels()).zip(&gains) {
+        for channel in 0..3 {
+            pixel[channel] = stretch((source[channel] as f32 * gain).min(255.0), black);
+        }
+    }
+    output
+}
+
+// 去除光照不均后按 Otsu 阈值二值化
+fn black_white(gray: &GrayImage) -> GrayImage {
+    let normalized = normalized_gray(gray);
+    let threshold = otsu_threshold(&histogram(normalized.pixels().map(|p| p[0])))
+        .clamp(THRESHOLD_RANGE.0, THRESHOLD_RANGE.1);
+    GrayImage::from_fn(normalized.width(), normalized.height(), |x, y| {
+        if normalized.get_pixel(x, y)[0] <= threshold {
+            Luma([0])
+        } else {
+            Luma([255])
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(h: &[f64; 8], (u, v): (f32, f32)) -> (f32, f32) {
+        let (u, v) = (u as f64, v as f64);
+        let w = h[6] * u + h[7] * v + 1.0;
+        (((h[0] * u + h[1] * v + h[2]) / w) as f32, ((h[3] * u + h[4] * v + h[5]) / w) as f32)
+    }
+
+    fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+        let distance = (actual.0 - expected.0).hypot(actual.1 - expected.1);
+        assert!(distance < 1e-2, "{:?} != {:?}", actual, expected);
+    }
+
+    // 两条对角线的交点
+    fn diagonal_intersection([a, b, c, d]: [(f32, f32); 4]) -> (f32, f32) {
+        let (r, s) = ((c.0 - a.0, c.1 - a.1), (d.0 - b.0, d.1 - b.1));
+        let t = ((b.0 - a.0) * s.1 - (b.1 - a.1) * s.0) / (r.0 * s.1 - r.1 * s.0);
+        (a.0 + t * r.0, a.1 + t * r.1)
+    }
+
+    // 文字行: 每 30 像素一行、4 像素高的虚线,按 tan(角度) 向下倾斜
+    fn text_page(degrees: f32) -> RgbaImage {
+        let slope = degrees.to_radians().tan();
+        RgbaImage::from_fn(800, 600, |x, y| {
+            let row = y as f32 - x as f32 * slope;
+            let on_line = row.rem_euclid(30.0) < 4.0;
+            let in_word = x % 40 < 32;
+            if on_line && in_word && (50..750).contains(&x) {
+                Rgba([20, 20, 20, 255])
+            } else {
+                WHITE
+            }
+        })
+    }
+
+    #[test]
+    fn homography_maps_quadrilateral_to_rectangle() {
+        let quad = [(112.0, 80.0), (530.0, 95.0), (590.0, 640.0), (60.0, 600.0)];
+        let rectangle = [(0.0, 0.0), (400.0, 0.0), (400.0, 500.0), (0.0, 500.0)];
+        let h = homography(quad, rectangle).unwrap();
+
+        for (corner, expected) in quad.into_iter().zip(rectangle) {
+            assert_near(apply(&h, corner), expected);
+        }
+        // 透视变换保持直线,对角线交点落在矩形中心
+        assert_near(apply(&h, diagonal_intersection(quad)), (200.0, 250.0));
+    }
+
+    #[test]
+    fn homography_rejects_degenerate_points() {
+        let rectangle = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        assert!(homography([(5.0, 5.0); 4], rectangle).is_none());
+    }
+
+    #[test]
+    fn otsu_threshold_separates_two_peaks() {
+        let mut histogram = [0u64; 256];
+        histogram[30..=50].fill(100);
+        histogram[190..=230].fill(300);
+        let threshold = otsu_threshold(&histogram);
+        assert!((50..190).contains(&threshold), "threshold {}", threshold);
+
+        // 没有第二个峰时不分割
+        let mut flat = [0u64; 256];
+        flat[128] = 1000;
+        assert_eq!(otsu_threshold(&flat), 127);
+    }
+
+    #[test]
+    fn detect_skew_recovers_line_angle() {
+        for degrees in [3.0, -4.5, 0.0] {
+            let angle = detect_skew(&text_page(degrees));
+            assert!((angle - degrees).abs() <= 0.15, "expected {}, detected {}", degrees, angle);
+        }
+    }
+}
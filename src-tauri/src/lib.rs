@@ -1,13 +1,37 @@
 use std::thread;
 use std::time::Duration;
 use tauri::{AppHandle, Manager, Emitter};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_global_shortcut::{
     Code, GlobalShortcutExt as _, Modifiers, Shortcut, ShortcutState,
 };
 
 mod apps;
+mod font_subset;
 mod image_compress;
+mod image_data_uri;
+mod image_dedup;
+mod image_edit;
+mod image_palette;
+mod image_quality;
+mod image_scan;
+mod pdf_archive;
+mod pdf_extract;
+mod pdf_fonts;
+mod pdf_form;
 mod pdf_generator;
+mod pdf_image;
+mod pdf_info;
+mod pdf_markdown;
+mod pdf_merge;
+mod pdf_optimize;
+mod pdf_overlay;
+mod pdf_pages;
+mod pdf_security;
+mod pdf_sign;
+mod pdf_text;
+mod pdf_toc;
+mod pdf_utils;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -29,12 +53,13 @@ fn launch_app(path: String) -> Result<(), String> {
 async fn compress_image(
     path: String, 
     max_size_kb: u32,
+    with_heatmap: Option<bool>,
     app: AppHandle,
     image_id: String
 ) -> Result<image_compress::CompressResult, String> {
     // 在单独的线程中执行压缩,避免阻塞主线程
     tokio::task::spawn_blocking(move || {
-        image_compress::compress_image(&path, max_size_kb, |progress| {
+        image_compress::compress_image(&path, max_size_kb, with_heatmap.unwrap_or(false), |progress| {
             // 发送进度事件到前端
             let _ = app.emit(&format!("compress-progress-{}", image_id), progress);
         })
@@ -43,6 +68,98 @@ async fn compress_image(
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+#[tauri::command]
+async fn compress_transformed_image(
+    path: String,
+    operations: Vec<image_edit::TransformOp>,
+    max_size_kb: u32,
+    with_heatmap: Option<bool>,
+    app: AppHandle,
+    image_id: String
+) -> Result<image_compress::CompressResult, String> {
+    tokio::task::spawn_blocking(move || {
+        image_compress::compress_transformed_image(&path, &operations, max_size_kb, with_heatmap.unwrap_or(false), |progress| {
+            let _ = app.emit(&format!("compress-progress-{}", image_id), progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn edit_image(
+    path: String,
+    operations: Vec<image_edit::TransformOp>,
+    output_path: String
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        image_edit::edit_image(&path, &operations, &output_path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn find_duplicate_images(
+    dir: String,
+    algorithm: image_dedup::HashAlgorithm,
+    threshold: u32,
+    recursive: bool,
+    app: AppHandle
+) -> Result<image_dedup::DuplicateScanResult, String> {
+    tokio::task::spawn_blocking(move || {
+        image_dedup::find_duplicate_images(&dir, algorithm, threshold, recursive, |progress| {
+            let _ = app.emit("duplicate-scan-progress", progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn extract_palette(path: String, count: u32) -> Result<Vec<image_palette::PaletteColor>, String> {
+    tokio::task::spawn_blocking(move || {
+        image_palette::extract_palette(&path, count)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn export_palette(
+    colors: Vec<image_palette::PaletteColor>,
+    format: image_palette::PaletteExportFormat,
+    output_path: String
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        image_palette::export_palette(&colors, format, &output_path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn image_to_data_uri(
+    path: String,
+    options: Option<image_data_uri::DataUriOptions>,
+    copy_to_clipboard: bool,
+    app: AppHandle
+) -> Result<image_data_uri::DataUriResult, String> {
+    let result = tokio::task::spawn_blocking(move || {
+        image_data_uri::image_to_data_uri(&path, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+
+    if copy_to_clipboard {
+        app.clipboard()
+            .write_text(result.text.clone())
+            .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+    }
+
+    Ok(result)
+}
+
 #[tauri::command]
 fn get_cpu_count() -> usize {
     num_cpus::get()
@@ -94,9 +211,173 @@ async fn save_images_as_zip(images: Vec<image_compress::ImageData>, path: String
 }
 
 #[tauri::command]
-async fn generate_pdf(text: String, image_paths: Vec<String>, output_path: String) -> Result<(), String> {
+async fn generate_pdf(
+    text: String,
+    text_format: Option<pdf_generator::TextFormat>,
+    base_dir: Option<String>,
+    image_paths: Vec<pdf_generator::PdfImageInput>,
+    options: Option<pdf_generator::PdfOptions>,
+    output_path: String,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_generator::generate_pdf(
+            &text,
+            text_format.unwrap_or_default(),
+            base_dir.as_deref(),
+            image_paths,
+            &options.unwrap_or_default(),
+            &output_path,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn list_pdf_fonts() -> Result<Vec<String>, String> {
+    tokio::task::spawn_blocking(pdf_fonts::list_font_families)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+}
+
+#[tauri::command]
+async fn merge_pdfs(
+    inputs: Vec<pdf_generator::PdfImageInput>,
+    encryption: Option<pdf_security::PdfEncryption>,
+    output_path: String,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_merge::merge_pdfs(inputs, encryption.as_ref(), &output_path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn decrypt_pdf(path: String, password: String, output_path: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_security::decrypt_pdf(&path, &password, &output_path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn optimize_pdf(
+    path: String,
+    output_path: String,
+    options: Option<pdf_optimize::PdfOptimizeOptions>,
+    app: AppHandle,
+    task_id: String
+) -> Result<pdf_optimize::PdfOptimizeResult, String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_optimize::optimize_pdf(&path, &output_path, &options.unwrap_or_default(), |progress| {
+            let _ = app.emit(&format!("pdf-optimize-progress-{}", task_id), progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn extract_pdf_text(path: String, output_path: Option<String>) -> Result<Vec<String>, String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_extract::extract_text(&path, output_path.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn extract_pdf_images(path: String, output_dir: String) -> Result<Vec<String>, String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_extract::extract_images(&path, &output_dir)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn get_pdf_info(path: String) -> Result<pdf_info::PdfInfo, String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_info::pdf_info(&path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn split_pdf(path: String, mode: pdf_pages::SplitMode, output_dir: String) -> Result<Vec<String>, String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_pages::split_pdf(&path, &mode, &output_dir)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn extract_pdf_pages(path: String, pages: String, output_path: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_pages::extract_pages(&path, &pages, &output_path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn reorder_pdf_pages(path: String, order: Vec<u32>, output_path: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_pages::reorder_pages(&path, &order, &output_path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn delete_pdf_pages(path: String, pages: String, output_path: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_pages::delete_pages(&path, &pages, &output_path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn rotate_pdf_pages(path: String, rotations: Vec<pdf_pages::PageRotation>, output_path: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_pages::rotate_pages(&path, &rotations, &output_path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn list_pdf_form_fields(path: String) -> Result<Vec<pdf_form::FormField>, String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_form::list_form_fields(&path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn fill_pdf_form(
+    path: String,
+    values: serde_json::Map<String, serde_json::Value>,
+    flatten: Option<bool>,
+    output_path: String,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        pdf_form::fill_form(&path, &values, flatten.unwrap_or(false), &output_path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+async fn sign_pdf(path: String, options: pdf_sign::SignatureOptions, output_path: String) -> Result<(), String> {
     tokio::task::spawn_blocking(move || {
-        pdf_generator::generate_pdf(&text, image_paths, &output_path)
+        pdf_sign::sign_pdf(&path, &options, &output_path)
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
@@ -179,6 +460,7 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .on_tray_icon_event(|app, event| match event {
             tauri::tray::TrayIconEvent::DoubleClick { .. } => {
@@ -209,13 +491,34 @@ pub fn run() {
             get_installed_apps,
             launch_app,
             compress_image,
+            compress_transformed_image,
+            edit_image,
+            find_duplicate_images,
+            extract_palette,
+            export_palette,
+            image_to_data_uri,
             save_compressed_image,
             save_temp_image,
             read_file_as_base64,
             get_file_size,
             save_images_as_zip,
             get_cpu_count,
-            generate_pdf
+            generate_pdf,
+            list_pdf_fonts,
+            merge_pdfs,
+            decrypt_pdf,
+            optimize_pdf,
+            extract_pdf_text,
+            extract_pdf_images,
+            get_pdf_info,
+            split_pdf,
+            extract_pdf_pages,
+            reorder_pdf_pages,
+            delete_pdf_pages,
+            rotate_pdf_pages,
+            list_pdf_form_fields,
+            fill_pdf_form,
+            sign_pdf
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,479 @@
+use printpdf::lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use std::fs;
+
+use crate::pdf_optimize;
+use crate::pdf_security;
+use crate::pdf_utils::{self, resolve_dict};
+
+// PDF/A-2 基于 PDF 1.7
+const PDFA_VERSION: &str = "1.7";
+const SRGB_NAME: &str = "sRGB IEC61966-2.1";
+
+// PDF/A 不允许的动作类型 (ISO 19005-2 6.5.1)
+const FORBIDDEN_ACTIONS: &[&[u8]] = &[
+    b"Launch",
+    b"Sound",
+    b"Movie",
+    b"ResetForm",
+    b"ImportData",
+    b"JavaScript",
+    b"Hide",
+    b"SetOCGState",
+    b"Rendition",
+    b"Trans",
+    b"GoTo3DView",
+];
+
+// 注释标志: 不可见、隐藏、不显示、切换不显示; 打印
+const ANNOT_HIDDEN_FLAGS: i64 = 1 | 2 | 32 | 256;
+const ANNOT_PRINT_FLAG: i64 = 4;
+
+// 保留到 PDF/A 文档信息字典中的条目,都有对应的 XMP 属性
+const INFO_KEYS: [&str; 6] = ["Title", "Author", "Subject", "Keywords", "Creator", "Producer"];
+
+// sRGB 的 D50 白点和经 Bradford 适配到 D50 的三原色
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+const SRGB_RED: [f64; 3] = [0.4361, 0.2225, 0.0139];
+const SRGB_GREEN: [f64; 3] = [0.3851, 0.7169, 0.0971];
+const SRGB_BLUE: [f64; 3] = [0.1431, 0.0606, 0.7141];
+// 色调曲线的采样点数
+const TRC_POINTS: usize = 1024;
+
+// 把生成的文档转为 PDF/A-2b: 写入 sRGB 输出意图和 XMP 元数据,去掉 PDF/A 不允许的内容。
+// 字体必须已全部嵌入,文档不能加密
+pub fn convert_to_pdfa(doc: &mut Document) -> Result<(), String> {
+    if doc.trailer.get(b"Encrypt").is_ok() {
+        return Err("PDF/A does not allow encryption".to_string());
+    }
+    doc.version = PDFA_VERSION.to_string();
+
+    remove_forbidden_features(doc);
+    let info = archive_info(doc)?;
+    let metadata_id = doc.add_object(xmp_metadata(&info));
+    let output_intent = srgb_output_intent(doc)?;
+
+    let catalog = doc.catalog_mut().map_err(|e| format!("Failed to read catalog: {}", e))?;
+    catalog.set("Metadata", metadata_id);
+    catalog.set("OutputIntents", vec![Object::Dictionary(output_intent)]);
+
+    // 文件标识是必需的
+    if doc.trailer.get(b"ID").is_err() {
+        let id = Object::String(pdf_security::random_bytes(16)?, StringFormat::Hexadecimal);
+        doc.trailer.set("ID", vec![id.clone(), id]);
+    }
+    Ok(())
+}
+
+// lopdf 写出的文件头后没有二进制注释行,PDF/A 要求有,改用自己的序列化输出
+pub fn save_pdfa(doc: &Document, output_path: &str) -> Result<(), String> {
+    let bytes = pdf_optimize::write_compact(doc)?;
+    fs::write(output_path, bytes).map_err(|e| format!("Failed to save PDF: {}", e))
+}
+
+// 文档属性,键为信息字典中的名称
+struct ArchiveInfo {
+    fields: Vec<(&'static str, String)>,
+    // XMP 格式的当前时间,信息字典中的日期与之一致
+    date: String,
+}
+
+impl ArchiveInfo {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.fields.iter().find(|(k, _)| *k == key).map(|(_, value)| value.as_str())
+    }
+}
+
+// 只保留有 XMP 对应属性的文档信息,并重写创建和修改时间
+fn archive_info(doc: &mut Document) -> Result<ArchiveInfo, String> {
+    let now = chrono::Local::now();
+    let offset = now.format("%z").to_string();
+    let pdf_date = format!("D:{}{}'{}'", now.format("%Y%m%d%H%M%S"), &offset[..3], &offset[3..]);
+    let date = now.format("%Y-%m-%dT%H:%M:%S%:z").to_string();
+
+    let old_info = doc
+        .trailer
+        .get(b"Info")
+        .ok()
+        .and_then(|info| resolve_dict(doc, info))
+        .cloned()
+        .unwrap_or_default();
+    let mut fields = Vec::new();
+    let mut info = Dictionary::new();
+    for key in INFO_KEYS {
+        let Some(value) = old_info.get(key.as_bytes()).ok().and_then(|v| v.as_str().ok()) else {
+            continue;
+        };
+        let text = pdf_utils::decode_text_string(value);
+        if text.trim().is_empty() {
+            continue;
+        }
+        info.set(key, pdf_utils::text_string(&text));
+        fields.push((key, text));
+    }
+    info.set("CreationDate", Object::string_literal(pdf_date.clone()));
+    info.set("ModDate", Object::string_literal(pdf_date));
+
+    match doc.trailer.get(b"Info").and_then(Object::as_reference) {
+        Ok(info_id) => {
+            doc.objects.insert(info_id, Object::Dictionary(info));
+        }
+        Err(_) => {
+            let info_id = doc.add_object(info);
+            doc.trailer.set("Info", info_id);
+        }
+    }
+    Ok(ArchiveInfo { fields, date })
+}
+
+// 与文档信息一致的 XMP 元数据,元数据流不能压缩
+fn xmp_metadata(info: &ArchiveInfo) -> Stream {
+    let mut properties = String::new();
+    properties.push_str("<pdfaid:part>2</pdfaid:part>\n<pdfaid:conformance>B</pdfaid:conformance>\n");
+    properties.push_str("<dc:format>application/pdf</dc:format>\n");
+    if let Some(title) = info.get("Title") {
+        properties.push_str(&format!(
+            "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>\n",
+            xml_escape(title)
+        ));
+    }
+    if let Some(author) = info.get("Author") {
+        properties.push_str(&format!(
+            "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n",
+            xml_escape(author)
+        ));
+    }
+    if let Some(subject) = info.get("Subject") {
+        properties.push_str(&format!(
+            "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>\n",
+            xml_escape(subject)
+        ));
+    }
+    for (key, property) in [("Keywords", "pdf:Keywords"), ("Producer", "pdf:Producer"), ("Creator", "xmp:CreatorTool")] {
+        if let Some(value) = info.get(key) {
+            properties.push_str(&format!("<{0}>{1}</{0}>\n", property, xml_escape(value)));
+        }
+    }
+    for property in ["xmp:CreateDate", "xmp:ModifyDate", "xmp:MetadataDate"] {
+        properties.push_str(&format!("<{0}>{1}</{0}>\n", property, info.date));
+    }
+
+    let xmp = format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+         <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n\
+         <rdf:Description rdf:about=\"\" \
+         xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+         xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\" \
+         xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">\n\
+         {}</rdf:Description>\n\
+         </rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>",
+        properties
+    );
+    Stream::new(dictionary! { "Type" => "Metadata", "Subtype" => "XML" }, xmp.into_bytes()).with_compression(false)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// 以 sRGB 为目标的 PDF/A 输出意图
+fn srgb_output_intent(doc: &mut Document) -> Result<Dictionary, String> {
+    let mut profile = Stream::new(dictionary! { "N" => 3 }, srgb_icc_profile());
+    profile
+        .compress()
+        .map_err(|e| format!("Failed to compress ICC profile: {}", e))?;
+    let profile_id = doc.add_object(profile);
+
+    Ok(dictionary! {
+        "Type" => "OutputIntent",
+        "S" => "GTS_PDFA1",
+        "OutputConditionIdentifier" => Object::string_literal(SRGB_NAME),
+        "RegistryName" => Object::string_literal("http://www.color.org"),
+        "Info" => Object::string_literal(SRGB_NAME),
+        "DestOutputProfile" => profile_id,
+    })
+}
+
+// 按 ICC v2 生成 sRGB 显示器配置文件 (矩阵/TRC 模型)
+fn srgb_icc_profile() -> Vec<u8> {
+    let curve = curve_tag();
+    let tags: [(&[u8; 4], Vec<u8>); 9] = [
+        (b"desc", text_description_tag(SRGB_NAME)),
+        (b"cprt", [b"text".as_slice(), &[0; 4], b"No copyright, use freely\0"].concat()),
+        (b"wtpt", xyz_tag(D50)),
+        (b"rXYZ", xyz_tag(SRGB_RED)),
+        (b"gXYZ", xyz_tag(SRGB_GREEN)),
+        (b"bXYZ", xyz_tag(SRGB_BLUE)),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve.clone()),
+        (b"bTRC", curve),
+    ];
+
+    // 标签数据依次放在标签表之后,4 字节对齐
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let data_start = 128 + 4 + 12 * tags.len();
+    for (signature, tag) in &tags {
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        data.extend_from_slice(tag);
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+
+    let size = data_start + data.len();
+    let mut header = Vec::with_capacity(128);
+    header.extend_from_slice(&(size as u32).to_be_bytes());
+    header.extend_from_slice(&[0; 4]);
+    // 版本 2.1
+    header.extend_from_slice(&[2, 0x10, 0, 0]);
+    header.extend_from_slice(b"mntrRGB XYZ ");
+    // 创建时间: 年月日时分秒
+    for field in [2024u16, 1, 1, 0, 0, 0] {
+        header.extend_from_slice(&field.to_be_bytes());
+    }
+    header.extend_from_slice(b"acsp");
+    // 平台、标志、设备厂商和型号、设备属性、渲染意图
+    header.extend_from_slice(&[0; 28]);
+    for value in D50 {
+        header.extend_from_slice(&s15_fixed16(value));
+    }
+    header.resize(128, 0);
+
+    [header, table, data].concat()
+}
+
+fn s15_fixed16(value: f64) -> [u8; 4] {
+    ((value * 65536.0).round() as i32).to_be_bytes()
+}
+
+fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    for value in xyz {
+        tag.extend_from_slice(&s15_fixed16(value));
+    }
+    tag
+}
+
+// sRGB 色调曲线: 低端线性,其余为 2.4 次幂
+fn curve_tag() -> Vec<u8> {
+    let mut tag = b"curv\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(TRC_POINTS as u32).to_be_bytes());
+    for i in 0..TRC_POINTS {
+        let v = i as f64 / (TRC_POINTS - 1) as f64;
+        let linear = if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) };
+        tag.extend_from_slice(&((linear * 65535.0).round() as u16).to_be_bytes());
+    }
+    tag
+}
+
+// textDescriptionType: ASCII 描述,Unicode 和 ScriptCode 部分留空
+fn text_description_tag(text: &str) -> Vec<u8> {
+    let mut tag = b"desc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(text.len() as u32 + 1).to_be_bytes());
+    tag.extend_from_slice(text.as_bytes());
+    tag.push(0);
+    tag.extend_from_slice(&[0; 8]);
+    tag.extend_from_slice(&[0; 3]);
+    tag.extend_from_slice(&[0; 67]);
+    tag
+}
+
+// 去掉 PDF/A 不允许的内容: 脚本和其他受限动作、隐藏或不打印的注释、图片插值、
+// 传递函数和半色调、外部文件流,以及缺少名称的可选内容配置
+fn remove_forbidden_features(doc: &mut Document) {
+    let forbidden_action_ids: Vec<ObjectId> = doc
+        .objects
+        .iter()
+        .filter(|(_, object)| object.as_dict().map(is_forbidden_action).unwrap_or(false))
+        .map(|(id, _)| *id)
+        .collect();
+
+    for object in doc.objects.values_mut() {
+        let dict = match object {
+            Object::Dictionary(dict) => dict,
+            Object::Stream(stream) => {
+                for key in [b"F".as_slice(), b"FFilter", b"FDecodeParms"] {
+                    stream.dict.remove(key);
+                }
+                &mut stream.dict
+            }
+            _ => continue,
+        };
+
+        dict.remove(b"AA");
+        let action_forbidden = match dict.get(b"A") {
+            Ok(Object::Dictionary(action)) => is_forbidden_action(action),
+            Ok(Object::Reference(id)) => forbidden_action_ids.contains(id),
+            _ => false,
+        };
+        if action_forbidden {
+            dict.remove(b"A");
+        }
+
+        if dict.has(b"Rect") && dict.has(b"Subtype") {
+            let flags = dict.get(b"F").and_then(Object::as_i64).unwrap_or(0);
+            dict.set("F", (flags & !ANNOT_HIDDEN_FLAGS) | ANNOT_PRINT_FLAG);
+        }
+        if dict.type_is(b"XObject") || dict.has(b"BitsPerComponent") {
+            dict.remove(b"Interpolate");
+        }
+        if dict.type_is(b"ExtGState") {
+            for key in [b"TR".as_slice(), b"TR2", b"HTP", b"HT"] {
+                dict.remove(key);
+            }
+        }
+    }
+
+    let forbidden_open_action = match doc.catalog().ok().and_then(|catalog| catalog.get(b"OpenAction").ok()) {
+        Some(Object::Dictionary(action)) => is_forbidden_action(action),
+        Some(Object::Reference(id)) => forbidden_action_ids.contains(id),
+        _ => false,
+    };
+    let names_id = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Names").ok())
+        .and_then(|names| names.as_reference().ok());
+
+    if let Ok(catalog) = doc.catalog_mut() {
+        if forbidden_open_action {
+            catalog.remove(b"OpenAction");
+        }
+        // 可选内容的默认配置必须有名称,且不能有 AS
+        if let Ok(Object::Dictionary(properties)) = catalog.get_mut(b"OCProperties") {
+            if let Ok(Object::Dictionary(config)) = properties.get_mut(b"D") {
+                if !config.has(b"Name") {
+                    config.set("Name", Object::string_literal("Default"));
+                }
+                config.remove(b"AS");
+            }
+        }
+        if let Ok(Object::Dictionary(names)) = catalog.get_mut(b"Names") {
+            names.remove(b"JavaScript");
+            names.remove(b"EmbeddedFiles");
+        }
+    }
+    if let Some(Ok(names)) = names_id.map(|id| doc.get_dictionary_mut(id)) {
+        names.remove(b"JavaScript");
+        names.remove(b"EmbeddedFiles");
+    }
+}
+
+fn is_forbidden_action(action: &Dictionary) -> bool {
+    action
+        .get(b"S")
+        .and_then(Object::as_name)
+        .map(|kind| FORBIDDEN_ACTIONS.contains(&kind))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf_fonts::{self, PdfFonts};
+    use crate::pdf_generator::{self, PdfBuilder};
+    use printpdf::Mm;
+
+    // 生成一页带文字的文档,再加上 PDF/A 不允许的 JavaScript
+    fn generated_document() -> Document {
+        let text = "Archive test";
+        let font_files = pdf_fonts::load_fonts(&[pdf_fonts::TEST_FONT.to_string()], text).unwrap();
+        let builder = PdfBuilder::new("Archive", true);
+        let fonts = PdfFonts::new(&builder.doc, &font_files).unwrap();
+        let (page, layer) = builder.doc.add_page(Mm(210.0), Mm(297.0), "Layer 1");
+        let layer = builder.doc.get_page(page).get_layer(layer);
+        fonts.use_text(&layer, text, 12.0, Mm(20.0), Mm(270.0));
+        drop(fonts);
+        let mut doc = pdf_generator::finish_document(builder).unwrap();
+
+        let script = dictionary! { "S" => "JavaScript", "JS" => Object::string_literal("app.alert(1)") };
+        let names = doc.add_object(dictionary! { "JavaScript" => dictionary! { "Names" => vec![] } });
+        let catalog = doc.catalog_mut().unwrap();
+        catalog.set("OpenAction", script);
+        catalog.set("Names", names);
+        doc
+    }
+
+    fn resolved<'a>(doc: &'a Document, object: &'a Object) -> &'a Object {
+        match object {
+            Object::Reference(id) => doc.get_object(*id).unwrap(),
+            object => object,
+        }
+    }
+
+    fn has_font_file(doc: &Document, font: &Dictionary) -> bool {
+        font.get(b"FontDescriptor")
+            .ok()
+            .and_then(|descriptor| resolve_dict(doc, descriptor))
+            .is_some_and(|descriptor| ["FontFile", "FontFile2", "FontFile3"].iter().any(|k| descriptor.has(k.as_bytes())))
+    }
+
+    #[test]
+    fn converted_document_is_structurally_pdfa() {
+        let mut doc = generated_document();
+        convert_to_pdfa(&mut doc).unwrap();
+        let path = std::env::temp_dir().join(format!("pdf_archive_test_{}.pdf", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        save_pdfa(&doc, &path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let doc = Document::load_mem(&bytes).unwrap();
+
+        // 文件头后的二进制注释行
+        assert!(bytes.starts_with(b"%PDF-1.7\n%"));
+        assert!(bytes[10..14].iter().all(|b| *b >= 128));
+        assert!(doc.trailer.get(b"ID").is_ok());
+        assert!(doc.trailer.get(b"Encrypt").is_err());
+
+        let catalog = doc.catalog().unwrap();
+        let intents = resolved(&doc, catalog.get(b"OutputIntents").unwrap()).as_array().unwrap();
+        let intent = resolved(&doc, &intents[0]).as_dict().unwrap();
+        assert_eq!(intent.get(b"S").unwrap().as_name().unwrap(), b"GTS_PDFA1");
+        let profile = resolved(&doc, intent.get(b"DestOutputProfile").unwrap()).as_stream().unwrap();
+        assert_eq!(profile.dict.get(b"N").unwrap().as_i64().unwrap(), 3);
+
+        let metadata = resolved(&doc, catalog.get(b"Metadata").unwrap()).as_stream().unwrap();
+        let xmp = String::from_utf8_lossy(&metadata.content);
+        assert!(xmp.contains("<pdfaid:part>2</pdfaid:part>"));
+        assert!(xmp.contains("<pdfaid:conformance>B</pdfaid:conformance>"));
+
+        assert!(catalog.get(b"OpenAction").is_err());
+        let names = resolved(&doc, catalog.get(b"Names").unwrap()).as_dict().unwrap();
+        assert!(!names.has(b"JavaScript"));
+
+        let mut fonts = 0;
+        for object in doc.objects.values() {
+            let dict = match object {
+                Object::Dictionary(dict) => dict,
+                Object::Stream(stream) => &stream.dict,
+                _ => continue,
+            };
+            assert!(dict.get(b"S").and_then(Object::as_name).ok() != Some(b"JavaScript".as_slice()));
+            assert!(!dict.has(b"JS") && !dict.has(b"AA"));
+            // PDF/A-2 允许透明,但生成的文档不应带透明组
+            assert!(dict.get(b"S").and_then(Object::as_name).ok() != Some(b"Transparency".as_slice()));
+
+            if dict.type_is(b"Font") {
+                fonts += 1;
+                match dict.get(b"Subtype").and_then(Object::as_name).unwrap() {
+                    b"Type0" => {
+                        let descendants = resolved(&doc, dict.get(b"DescendantFonts").unwrap()).as_array().unwrap();
+                        for descendant in descendants {
+                            assert!(has_font_file(&doc, resolved(&doc, descendant).as_dict().unwrap()));
+                        }
+                    }
+                    b"Type3" => {}
+                    _ => assert!(has_font_file(&doc, dict)),
+                }
+            }
+        }
+        assert!(fonts > 0);
+    }
+}
@@ -0,0 +1,1000 @@
+use ::image::{DynamicImage, GrayImage, ImageFormat, RgbImage, RgbaImage};
+use printpdf::lopdf::content::Content;
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::pdf_utils::{self, resolve, resolve_dict};
+
+// 两段文本的水平间距超过字号的这个比例时插入空格
+const WORD_GAP: f32 = 0.2;
+// 基线高度差小于字号的这个比例时视为同一行
+const LINE_TOLERANCE: f32 = 0.5;
+// 行距超过字号的这个比例时插入空行分段
+const PARAGRAPH_GAP: f32 = 2.0;
+// 嵌套表单 XObject 的最大深度,防止循环引用
+const MAX_FORM_DEPTH: usize = 8;
+// 简单字体缺少宽度信息时的字宽 (千分之一字号)
+const DEFAULT_SIMPLE_WIDTH: f32 = 500.0;
+
+const IDENTITY: [f32; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+// WinAnsiEncoding 中 0x80-0x9F 与 Latin-1 不同的部分
+const WIN_ANSI_HIGH: [char; 32] = [
+    '€', '\u{FFFD}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{FFFD}', 'Ž', '\u{FFFD}',
+    '\u{FFFD}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{FFFD}', 'ž', 'Ÿ',
+];
+
+// Differences 中常见的字形名称
+const GLYPH_NAMES: [(&str, char); 46] = [
+    ("space", ' '), ("exclam", '!'), ("quotedbl", '"'), ("numbersign", '#'), ("dollar", '$'),
+    ("percent", '%'), ("ampersand", '&'), ("quotesingle", '\''), ("parenleft", '('), ("parenright", ')'),
+    ("asterisk", '*'), ("plus", '+'), ("comma", ','), ("hyphen", '-'), ("period", '.'), ("slash", '/'),
+    ("zero", '0'), ("one", '1'), ("two", '2'), ("three", '3'), ("four", '4'), ("five", '5'), ("six", '6'),
+    ("seven", '7'), ("eight", '8'), ("nine", '9'), ("colon", ':'), ("semicolon", ';'), ("less", '<'),
+    ("equal", '='), ("greater", '>'), ("question", '?'), ("at", '@'), ("bracketleft", '['),
+    ("backslash", '\\'), ("bracketright", ']'), ("underscore", '_'), ("braceleft", '{'), ("bar", '|'),
+    ("braceright", '}'), ("bullet", '•'), ("endash", '–'), ("emdash", '—'), ("quoteleft", '‘'),
+    ("quoteright", '’'), ("ellipsis", '…'),
+];
+
+// 文本解码和定位所需的字体信息
+struct TextFont {
+    // Type0 字体默认按双字节取码
+    composite: bool,
+    // ToUnicode 中的编码空间: (字节数, 下界, 上界)
+    code_ranges: Vec<(usize, u32, u32)>,
+    to_unicode: HashMap<u32, String>,
+    // 没有 ToUnicode 时单字节编码对应的字符
+    encoding: Vec<Option<char>>,
+    widths: HashMap<u32, f32>,
+    default_width: f32,
+}
+
+// 随 q/Q 保存和恢复的图形状态
+#[derive(Clone)]
+struct TextState {
+    ctm: [f32; 6],
+    font: Option<Rc<TextFont>>,
+    font_size: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scale: f32,
+    leading: f32,
+    rise: f32,
+}
+
+// 一次文本绘制的结果,坐标为页面空间
+struct TextChunk {
+    x: f32,
+    y: f32,
+    end_x: f32,
+    size: f32,
+    text: String,
+}
+
+// 按页提取文本,每页内按从上到下、从左到右的阅读顺序排列;
+// 指定 output_path 时同时写入文本文件,页与页之间用换页符分隔
+pub fn extract_text(path: &str, output_path: Option<&str>) -> Result<Vec<String>, String> {
+    let doc = pdf_utils::load_pdf(path)?;
+    let mut fonts: HashMap<ObjectId, Rc<TextFont>> = HashMap::new();
+
+    let pages = doc
+        .get_pages()
+        .into_values()
+        .map(|page_id| {
+            let mut chunks = Vec::new();
+            if let Ok(content) = doc.get_page_content(page_id) {
+                let resources = page_resources(&doc, page_id);
+                let state = TextState::new(IDENTITY);
+                scan_text(&doc, &content, &resources, state, 0, &mut fonts, &mut chunks);
+            }
+            layout_text(chunks)
+        })
+        .collect::<Vec<_>>();
+
+    println!("提取文本: {}, {} 页", path, pages.len());
+    if let Some(output_path) = output_path {
+        fs::write(output_path, pages.join("\n\u{c}\n")).map_err(|e| format!("Failed to save text: {}", e))?;
+    }
+    Ok(pages)
+}
+
+// 把嵌入的图片导出到目录,JPEG 和 JPEG 2000 保持原始编码,其他格式转为 PNG
+pub fn extract_images(path: &str, output_dir: &str) -> Result<Vec<String>, String> {
+    let doc = pdf_utils::load_pdf(path)?;
+    fs::create_dir_all(output_dir).map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let stem = pdf_utils::file_stem(path);
+    let mut seen: HashSet<ObjectId> = HashSet::new();
+    let mut saved = Vec::new();
+    for (page_number, page_id) in doc.get_pages() {
+        let mut image_ids = Vec::new();
+        for resources in page_resources(&doc, page_id) {
+            collect_images(&doc, resources, 0, &mut image_ids);
+        }
+
+        let mut index = 0;
+        for image_id in image_ids {
+            // 多个页面共用的图片只导出一次
+            if !seen.insert(image_id) {
+                continue;
+            }
+            let Ok(stream) = doc.get_object(image_id).and_then(Object::as_stream) else {
+                continue;
+            };
+
+            index += 1;
+            let base = Path::new(output_dir).join(format!("{}_p{}_{}", stem, page_number, index));
+            match export_image(&doc, stream, &base)? {
+                Some(file) => saved.push(file),
+                None => println!("  跳过不支持的图片: {:?}", image_id),
+            }
+        }
+    }
+
+    println!("提取图片: {}, 共 {} 张", path, saved.len());
+    Ok(saved)
+}
+
+pub(crate) fn page_resources(doc: &Document, page_id: ObjectId) -> Vec<&Dictionary> {
+    let (resource_dict, resource_ids) = doc.get_page_resources(page_id);
+    let mut resources: Vec<&Dictionary> = resource_dict.into_iter().collect();
+    resources.extend(resource_ids.into_iter().filter_map(|id| doc.get_dictionary(id).ok()));
+    resources
+}
+
+fn find_resource<'a>(doc: &'a Document, resources: &[&'a Dictionary], category: &[u8], name: &[u8]) -> Option<&'a Object> {
+    resources
+        .iter()
+        .find_map(|dict| resolve_dict(doc, dict.get(category).ok()?)?.get(name).ok())
+}
+
+impl TextState {
+    fn new(ctm: [f32; 6]) -> Self {
+        TextState {
+            ctm,
+            font: None,
+            font_size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        }
+    }
+}
+
+fn scan_text(
+    doc: &Document,
+    data: &[u8],
+    resources: &[&Dictionary],
+    mut state: TextState,
+    depth: usize,
+    fonts: &mut HashMap<ObjectId, Rc<TextFont>>,
+    chunks: &mut Vec<TextChunk>,
+) {
+    let Ok(content) = Content::decode(data) else {
+        return;
+    };
+
+    let mut saved: Vec<TextState> = Vec::new();
+    // 文本矩阵和文本行矩阵
+    let mut tm = IDENTITY;
+    let mut tlm = IDENTITY;
+    for operation in &content.operations {
+        let operands = &operation.operands;
+        let number = |index: usize| operands.get(index).and_then(|value| value.as_float().ok()).unwrap_or(0.0);
+        match operation.operator.as_str() {
+            "q" => saved.push(state.clone()),
+            "Q" => {
+                if let Some(previous) = saved.pop() {
+                    state = previous;
+                }
+            }
+            "cm" => {
+                if let Some(matrix) = read_matrix(operands) {
+                    state.ctm = multiply(&matrix, &state.ctm);
+                }
+            }
+            "BT" => {
+                tm = IDENTITY;
+                tlm = IDENTITY;
+            }
+            "Tf" => {
+                state.font = operands
+                    .first()
+                    .and_then(|name| name.as_name().ok())
+                    .and_then(|name| find_resource(doc, resources, b"Font", name))
+                    .and_then(|font| load_font(doc, font, fonts));
+                state.font_size = number(1);
+            }
+            "Tc" => state.char_spacing = number(0),
+            "Tw" => state.word_spacing = number(0),
+            "Tz" => state.horizontal_scale = number(0) / 100.0,
+            "TL" => state.leading = number(0),
+            "Ts" => state.rise = number(0),
+            "Td" | "TD" => {
+                if operation.operator == "TD" {
+                    state.leading = -number(1);
+                }
+                tlm = multiply(&[1.0, 0.0, 0.0, 1.0, number(0), number(1)], &tlm);
+                tm = tlm;
+            }
+            "Tm" => {
+                if let Some(matrix) = read_matrix(operands) {
+                    tlm = matrix;
+                    tm = matrix;
+                }
+            }
+            "T*" => {
+                tlm = multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -state.leading], &tlm);
+                tm = tlm;
+            }
+            "Tj" | "'" | "\"" => {
+                if operation.operator != "Tj" {
+                    if operation.operator == "\"" {
+                        state.word_spacing = number(0);
+                        state.char_spacing = number(1);
+                    }
+                    tlm = multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -state.leading], &tlm);
+                    tm = tlm;
+                }
+                if let Some(Object::String(bytes, _)) = operands.last() {
+                    show_text(bytes, &state, &mut tm, chunks);
+                }
+            }
+            "TJ" => {
+                let Some(items) = operands.first().and_then(|items| items.as_array().ok()) else {
+                    continue;
+                };
+                for item in items {
+                    match item {
+                        Object::String(bytes, _) => show_text(bytes, &state, &mut tm, chunks),
+                        // 数字为字距调整,单位为千分之一字号
+                        _ => {
+                            let adjustment = item.as_float().unwrap_or(0.0);
+                            let tx = -adjustment / 1000.0 * state.font_size * state.horizontal_scale;
+                            tm = multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], &tm);
+                        }
+                    }
+                }
+            }
+            "Do" if depth < MAX_FORM_DEPTH => {
+                let Some(stream) = operands
+                    .first()
+                    .and_then(|name| name.as_name().ok())
+                    .and_then(|name| find_resource(doc, resources, b"XObject", name))
+                    .and_then(|xobject| resolve(doc, xobject))
+                    .and_then(|xobject| xobject.as_stream().ok())
+                else {
+                    continue;
+                };
+                if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Form") {
+                    continue;
+                }
+
+                let Some(form_content) = stream_data(stream) else {
+                    continue;
+                };
+                let matrix = stream
+                    .dict
+                    .get(b"Matrix")
+                    .ok()
+                    .and_then(|matrix| matrix.as_array().ok())
+                    .and_then(|matrix| read_matrix(matrix))
+                    .unwrap_or(IDENTITY);
+                // 表单没有自己的资源时沿用上层资源
+                let form_resources = match stream.dict.get(b"Resources").ok().and_then(|r| resolve_dict(doc, r)) {
+                    Some(dict) => vec![dict],
+                    None => resources.to_vec(),
+                };
+                let mut form_state = state.clone();
+                form_state.ctm = multiply(&matrix, &state.ctm);
+                scan_text(doc, &form_content, &form_resources, form_state, depth + 1, fonts, chunks);
+            }
+            _ => {}
+        }
+    }
+}
+
+// 绘制一段字符串: 解码文本并按字宽推进文本矩阵
+fn show_text(bytes: &[u8], state: &TextState, tm: &mut [f32; 6], chunks: &mut Vec<TextChunk>) {
+    let Some(font) = &state.font else {
+        return;
+    };
+
+    let start = multiply(&multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, state.rise], tm), &state.ctm);
+    let mut text = String::new();
+    for (code, length) in font.codes(bytes) {
+        text.push_str(&font.decode(code, length));
+
+        let width = font.width(code) / 1000.0 * state.font_size;
+        let word_spacing = if length == 1 && code == 32 { state.word_spacing } else { 0.0 };
+        let tx = (width + state.char_spacing + word_spacing) * state.horizontal_scale;
+        *tm = multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], tm);
+    }
+    let end = multiply(tm, &state.ctm);
+
+    if !text.is_empty() {
+        chunks.push(TextChunk {
+            x: start[4],
+            y: start[5],
+            end_x: end[4],
+            size: (state.font_size * start[2].hypot(start[3])).abs().max(1.0),
+            text,
+        });
+    }
+}
+
+// 把文本片段按基线分行,行内按横坐标排序,间距较大处补空格
+fn layout_text(mut chunks: Vec<TextChunk>) -> String {
+    chunks.sort_by(|a, b| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)));
+
+    let mut lines: Vec<(f32, f32, Vec<TextChunk>)> = Vec::new();
+    for chunk in chunks {
+        match lines.last_mut() {
+            Some((y, size, line)) if (*y - chunk.y).abs() < LINE_TOLERANCE * size.min(chunk.size) => {
+                *size = size.max(chunk.size);
+                line.push(chunk);
+            }
+            _ => lines.push((chunk.y, chunk.size, vec![chunk])),
+        }
+    }
+
+    let mut output: Vec<String> = Vec::new();
+    let mut previous_y: Option<f32> = None;
+    for (y, size, mut line) in lines {
+        line.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+        let mut text = String::new();
+        let mut end_x = f32::MIN;
+        for chunk in line {
+            let gap = chunk.x - end_x > WORD_GAP * chunk.size;
+            if gap && !text.is_empty() && !text.ends_with(char::is_whitespace) && !chunk.text.starts_with(char::is_whitespace) {
+                text.push(' ');
+            }
+            text.push_str(&chunk.text);
+            end_x = chunk.end_x;
+        }
+
+        let text = text.trim_end().to_string();
+        if text.is_empty() {
+            continue;
+        }
+        if previous_y.is_some_and(|previous| previous - y > PARAGRAPH_GAP * size) {
+            output.push(String::new());
+        }
+        output.push(text);
+        previous_y = Some(y);
+    }
+    output.join("\n")
+}
+
+fn load_font(doc: &Document, font: &Object, cache: &mut HashMap<ObjectId, Rc<TextFont>>) -> Option<Rc<TextFont>> {
+    let Ok(font_id) = font.as_reference() else {
+        return resolve_dict(doc, font).map(|dict| Rc::new(TextFont::new(doc, dict)));
+    };
+    if let Some(font) = cache.get(&font_id) {
+        return Some(font.clone());
+    }
+    let font = Rc::new(TextFont::new(doc, doc.get_dictionary(font_id).ok()?));
+    cache.insert(font_id, font.clone());
+    Some(font)
+}
+
+impl TextFont {
+    fn new(doc: &Document, dict: &Dictionary) -> Self {
+        let composite = dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Type0");
+        let mut font = TextFont {
+            composite,
+            code_ranges: Vec::new(),
+            to_unicode: HashMap::new(),
+            encoding: Vec::new(),
+            widths: HashMap::new(),
+            default_width: if composite { 1000.0 } else { DEFAULT_SIMPLE_WIDTH },
+        };
+
+        let to_unicode = dict
+            .get(b"ToUnicode")
+            .ok()
+            .and_then(|cmap| resolve(doc, cmap))
+            .and_then(|cmap| cmap.as_stream().ok())
+            .and_then(stream_data);
+        if let Some(cmap) = to_unicode {
+            font.parse_to_unicode(&cmap);
+        }
+
+        if composite {
+            let cid_font = dict
+                .get(b"DescendantFonts")
+                .ok()
+                .and_then(|fonts| resolve(doc, fonts))
+                .and_then(|fonts| fonts.as_array().ok())
+                .and_then(|fonts| fonts.first())
+                .and_then(|cid_font| resolve_dict(doc, cid_font));
+            if let Some(cid_font) = cid_font {
+                font.read_cid_widths(doc, cid_font);
+            }
+        } else {
+            font.encoding = simple_encoding(doc, dict);
+            font.read_simple_widths(doc, dict);
+        }
+        font
+    }
+
+    // 按编码空间把字节拆成 (编码, 字节数)
+    fn codes(&self, bytes: &[u8]) -> Vec<(u32, usize)> {
+        let default_length = if self.composite { 2 } else { 1 };
+        let mut codes = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let length = self
+                .code_ranges
+                .iter()
+                .find(|&&(length, low, high)| {
+                    bytes.get(i..i + length).is_some_and(|code| (low..=high).contains(&read_code(code)))
+                })
+                .map_or(default_length, |&(length, _, _)| length)
+                .min(bytes.len() - i);
+            codes.push((read_code(&bytes[i..i + length]), length));
+            i += length;
+        }
+        codes
+    }
+
+    fn decode(&self, code: u32, length: usize) -> String {
+        if let Some(text) = self.to_unicode.get(&code) {
+            return text.clone();
+        }
+        // 没有 ToUnicode 的 Type0 字体无法得到字符
+        if self.composite || length != 1 {
+            return String::new();
+        }
+        self.encoding.get(code as usize).copied().flatten().map(String::from).unwrap_or_default()
+    }
+
+    fn width(&self, code: u32) -> f32 {
+        self.widths.get(&code).copied().unwrap_or(self.default_width)
+    }
+
+    fn parse_to_unicode(&mut self, cmap: &[u8]) {
+        let tokens = cmap_tokens(cmap);
+        let mut i = 0;
+        while i < tokens.len() {
+            match tokens[i].as_slice() {
+                b"begincodespacerange" => {
+                    i += 1;
+                    while let (Some(low), Some(high)) = (hex_token(&tokens, i), hex_token(&tokens, i + 1)) {
+                        self.code_ranges.push((low.len(), read_code(&low), read_code(&high)));
+                        i += 2;
+                    }
+                }
+                b"beginbfchar" => {
+                    i += 1;
+                    while let (Some(code), Some(text)) = (hex_token(&tokens, i), hex_token(&tokens, i + 1)) {
+                        self.to_unicode.insert(read_code(&code), utf16_text(&text));
+                        i += 2;
+                    }
+                }
+                b"beginbfrange" => {
+                    i += 1;
+                    while let (Some(low), Some(high)) = (hex_token(&tokens, i), hex_token(&tokens, i + 1)) {
+                        let (low, high) = (read_code(&low), read_code(&high));
+                        // 限制范围大小,避免损坏的 CMap 占用过多内存
+                        let high = high.min(low.saturating_add(0xFFFF));
+                        if let Some(start) = hex_token(&tokens, i + 2) {
+                            // 目标按最后一个 UTF-16 单元递增
+                            for (offset, code) in (low..=high).enumerate() {
+                                let mut text = start.clone();
+                                if let Some(last) = text.len().checked_sub(1) {
+                                    text[last] = text[last].wrapping_add(offset as u8);
+                                }
+                                self.to_unicode.insert(code, utf16_text(&text));
+                            }
+                            i += 3;
+                        } else if tokens.get(i + 2).is_some_and(|token| token == b"[") {
+                            i += 3;
+                            let mut code = low;
+                            while let Some(text) = hex_token(&tokens, i) {
+                                if code <= high {
+                                    self.to_unicode.insert(code, utf16_text(&text));
+                                }
+                                code += 1;
+                                i += 1;
+                            }
+                            // 跳过 "]"
+                            i += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    // W 数组: [起始编号 [宽度...]] 或 [起始编号 结束编号 宽度]
+    fn read_cid_widths(&mut self, doc: &Document, cid_font: &Dictionary) {
+        if let Ok(width) = cid_font.get(b"DW").and_then(Object::as_float) {
+            self.default_width = width;
+        }
+        let Some(widths) = cid_font.get(b"W").ok().and_then(|w| resolve(doc, w)).and_then(|w| w.as_array().ok()) else {
+            return;
+        };
+
+        let mut i = 0;
+        while i + 1 < widths.len() {
+            let Ok(start) = widths[i].as_i64() else {
+                break;
+            };
+            match resolve(doc, &widths[i + 1]) {
+                Some(Object::Array(values)) => {
+                    for (offset, width) in values.iter().enumerate() {
+                        if let Ok(width) = width.as_float() {
+                            self.widths.insert((start + offset as i64) as u32, width);
+                        }
+                    }
+                    i += 2;
+                }
+                Some(end) => {
+                    let (Ok(end), Some(Ok(width))) = (end.as_i64(), widths.get(i + 2).map(Object::as_float)) else {
+                        break;
+                    };
+                    // 限制范围大小,避免损坏的宽度表占用过多内存
+                    for code in start..=end.min(start + 0xFFFF) {
+                        self.widths.insert(code as u32, width);
+                    }
+                    i += 3;
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn read_simple_widths(&mut self, doc: &Document, dict: &Dictionary) {
+        let base_font = dict.get(b"BaseFont").and_then(Object::as_name).unwrap_or_default();
+        // 标准 14 字体中的 Courier 是等宽字体
+        if base_font.starts_with(b"Courier") {
+            self.default_width = 600.0;
+        }
+        if let Some(width) = dict
+            .get(b"FontDescriptor")
+            .ok()
+            .and_then(|descriptor| resolve_dict(doc, descriptor))
+            .and_then(|descriptor| descriptor.get(b"MissingWidth").and_then(Object::as_float).ok())
+            .filter(|&width| width > 0.0)
+        {
+            self.default_width = width;
+        }
+
+        let first_char = dict.get(b"FirstChar").and_then(Object::as_i64).unwrap_or(0);
+        let widths = dict.get(b"Widths").ok().and_then(|w| resolve(doc, w)).and_then(|w| w.as_array().ok());
+        for (offset, width) in widths.into_iter().flatten().enumerate() {
+            if let Some(Ok(width)) = resolve(doc, width).map(Object::as_float) {
+                self.widths.insert((first_char + offset as i64) as u32, width);
+            }
+        }
+    }
+}
+
+// 简单字体的单字节编码: 以 WinAnsiEncoding 为基础,再应用 Differences
+fn simple_encoding(doc: &Document, dict: &Dictionary) -> Vec<Option<char>> {
+    let mut encoding: Vec<Option<char>> = (0u8..=255)
+        .map(|byte| match byte {
+            0x80..=0x9F => Some(WIN_ANSI_HIGH[byte as usize - 0x80]).filter(|&c| c != '\u{FFFD}'),
+            0x20..=0x7E | 0xA0..=0xFF => Some(byte as char),
+            _ => None,
+        })
+        .collect();
+
+    let differences = dict
+        .get(b"Encoding")
+        .ok()
+        .and_then(|encoding| resolve_dict(doc, encoding))
+        .and_then(|encoding| encoding.get(b"Differences").ok())
+        .and_then(|differences| differences.as_array().ok());
+    let mut code = 0usize;
+    for item in differences.into_iter().flatten() {
+        match item {
+            Object::Integer(start) => code = *start as usize,
+            Object::Name(name) => {
+                if let Some(slot) = encoding.get_mut(code) {
+                    *slot = glyph_name_char(name);
+                }
+                code += 1;
+            }
+            _ => {}
+        }
+    }
+    encoding
+}
+
+// 字形名称对应的字符: uniXXXX / uXXXX[XX]、单个字母数字以及常见标点
+fn glyph_name_char(name: &[u8]) -> Option<char> {
+    let name = std::str::from_utf8(name).ok()?;
+    if let Some(hex) = name.strip_prefix("uni").filter(|hex| hex.len() == 4) {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+    if let Some(hex) = name.strip_prefix('u').filter(|hex| (4..=6).contains(&hex.len())) {
+        if let Some(c) = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+            return Some(c);
+        }
+    }
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return c.is_ascii_alphanumeric().then_some(c);
+    }
+    GLYPH_NAMES.iter().find(|(glyph, _)| *glyph == name).map(|&(_, c)| c)
+}
+
+// CMap 的词法单元: 十六进制串保留尖括号,方括号单独成词
+fn cmap_tokens(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        if byte.is_ascii_whitespace() {
+            i += 1;
+        } else if byte == b'%' {
+            while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                i += 1;
+            }
+        } else if byte == b'<' && data.get(i + 1) != Some(&b'<') {
+            let end = data[i..].iter().position(|&b| b == b'>').map_or(data.len(), |end| i + end + 1);
+            tokens.push(data[i..end].to_vec());
+            i = end;
+        } else if byte == b'[' || byte == b']' {
+            tokens.push(vec![byte]);
+            i += 1;
+        } else {
+            let start = i;
+            while i < data.len() && !data[i].is_ascii_whitespace() && !b"[]<%".contains(&data[i]) {
+                i += 1;
+            }
+            // "<<" 等其他分隔符
+            i = i.max(start + 1);
+            tokens.push(data[start..i].to_vec());
+        }
+    }
+    tokens
+}
+
+// 十六进制串词法单元的字节值
+fn hex_token(tokens: &[Vec<u8>], index: usize) -> Option<Vec<u8>> {
+    let token = tokens.get(index)?;
+    let hex: Vec<u8> = token.strip_prefix(b"<")?.strip_suffix(b">")?.iter().copied().filter(u8::is_ascii_hexdigit).collect();
+    hex.chunks(2)
+        .map(|pair| {
+            let digits = std::str::from_utf8(pair).ok()?;
+            // 奇数位时末尾补 0
+            u8::from_str_radix(&format!("{:0<2}", digits), 16).ok()
+        })
+        .collect()
+}
+
+fn utf16_text(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn read_code(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |code, &byte| (code << 8) | byte as u32)
+}
+
+fn read_matrix(operands: &[Object]) -> Option<[f32; 6]> {
+    let values: Vec<f32> = operands.iter().map(|value| value.as_float().ok()).collect::<Option<_>>()?;
+    values.try_into().ok()
+}
+
+// 矩阵乘法 m × n,两者都是 PDF 的 [a b c d e f] 形式
+fn multiply(m: &[f32; 6], n: &[f32; 6]) -> [f32; 6] {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+// 解压后的流数据; lopdf 不解压图片流,这里去掉 Subtype 后再解压
+fn stream_data(stream: &Stream) -> Option<Vec<u8>> {
+    if !stream.dict.has(b"Filter") {
+        return Some(stream.content.clone());
+    }
+    let mut dict = stream.dict.clone();
+    dict.remove(b"Subtype");
+    Stream::new(dict, stream.content.clone()).decompressed_content().ok()
+}
+
+// 资源中的图片 XObject,包括表单 XObject 内部使用的图片
+pub(crate) fn collect_images(doc: &Document, resources: &Dictionary, depth: usize, image_ids: &mut Vec<ObjectId>) {
+    let Some(xobjects) = resources.get(b"XObject").ok().and_then(|xobjects| resolve_dict(doc, xobjects)) else {
+        return;
+    };
+    for (_, xobject) in xobjects.iter() {
+        let Ok(id) = xobject.as_reference() else {
+            continue;
+        };
+        let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+            continue;
+        };
+        match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+            Ok(b"Image") => {
+                let is_mask = stream.dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false);
+                if !is_mask && !image_ids.contains(&id) {
+                    image_ids.push(id);
+                }
+            }
+            Ok(b"Form") if depth < MAX_FORM_DEPTH => {
+                if let Some(form_resources) = stream.dict.get(b"Resources").ok().and_then(|r| resolve_dict(doc, r)) {
+                    collect_images(doc, form_resources, depth + 1, image_ids);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// 写出一张图片,返回文件路径; 无法解码的格式返回 None
+fn export_image(doc: &Document, stream: &Stream, base: &Path) -> Result<Option<String>, String> {
+    let filters = stream.filters().unwrap_or_default();
+    let extension = match filters.as_slice() {
+        [filter] if filter == "DCTDecode" => Some("jpg"),
+        [filter] if filter == "JPXDecode" => Some("jp2"),
+        _ => None,
+    };
+    if let Some(extension) = extension {
+        let path = base.with_extension(extension);
+        fs::write(&path, &stream.content).map_err(|e| format!("Failed to save image: {}", e))?;
+        return Ok(Some(path.to_string_lossy().to_string()));
+    }
+
+    let Some(img) = decode_image(doc, stream) else {
+        return Ok(None);
+    };
+    let path = base.with_extension("png");
+    img.save_with_format(&path, ImageFormat::Png)
+        .map_err(|e| format!("Failed to save image: {}", e))?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+// 解码未压缩或 Flate/LZW 压缩的图片,支持灰度、RGB、CMYK 和索引色,有同尺寸 SMask 时加入透明通道
+fn decode_image(doc: &Document, stream: &Stream) -> Option<DynamicImage> {
+    let dict = &stream.dict;
+    let width = u32::try_from(dict.get(b"Width").and_then(Object::as_i64).ok()?).ok()?;
+    let height = u32::try_from(dict.get(b"Height").and_then(Object::as_i64).ok()?).ok()?;
+    let color_space = resolve(doc, dict.get(b"ColorSpace").ok()?)?;
+
+    let (components, palette) = match color_space {
+        Object::Array(items) if items.first().and_then(|name| name.as_name().ok()) == Some(b"Indexed") => {
+            let base = color_components(doc, resolve(doc, items.get(1)?)?)?;
+            let lookup = match resolve(doc, items.get(3)?)? {
+                Object::String(bytes, _) => bytes.clone(),
+                Object::Stream(lookup) => stream_data(lookup)?,
+                _ => return None,
+            };
+            let palette: Vec<[u8; 3]> = lookup.chunks_exact(base as usize).map(to_rgb).collect();
+            (1, Some(palette))
+        }
+        color_space => (color_components(doc, color_space)?, None),
+    };
+
+    let samples = read_samples(stream, width, height, components, palette.is_none())?;
+    // Decode 为 [1 0] 时取反
+    let inverted = palette.is_none()
+        && dict
+            .get(b"Decode")
+            .and_then(Object::as_array)
+            .is_ok_and(|decode| decode.first().and_then(|v| v.as_float().ok()) == Some(1.0));
+
+    let rgb: Vec<u8> = match &palette {
+        Some(palette) => samples
+            .iter()
+            .flat_map(|&index| palette.get(index as usize).copied().unwrap_or([0; 3]))
+            .collect(),
+        None => samples
+            .chunks_exact(components as usize)
+            .flat_map(|color| {
+                let color: Vec<u8> = color.iter().map(|&v| if inverted { 255 - v } else { v }).collect();
+                to_rgb(&color)
+            })
+            .collect(),
+    };
+
+    let alpha = dict
+        .get(b"SMask")
+        .ok()
+        .and_then(|mask| resolve(doc, mask))
+        .and_then(|mask| mask.as_stream().ok())
+        .filter(|mask| {
+            mask.dict.get(b"Width").and_then(Object::as_i64).ok() == Some(width as i64)
+                && mask.dict.get(b"Height").and_then(Object::as_i64).ok() == Some(height as i64)
+        })
+        .and_then(|mask| read_samples(mask, width, height, 1, true));
+
+    match alpha {
+        Some(alpha) => {
+            let rgba = rgb.chunks_exact(3).zip(alpha).flat_map(|(color, a)| [color[0], color[1], color[2], a]).collect();
+            RgbaImage::from_raw(width, height, rgba).map(DynamicImage::ImageRgba8)
+        }
+        None if components == 1 && palette.is_none() => {
+            GrayImage::from_raw(width, height, rgb.chunks_exact(3).map(|color| color[0]).collect()).map(DynamicImage::ImageLuma8)
+        }
+        None => RgbImage::from_raw(width, height, rgb).map(DynamicImage::ImageRgb8),
+    }
+}
+
+// 按行读取采样值,转成每分量 8 位; 索引色保留原始索引
+fn read_samples(stream: &Stream, width: u32, height: u32, components: u8, scale: bool) -> Option<Vec<u8>> {
+    let filters = stream.filters().unwrap_or_default();
+    if !filters.iter().all(|filter| filter == "FlateDecode" || filter == "LZWDecode") {
+        return None;
+    }
+    let bits = stream.dict.get(b"BitsPerComponent").and_then(Object::as_i64).ok()?;
+    if !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+        return None;
+    }
+
+    let data = stream_data(stream)?;
+    let (width, height, components, bits) = (width as usize, height as usize, components as usize, bits as usize);
+    let row_bytes = (width * components * bits).div_ceil(8);
+    if data.len() < row_bytes * height {
+        return None;
+    }
+
+    let max = (1u32 << bits.min(8)) - 1;
+    let mut samples = Vec::with_capacity(width * height * components);
+    for row in data.chunks_exact(row_bytes).take(height) {
+        for i in 0..width * components {
+            let value = match bits {
+                8 => row[i] as u32,
+                // 16 位只取高字节
+                16 => row[i * 2] as u32,
+                _ => {
+                    let bit = i * bits;
+                    (row[bit / 8] as u32 >> (8 - bits - bit % 8)) & max
+                }
+            };
+            samples.push(if scale && bits < 8 { (value * 255 / max) as u8 } else { value as u8 });
+        }
+    }
+    Some(samples)
+}
+
+fn color_components(doc: &Document, color_space: &Object) -> Option<u8> {
+    match color_space {
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceGray" | b"CalGray" => Some(1),
+            b"DeviceRGB" | b"CalRGB" => Some(3),
+            b"DeviceCMYK" => Some(4),
+            _ => None,
+        },
+        Object::Array(items) => match items.first().and_then(|name| name.as_name().ok())? {
+            b"ICCBased" => {
+                let profile = resolve(doc, items.get(1)?)?.as_stream().ok()?;
+                let components = profile.dict.get(b"N").and_then(Object::as_i64).ok()?;
+                matches!(components, 1 | 3 | 4).then_some(components as u8)
+            }
+            b"CalGray" => Some(1),
+            b"CalRGB" => Some(3),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// 灰度、RGB 和 CMYK 颜色转为 RGB
+fn to_rgb(color: &[u8]) -> [u8; 3] {
+    match *color {
+        [gray] => [gray; 3],
+        [c, m, y, k] => {
+            let channel = |v: u8| ((255 - v as u32) * (255 - k as u32) / 255) as u8;
+            [channel(c), channel(m), channel(y)]
+        }
+        [r, g, b, ..] => [r, g, b],
+        _ => [0; 3],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use printpdf::lopdf::dictionary;
+
+    const TO_UNICODE: &[u8] = b"/CIDInit /ProcSet findresource begin
+12 dict begin
+begincmap
+/CMapName /Test-UCS def
+2 begincodespacerange
+<00> <7F>
+<8000> <FFFF>
+endcodespacerange
+2 beginbfchar
+<20> <0020>
+<41> <0041>
+endbfchar
+2 beginbfrange
+<8100> <8102> <4E00>
+<8200> <8201> [<0048 0069> <D83D DE00>]
+endbfrange
+endcmap
+CMapName currentdict /CMap defineresource pop
+end
+end";
+
+    fn decode_all(font: &TextFont, bytes: &[u8]) -> String {
+        font.codes(bytes).into_iter().map(|(code, length)| font.decode(code, length)).collect()
+    }
+
+    fn chunk(x: f32, y: f32, text: &str) -> TextChunk {
+        // 每个字符宽 6pt (12pt 字号)
+        let end_x = x + text.chars().count() as f32 * 6.0;
+        TextChunk { x, y, end_x, size: 12.0, text: text.to_string() }
+    }
+
+    #[test]
+    fn to_unicode_cmap_maps_chars_and_ranges() {
+        let mut font = TextFont::new(&Document::new(), &dictionary! { "Subtype" => "Type0" });
+        font.parse_to_unicode(TO_UNICODE);
+
+        assert_eq!(font.code_ranges, [(1, 0x00, 0x7F), (2, 0x8000, 0xFFFF)]);
+        // bfchar
+        assert_eq!(decode_all(&font, b"A A"), "A A");
+        // 起始值递增的 bfrange
+        assert_eq!(decode_all(&font, &[0x81, 0x00, 0x81, 0x02]), "一丂");
+        // 数组形式的 bfrange,目标可以是多个字符或代理对
+        assert_eq!(decode_all(&font, &[0x82, 0x00, 0x20, 0x82, 0x01]), "Hi 😀");
+        // 范围外的编码没有字符
+        assert_eq!(decode_all(&font, &[0x81, 0x03, 0x42]), "");
+    }
+
+    #[test]
+    fn simple_encoding_applies_differences_to_win_ansi() {
+        let mut doc = Document::new();
+        let encoding = doc.add_object(dictionary! {
+            "Type" => "Encoding",
+            "Differences" => vec![
+                65.into(),
+                Object::Name(b"uni4E2D".to_vec()),
+                Object::Name(b"bullet".to_vec()),
+                0x90.into(),
+                Object::Name(b"unknownglyph".to_vec()),
+            ],
+        });
+        let font = dictionary! { "Subtype" => "Type1", "Encoding" => encoding };
+
+        let encoding = simple_encoding(&doc, &font);
+        assert_eq!(encoding[b'A' as usize], Some('中'));
+        assert_eq!(encoding[b'B' as usize], Some('•'));
+        assert_eq!(encoding[b'C' as usize], Some('C'));
+        assert_eq!(encoding[0x80], Some('€'));
+        assert_eq!(encoding[0x81], None);
+        assert_eq!(encoding[0x90], None);
+        assert_eq!(encoding[0xE9], Some('é'));
+        assert_eq!(encoding[0x0A], None);
+    }
+
+    #[test]
+    fn layout_orders_chunks_by_line_then_column() {
+        let chunks = vec![
+            // 第二行,右栏先于左栏绘制
+            chunk(300.0, 686.0, "right two"),
+            chunk(72.0, 686.0, "left two"),
+            // 第一行,基线略有偏差
+            chunk(300.0, 700.4, "right one"),
+            chunk(72.0, 700.0, "left one"),
+            // 紧接的片段不加空格
+            chunk(120.0, 700.0, ","),
+            // 间距较大,另起一段
+            chunk(72.0, 600.0, "footer"),
+        ];
+
+        assert_eq!(layout_text(chunks), "left one, right one\nleft two right two\n\nfooter");
+    }
+}
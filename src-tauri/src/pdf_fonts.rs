@@ -0,0 +1,655 @@
+use printpdf::lopdf::content::Content;
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId};
+use printpdf::*;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use ttf_parser::{name_id, Face};
+
+use crate::font_subset;
+use crate::pdf_text::{face_covers, is_ignorable, TextMeasurer};
+use crate::pdf_utils::resolve_dict;
+
+// 随应用打包的字体
+const BUNDLED_FONT: &str = "assets/AlibabaPuHuiTi-3-65-Medium.ttf";
+
+// 没有可用字体时依次尝试的系统字体,缺字时也优先从这些字体中回退
+const FALLBACK_FAMILIES: &[&str] = &[
+    // 中文
+    "Noto Sans SC",
+    "Source Han Sans SC",
+    "WenQuanYi Micro Hei",
+    "Microsoft YaHei",
+    "DengXian",
+    "SimHei",
+    "Arial Unicode MS",
+    // 拉丁字母及其他文字
+    "Noto Sans",
+    "DejaVu Sans",
+    "Arial",
+    "Liberation Sans",
+    "Helvetica",
+    // 符号与表情,只能使用有轮廓的单色字体
+    "Segoe UI Symbol",
+    "Segoe UI Emoji",
+    "Noto Emoji",
+    "Symbola",
+];
+
+// ToUnicode 每个 bfchar 段最多 100 个条目
+const TO_UNICODE_BLOCK: usize = 100;
+
+// 单元测试用的字体: Noto Sans,只含 Latin-1 字符 (见 tests/fixtures/README.md)
+#[cfg(test)]
+pub(crate) const TEST_FONT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/NotoSans-Latin1.ttf");
+
+// 系统中的字体文件
+struct FontFile {
+    path: PathBuf,
+    family: String,
+    full_name: String,
+    // 与常规字重的差距,粗体、斜体排在后面
+    style_rank: u32,
+    // cmap 中的 Unicode 码位区间 (闭区间,按起点排序),查找回退字体时不必读取字体文件
+    coverage: Vec<(u32, u32)>,
+}
+
+impl FontFile {
+    // cmap 包含该字符; 是否有可嵌入的字形要读取字体后再确认
+    fn maps(&self, c: char) -> bool {
+        let c = c as u32;
+        let index = self.coverage.partition_point(|&(start, _)| start <= c);
+        index > 0 && self.coverage[index - 1].1 >= c
+    }
+}
+
+// 已读取的字体文件
+pub struct LoadedFont {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+// 文档使用的字体链,按字符选择第一个包含它的字体
+pub struct PdfFonts<'a> {
+    pub measurer: TextMeasurer<'a>,
+    refs: Vec<IndirectFontRef>,
+}
+
+impl<'a> PdfFonts<'a> {
+    pub fn new(doc: &PdfDocumentReference, fonts: &'a [LoadedFont]) -> Result<Self, String> {
+        let data: Vec<&[u8]> = fonts.iter().map(|font| font.data.as_slice()).collect();
+        let measurer = TextMeasurer::new(&data)?;
+
+        let refs = fonts
+            .iter()
+            .map(|font| {
+                doc.add_external_font(std::io::Cursor::new(font.data.clone()))
+                    .map_err(|e| format!("Failed to load font {}: {}", font.name, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(PdfFonts { measurer, refs })
+    }
+
+    // 在指定位置输出一段文本 (坐标为基线起点)
+    pub fn use_text(&self, layer: &PdfLayerReference, text: &str, font_size: f32, x: Mm, y: Mm) {
+        layer.begin_text_section();
+        layer.set_text_cursor(x, y);
+        self.write_text(layer, text, font_size);
+        layer.end_text_section();
+    }
+
+    // 在当前文本段中接着输出文本,遇到其他字体的字符时切换字体
+    pub fn write_text(&self, layer: &PdfLayerReference, text: &str, font_size: f32) {
+        for (index, run) in self.measurer.font_runs(text) {
+            let font = &self.refs[index];
+            layer.set_font(font, font_size);
+            layer.write_text(run, font);
+        }
+    }
+}
+
+// 组成字体链: 用户指定的字体 (家族名或字体文件路径)、内置字体,都没有时使用系统字体;
+// 再为 text 中缺少的字符依次添加回退字体
+pub fn load_fonts(families: &[String], text: &str) -> Result<Vec<LoadedFont>, String> {
+    let mut fonts: Vec<LoadedFont> = Vec::new();
+
+    for family in families {
+        let font = load_family(family)?.ok_or_else(|| format!("Font not found: {}", family))?;
+        fonts.push(font);
+    }
+
+    if let Some(font) = load_bundled_font() {
+        fonts.push(font);
+    }
+
+    if fonts.is_empty() {
+        let font = FALLBACK_FAMILIES
+            .iter()
+            .find_map(|family| load_family(family).ok().flatten())
+            .or_else(|| system_fonts().iter().find_map(|file| load_path(&file.path, &file.full_name).ok()))
+            .ok_or("No usable font found, please install a TrueType font or choose a font file")?;
+        fonts.push(font);
+    }
+
+    let mut checked = HashSet::new();
+    for c in text.chars() {
+        if is_ignorable(c) || !checked.insert(c) || covers(&fonts, c) {
+            continue;
+        }
+
+        let font = find_fallback(&fonts, c)
+            .ok_or_else(|| format!("No installed font covers character '{}' (U+{:04X})", c, c as u32))?;
+        println!("字符 U+{:04X} 使用回退字体: {}", c as u32, font.name);
+        fonts.push(font);
+    }
+
+    Ok(fonts)
+}
+
+// 把嵌入的 TrueType 字体精简为页面中实际使用的字形,并同步裁剪宽度表和 ToUnicode
+pub fn subset_fonts(doc: &mut Document) -> Result<(), String> {
+    for (font_id, glyphs) in used_glyphs(doc) {
+        subset_font(doc, font_id, &glyphs)?;
+    }
+    Ok(())
+}
+
+// 各 Type0 字体在页面内容中使用的字形编号 (printpdf 生成的文档只在页面内容中写文本)
+fn used_glyphs(doc: &Document) -> BTreeMap<ObjectId, BTreeSet<u16>> {
+    let mut used: BTreeMap<ObjectId, BTreeSet<u16>> = BTreeMap::new();
+
+    for page_id in doc.get_pages().into_values() {
+        let mut fonts: BTreeMap<Vec<u8>, ObjectId> = BTreeMap::new();
+        let (resources, resource_ids) = doc.get_page_resources(page_id);
+        let resources = resources.into_iter().chain(resource_ids.iter().filter_map(|id| doc.get_dictionary(*id).ok()));
+        for resources in resources {
+            let Some(font_dict) = resources.get(b"Font").ok().and_then(|fonts| resolve_dict(doc, fonts)) else {
+                continue;
+            };
+            for (name, font) in font_dict.iter() {
+                let Ok(font_id) = font.as_reference() else {
+                    continue;
+                };
+                if is_embedded_type0(doc, font_id) {
+                    fonts.insert(name.clone(), font_id);
+                    used.entry(font_id).or_default();
+                }
+            }
+        }
+
+        let Some(content) = doc.get_page_content(page_id).ok().and_then(|data| Content::decode(&data).ok()) else {
+            continue;
+        };
+        let mut current = None;
+        for operation in &content.operations {
+            let strings: Vec<&Object> = match operation.operator.as_str() {
+                "Tf" => {
+                    current = operation.operands.first().and_then(|name| name.as_name().ok()).and_then(|name| fonts.get(name));
+                    continue;
+                }
+                "Tj" | "'" => operation.operands.iter().take(1).collect(),
+                "\"" => operation.operands.iter().skip(2).take(1).collect(),
+                "TJ" => operation.operands.first().and_then(|array| array.as_array().ok()).map(|array| array.iter().collect()).unwrap_or_default(),
+                _ => continue,
+            };
+            let Some(font_id) = current else {
+                continue;
+            };
+
+            let glyphs = used.entry(*font_id).or_default();
+            for string in strings {
+                if let Object::String(bytes, _) = string {
+                    glyphs.extend(bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])));
+                }
+            }
+        }
+    }
+
+    used
+}
+
+// 嵌入了 TrueType 字体程序的 Type0 字体
+fn is_embedded_type0(doc: &Document, font_id: ObjectId) -> bool {
+    font_file_ids(doc, font_id).is_some()
+}
+
+// (字体描述, 字体文件) 的对象编号
+fn font_file_ids(doc: &Document, font_id: ObjectId) -> Option<(ObjectId, ObjectId)> {
+    let font = doc.get_dictionary(font_id).ok()?;
+    if font.get(b"Subtype").and_then(Object::as_name).ok()? != b"Type0" {
+        return None;
+    }
+    let descendants = font.get(b"DescendantFonts").ok()?;
+    let cid_font = resolve_dict(doc, doc.dereference(descendants).ok()?.1.as_array().ok()?.first()?)?;
+    let descriptor_id = cid_font.get(b"FontDescriptor").ok()?.as_reference().ok()?;
+    let file_id = doc.get_dictionary(descriptor_id).ok()?.get(b"FontFile2").ok()?.as_reference().ok()?;
+    Some((descriptor_id, file_id))
+}
+
+// Type0 字体的 CID 字体字典,printpdf 直接写在 DescendantFonts 数组中
+fn cid_font_mut(doc: &mut Document, font_id: ObjectId) -> Option<&mut Dictionary> {
+    let descendants = doc.get_dictionary(font_id).ok()?.get(b"DescendantFonts").ok()?;
+    let first = doc.dereference(descendants).ok()?.1.as_array().ok()?.first()?;
+    if let Ok(cid_font_id) = first.as_reference() {
+        return doc.get_dictionary_mut(cid_font_id).ok();
+    }
+
+    let descendants = doc.get_dictionary_mut(font_id).ok()?.get_mut(b"DescendantFonts").ok()?;
+    descendants.as_array_mut().ok()?.first_mut()?.as_dict_mut().ok()
+}
+
+fn subset_font(doc: &mut Document, font_id: ObjectId, glyphs: &BTreeSet<u16>) -> Result<(), String> {
+    let Some((descriptor_id, file_id)) = font_file_ids(doc, font_id) else {
+        return Ok(());
+    };
+
+    let font_file = doc
+        .get_object_mut(file_id)
+        .and_then(Object::as_stream_mut)
+        .map_err(|e| format!("Failed to read embedded font: {}", e))?;
+    let data = if font_file.dict.has(b"Filter") {
+        font_file.decompressed_content().map_err(|e| format!("Failed to read embedded font: {}", e))?
+    } else {
+        font_file.content.clone()
+    };
+    let subset = font_subset::subset_glyphs(&data, glyphs)?;
+    font_file.dict.set("Length1", subset.len() as i64);
+    font_file.set_plain_content(subset);
+
+    // 子集字体名称加上 6 个大写字母的前缀
+    let tag = subset_tag(glyphs);
+    let rename = |name: &Object| -> Object {
+        let name = name.as_name().unwrap_or_default();
+        Object::Name(format!("{}+{}", tag, String::from_utf8_lossy(name)).into_bytes())
+    };
+
+    if let Ok(descriptor) = doc.get_dictionary_mut(descriptor_id) {
+        if let Ok(name) = descriptor.get(b"FontName") {
+            let name = rename(name);
+            descriptor.set("FontName", name);
+        }
+    }
+    if let Some(cid_font) = cid_font_mut(doc, font_id) {
+        if let Ok(name) = cid_font.get(b"BaseFont") {
+            let name = rename(name);
+            cid_font.set("BaseFont", name);
+        }
+        if let Ok(widths) = cid_font.get(b"W").and_then(Object::as_array) {
+            let widths = subset_widths(widths, glyphs);
+            cid_font.set("W", widths);
+        }
+    }
+
+    let to_unicode_id = doc.get_dictionary_mut(font_id).ok().and_then(|font| {
+        if let Ok(name) = font.get(b"BaseFont") {
+            let name = rename(name);
+            font.set("BaseFont", name);
+        }
+        font.get(b"ToUnicode").and_then(Object::as_reference).ok()
+    });
+    if let Some(Ok(to_unicode)) = to_unicode_id.map(|id| doc.get_object_mut(id).and_then(Object::as_stream_mut)) {
+        let cmap = to_unicode.decompressed_content().unwrap_or_else(|_| to_unicode.content.clone());
+        let cmap = subset_to_unicode(&String::from_utf8_lossy(&cmap), glyphs);
+        to_unicode.set_plain_content(cmap.into_bytes());
+    }
+
+    Ok(())
+}
+
+// 只保留使用到的字形宽度,连续的字形编号合并为一组: [起始编号 [宽度...] ...]
+fn subset_widths(widths: &[Object], glyphs: &BTreeSet<u16>) -> Object {
+    let mut kept: BTreeMap<i64, Object> = BTreeMap::new();
+    for pair in widths.chunks(2) {
+        let (Some(Ok(start)), Some(Ok(values))) = (pair.first().map(Object::as_i64), pair.get(1).map(Object::as_array)) else {
+            continue;
+        };
+        for (i, width) in values.iter().enumerate() {
+            let gid = start + i as i64;
+            if u16::try_from(gid).is_ok_and(|gid| glyphs.contains(&gid)) {
+                kept.insert(gid, width.clone());
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut group: Vec<Object> = Vec::new();
+    let mut group_start = 0;
+    for (gid, width) in kept {
+        if !group.is_empty() && gid != group_start + group.len() as i64 {
+            result.push(Object::Integer(group_start));
+            result.push(Object::Array(std::mem::take(&mut group)));
+        }
+        if group.is_empty() {
+            group_start = gid;
+        }
+        group.push(width);
+    }
+    if !group.is_empty() {
+        result.push(Object::Integer(group_start));
+        result.push(Object::Array(group));
+    }
+    Object::Array(result)
+}
+
+// 重建 ToUnicode 的 bfchar 段,只保留使用到的字形; 同时把增补平面字符改写为 UTF-16 代理对
+fn subset_to_unicode(cmap: &str, glyphs: &BTreeSet<u16>) -> String {
+    let (Some(first), Some(last)) = (cmap.find("beginbfchar"), cmap.rfind("endbfchar")) else {
+        return cmap.to_string();
+    };
+    let header_end = cmap[..first].rfind('\n').map_or(0, |i| i + 1);
+    let footer_start = last + "endbfchar".len();
+
+    let mut entries = Vec::new();
+    for line in cmap[header_end..footer_start].lines() {
+        let mut parts = line.split_whitespace().map(|part| part.trim_start_matches('<').trim_end_matches('>'));
+        let (Some(gid), Some(unicode)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let (Ok(gid), Ok(unicode)) = (u16::from_str_radix(gid, 16), u32::from_str_radix(unicode, 16)) else {
+            continue;
+        };
+        if let Some(c) = char::from_u32(unicode).filter(|_| unicode != 0 && glyphs.contains(&gid)) {
+            let utf16: String = c.encode_utf16(&mut [0; 2]).iter().map(|unit| format!("{:04x}", unit)).collect();
+            entries.push(format!("<{:04x}> <{}>", gid, utf16));
+        }
+    }
+
+    let mut result = cmap[..header_end].to_string();
+    for block in entries.chunks(TO_UNICODE_BLOCK) {
+        result.push_str(&format!("{} beginbfchar\n", block.len()));
+        for entry in block {
+            result.push_str(entry);
+            result.push('\n');
+        }
+        result.push_str("endbfchar\n");
+    }
+    result.push_str(cmap[footer_start..].trim_start());
+    result
+}
+
+// 由字形集合生成子集字体名称前缀
+fn subset_tag(glyphs: &BTreeSet<u16>) -> String {
+    let hash = glyphs
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, &gid| (hash ^ gid as u64).wrapping_mul(0x0100_0000_01b3));
+    (0..6).map(|i| (b'A' + ((hash >> (i * 8)) % 26) as u8) as char).collect()
+}
+
+// 系统中可用字体的家族名,供界面选择
+pub fn list_font_families() -> Vec<String> {
+    let mut families: Vec<String> = system_fonts().iter().map(|file| file.family.clone()).collect();
+    families.sort_by_key(|family| family.to_lowercase());
+    families.dedup();
+    families
+}
+
+fn covers(fonts: &[LoadedFont], c: char) -> bool {
+    fonts
+        .iter()
+        .any(|font| Face::parse(&font.data, 0).map(|face| face_covers(&face, c)).unwrap_or(false))
+}
+
+// 先在常用回退字体中查找,再查找其他系统字体; 只读取 cmap 包含该字符的字体文件
+fn find_fallback(fonts: &[LoadedFont], c: char) -> Option<LoadedFont> {
+    let preferred = FALLBACK_FAMILIES.iter().filter_map(|family| find_family(family));
+    let others = system_fonts().iter();
+
+    preferred
+        .chain(others)
+        .filter(|file| file.maps(c) && !fonts.iter().any(|font| font.name == file.full_name))
+        .find_map(|file| {
+            let font = load_path(&file.path, &file.full_name).ok()?;
+            covers(std::slice::from_ref(&font), c).then_some(font)
+        })
+}
+
+// 按家族名或完整名称查找字体,同一家族优先使用常规字重;
+// 参数是已存在的文件路径时直接读取该文件
+fn load_family(family: &str) -> Result<Option<LoadedFont>, String> {
+    let path = Path::new(family);
+    if path.is_file() {
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        return load_path(path, &name).map(Some);
+    }
+
+    match find_family(family) {
+        Some(file) => load_path(&file.path, &file.full_name).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn find_family(family: &str) -> Option<&'static FontFile> {
+    let family = family.to_lowercase();
+    system_fonts()
+        .iter()
+        .filter(|file| file.family.to_lowercase() == family || file.full_name.to_lowercase() == family)
+        .min_by_key(|file| file.style_rank)
+}
+
+fn load_path(path: &Path, name: &str) -> Result<LoadedFont, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read font file {:?}: {}", path, e))?;
+    let face = Face::parse(&data, 0).map_err(|e| format!("Failed to parse font {:?}: {}", path, e))?;
+    // printpdf 只能嵌入 TrueType 轮廓 (glyf) 的字体
+    if face.tables().glyf.is_none() {
+        return Err(format!("Unsupported font {:?}: only TrueType outline fonts can be embedded", path));
+    }
+
+    Ok(LoadedFont {
+        name: name.to_string(),
+        data,
+    })
+}
+
+// 内置字体 (在编译后的可执行文件旁边的assets目录),不存在时返回 None
+fn load_bundled_font() -> Option<LoadedFont> {
+    let font_path = if cfg!(debug_assertions) {
+        // 开发模式：使用src-tauri/assets下的字体
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(BUNDLED_FONT)
+    } else {
+        // 生产模式：使用可执行文件旁边的assets目录
+        std::env::current_exe().ok()?.parent()?.join(BUNDLED_FONT)
+    };
+
+    if !font_path.is_file() {
+        return None;
+    }
+    match load_path(&font_path, "AlibabaPuHuiTi") {
+        Ok(font) => Some(font),
+        Err(e) => {
+            println!("内置字体不可用: {}", e);
+            None
+        }
+    }
+}
+
+// 扫描系统字体目录,结果 (名称和字符覆盖范围,不含字体数据) 在进程内缓存
+fn system_fonts() -> &'static [FontFile] {
+    static FONTS: OnceLock<Vec<FontFile>> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        let mut paths = Vec::new();
+        for dir in system_font_dirs() {
+            collect_font_files(&dir, &mut paths);
+        }
+        paths.sort();
+        paths.dedup();
+
+        let fonts: Vec<FontFile> = paths.into_iter().filter_map(read_font_file).collect();
+        println!("找到 {} 个系统字体", fonts.len());
+        fonts
+    })
+}
+
+fn collect_font_files(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // 不跟随目录符号链接,避免循环
+        if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+            collect_font_files(&path, paths);
+        } else if is_font_file(&path) {
+            paths.push(path);
+        }
+    }
+}
+
+// 字体集合 (.ttc) 无法作为单个字体嵌入,只读取 .ttf / .otf
+fn is_font_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| matches!(ext.to_lowercase().as_str(), "ttf" | "otf"))
+        .unwrap_or(false)
+}
+
+fn read_font_file(path: PathBuf) -> Option<FontFile> {
+    let data = fs::read(&path).ok()?;
+    let face = Face::parse(&data, 0).ok()?;
+    face.tables().glyf?;
+
+    let family = font_name(&face, name_id::TYPOGRAPHIC_FAMILY).or_else(|| font_name(&face, name_id::FAMILY))?;
+    let full_name = font_name(&face, name_id::FULL_NAME).unwrap_or_else(|| family.clone());
+    let styled = face.is_bold() || face.is_italic() || face.is_oblique();
+    let style_rank = styled as u32 * 1000 + (face.weight().to_number() as i32 - 400).unsigned_abs();
+
+    Some(FontFile {
+        path,
+        family,
+        full_name,
+        style_rank,
+        coverage: cmap_coverage(&face),
+    })
+}
+
+// 把 Unicode cmap 子表中的码位合并为连续区间
+fn cmap_coverage(face: &Face) -> Vec<(u32, u32)> {
+    let mut codepoints = Vec::new();
+    if let Some(cmap) = face.tables().cmap {
+        for subtable in cmap.subtables {
+            if subtable.is_unicode() {
+                subtable.codepoints(|codepoint| codepoints.push(codepoint));
+            }
+        }
+    }
+    codepoints.sort_unstable();
+    codepoints.dedup();
+
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for codepoint in codepoints {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == codepoint => *end = codepoint,
+            _ => ranges.push((codepoint, codepoint)),
+        }
+    }
+    ranges
+}
+
+// 读取 name 表中的名称,优先使用英文名称
+fn font_name(face: &Face, id: u16) -> Option<String> {
+    let names: Vec<_> = face
+        .names()
+        .into_iter()
+        .filter(|name| name.name_id == id && name.is_unicode())
+        .collect();
+
+    names
+        .iter()
+        .find(|name| name.language_id == 0x0409)
+        .or_else(|| names.first())
+        .and_then(|name| name.to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn system_font_dirs() -> Vec<PathBuf> {
+    let windows_dir = std::env::var_os("WINDIR").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("C:\\Windows"));
+    let mut font_dirs = vec![windows_dir.join("Fonts")];
+    // 仅为当前用户安装的字体
+    if let Some(local) = dirs::data_local_dir() {
+        font_dirs.push(local.join("Microsoft").join("Windows").join("Fonts"));
+    }
+    font_dirs
+}
+
+#[cfg(target_os = "macos")]
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut font_dirs = vec![PathBuf::from("/System/Library/Fonts"), PathBuf::from("/Library/Fonts")];
+    if let Some(dir) = dirs::font_dir() {
+        font_dirs.push(dir);
+    }
+    font_dirs
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut font_dirs = fontconfig_dirs();
+    font_dirs.push(PathBuf::from("/usr/share/fonts"));
+    font_dirs.push(PathBuf::from("/usr/local/share/fonts"));
+    if let Some(dir) = dirs::font_dir() {
+        font_dirs.push(dir);
+    }
+    if let Some(home) = dirs::home_dir() {
+        font_dirs.push(home.join(".fonts"));
+    }
+    font_dirs
+}
+
+// 读取 fontconfig 配置中 <dir> 指定的字体目录
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn fontconfig_dirs() -> Vec<PathBuf> {
+    let mut config_files = vec![PathBuf::from("/etc/fonts/fonts.conf")];
+    if let Ok(entries) = fs::read_dir("/etc/fonts/conf.d") {
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "conf"))
+            .collect();
+        files.sort();
+        config_files.extend(files);
+    }
+
+    let mut font_dirs = Vec::new();
+    for file in config_files {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+
+        let mut rest = content.as_str();
+        while let Some(start) = rest.find("<dir") {
+            rest = &rest[start + "<dir".len()..];
+            let Some(tag_end) = rest.find('>') else {
+                break;
+            };
+            // 跳过 <dirname> 等其他标签和空标签
+            let attributes = &rest[..tag_end];
+            if !(attributes.is_empty() || attributes.starts_with(char::is_whitespace)) || attributes.ends_with('/') {
+                continue;
+            }
+            let Some(end) = rest.find("</dir>") else {
+                break;
+            };
+
+            let value = rest[tag_end + 1..end].trim();
+            if let Some(dir) = resolve_fontconfig_dir(value, attributes) {
+                font_dirs.push(dir);
+            }
+            rest = &rest[end..];
+        }
+    }
+    font_dirs
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn resolve_fontconfig_dir(value: &str, attributes: &str) -> Option<PathBuf> {
+    if attributes.contains("prefix=\"xdg\"") {
+        return dirs::data_dir().map(|dir| dir.join(value));
+    }
+    if let Some(relative) = value.strip_prefix("~/") {
+        return dirs::home_dir().map(|home| home.join(relative));
+    }
+
+    let path = PathBuf::from(value);
+    path.is_absolute().then_some(path)
+}
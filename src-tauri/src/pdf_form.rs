@@ -0,0 +1,830 @@
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId};
+use printpdf::path::PaintMode;
+use printpdf::{Cmyk, Color, Greyscale, Mm, PdfLayerReference, Rgb};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+use crate::pdf_fonts::{self, PdfFonts};
+use crate::pdf_generator::{self, PdfBuilder};
+use crate::pdf_overlay;
+use crate::pdf_text::{wrap_runs, PT_PER_MM};
+use crate::pdf_utils::{self, decode_text_string, resolve, resolve_dict};
+
+// 域标志 (Ff)
+const READ_ONLY: i64 = 1;
+const REQUIRED: i64 = 1 << 1;
+const MULTILINE: i64 = 1 << 12;
+const PASSWORD: i64 = 1 << 13;
+const RADIO: i64 = 1 << 15;
+const PUSHBUTTON: i64 = 1 << 16;
+const COMBO: i64 = 1 << 17;
+const EDIT: i64 = 1 << 18;
+const MULTI_SELECT: i64 = 1 << 21;
+const COMB: i64 = 1 << 24;
+// 注释标志 (F): 隐藏、不显示
+const HIDDEN_ANNOTATION: i64 = 1 << 1 | 1 << 5;
+
+// 域树和父级链的最大深度,防止循环引用
+const MAX_FIELD_DEPTH: usize = 32;
+// 控件内文字与边框的距离 (pt)
+const FIELD_PADDING: f32 = 2.0;
+// 自动字号 (DA 中字号为 0) 的上限和下限
+const AUTO_FONT_SIZE: f32 = 12.0;
+const MIN_FONT_SIZE: f32 = 4.0;
+// 多行文本和列表的行距,字号的倍数
+const FIELD_LINE_SPACING: f32 = 1.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormFieldType {
+    Text,
+    Checkbox,
+    Radio,
+    ComboBox,
+    ListBox,
+    Button,
+    Signature,
+}
+
+// 表单域信息,value 的格式与填写时相同,可修改后直接传给 fill_form
+#[derive(Debug, Clone, Serialize)]
+pub struct FormField {
+    // 完整名称,层级之间用 "." 连接
+    pub name: String,
+    pub field_type: FormFieldType,
+    // 文本和下拉框为字符串,复选框为布尔值 (有多个选中状态时为状态名),单选为状态名,
+    // 多选列表为数组,未填写为 null
+    pub value: Value,
+    // 下拉框和列表为选项值,复选框和单选为选中状态名
+    pub options: Vec<String>,
+    pub read_only: bool,
+    pub required: bool,
+    pub max_length: Option<u32>,
+    // 第一个控件所在页 (从 1 开始)
+    pub page: Option<u32>,
+}
+
+// 表单中的终端域及其控件 (控件可能就是域本身)
+struct Field {
+    id: ObjectId,
+    name: String,
+    field_type: FormFieldType,
+    flags: i64,
+    widgets: Vec<ObjectId>,
+}
+
+// 控件外观中显示的内容
+#[derive(Clone)]
+enum FieldText {
+    Line(String),
+    Multiline(String),
+    // 按 MaxLen 等分格子,每格一个字符
+    Comb(String, usize),
+    List { options: Vec<String>, selected: Vec<usize> },
+}
+
+// 需要生成外观的控件
+struct Appearance {
+    widget: ObjectId,
+    width: f32,
+    height: f32,
+    font_size: f32,
+    color: Color,
+    background: Option<Color>,
+    border: Option<(Color, f32)>,
+    // 0 左对齐、1 居中、2 右对齐
+    quadding: i64,
+    text: FieldText,
+}
+
+// 列出 PDF 中的表单域
+pub fn list_form_fields(path: &str) -> Result<Vec<FormField>, String> {
+    let doc = pdf_utils::load_pdf(path)?;
+    let pages = widget_pages(&doc);
+
+    let fields = collect_fields(&doc)
+        .iter()
+        .map(|field| FormField {
+            name: field.name.clone(),
+            field_type: field.field_type,
+            value: field_value(&doc, field),
+            options: field_options(&doc, field),
+            read_only: field.flags & READ_ONLY != 0,
+            required: field.flags & REQUIRED != 0,
+            max_length: max_length(&doc, field).map(|len| len as u32),
+            page: field.widgets.iter().find_map(|widget| pages.get(widget).copied()),
+        })
+        .collect::<Vec<_>>();
+
+    println!("读取表单: {}, {} 个字段", path, fields.len());
+    Ok(fields)
+}
+
+// 按 JSON 对象 (域名称 -> 值) 填写表单并重新生成外观;flatten 时把控件外观画到页面上并移除表单
+pub fn fill_form(path: &str, values: &Map<String, Value>, flatten: bool, output_path: &str) -> Result<(), String> {
+    let mut doc = pdf_utils::load_pdf(path)?;
+    pdf_utils::flatten_inherited_page_attributes(&mut doc);
+
+    let fields = collect_fields(&doc);
+    if fields.is_empty() {
+        return Err("PDF has no form fields".to_string());
+    }
+
+    let mut changed: HashSet<ObjectId> = HashSet::new();
+    for (name, value) in values {
+        let field = fields
+            .iter()
+            .find(|field| field.name == *name)
+            .ok_or_else(|| format!("Unknown form field: {}", name))?;
+        set_field_value(&mut doc, field, value)?;
+        changed.insert(field.id);
+    }
+
+    // 文本和选择域需要按新值生成外观;拍平时没有外观的域也要生成
+    let redraw: Vec<&Field> = fields
+        .iter()
+        .filter(|field| matches!(field.field_type, FormFieldType::Text | FormFieldType::ComboBox | FormFieldType::ListBox))
+        .filter(|field| {
+            changed.contains(&field.id) || (flatten && field.widgets.iter().any(|&widget| !has_appearance(&doc, widget)))
+        })
+        .collect();
+    update_appearances(&mut doc, &redraw)?;
+
+    if flatten {
+        flatten_fields(&mut doc, &fields)?;
+        doc.prune_objects();
+    }
+
+    doc.compress();
+    pdf_utils::save_pdf(&mut doc, output_path)?;
+    println!("填写表单: {} 个字段{}", values.len(), if flatten { ", 已拍平" } else { "" });
+    Ok(())
+}
+
+fn collect_fields(doc: &Document) -> Vec<Field> {
+    let roots = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").ok())
+        .and_then(|form| resolve_dict(doc, form))
+        .and_then(|form| form.get(b"Fields").ok())
+        .and_then(|fields| resolve(doc, fields))
+        .and_then(|fields| fields.as_array().ok());
+
+    let mut fields = Vec::new();
+    for root in roots.into_iter().flatten() {
+        if let Ok(id) = root.as_reference() {
+            collect_field(doc, id, "", &mut fields, 0);
+        }
+    }
+    fields
+}
+
+// 子节点有名称 (T) 的是子域,没有名称的是控件
+fn collect_field(doc: &Document, id: ObjectId, parent_name: &str, fields: &mut Vec<Field>, depth: usize) {
+    let Ok(dict) = doc.get_dictionary(id) else {
+        return;
+    };
+    if depth > MAX_FIELD_DEPTH {
+        return;
+    }
+
+    let partial = dict.get(b"T").and_then(Object::as_str).map(decode_text_string).unwrap_or_default();
+    let name = match (parent_name.is_empty(), partial.is_empty()) {
+        (_, true) => parent_name.to_string(),
+        (true, false) => partial,
+        (false, false) => format!("{}.{}", parent_name, partial),
+    };
+
+    let kids: Vec<ObjectId> = dict
+        .get(b"Kids")
+        .ok()
+        .and_then(|kids| resolve(doc, kids))
+        .and_then(|kids| kids.as_array().ok())
+        .map(|kids| kids.iter().filter_map(|kid| kid.as_reference().ok()).collect())
+        .unwrap_or_default();
+    let (children, widgets): (Vec<ObjectId>, Vec<ObjectId>) =
+        kids.into_iter().partition(|&kid| doc.get_dictionary(kid).is_ok_and(|kid| kid.has(b"T")));
+
+    if !children.is_empty() {
+        for child in children {
+            collect_field(doc, child, &name, fields, depth + 1);
+        }
+        return;
+    }
+
+    let Some(field_type) = inherited(doc, id, b"FT").and_then(|ft| ft.as_name().ok()) else {
+        return;
+    };
+    let flags = inherited(doc, id, b"Ff").and_then(|ff| ff.as_i64().ok()).unwrap_or(0);
+    let field_type = match field_type {
+        b"Tx" => FormFieldType::Text,
+        b"Btn" if flags & PUSHBUTTON != 0 => FormFieldType::Button,
+        b"Btn" if flags & RADIO != 0 => FormFieldType::Radio,
+        b"Btn" => FormFieldType::Checkbox,
+        b"Ch" if flags & COMBO != 0 => FormFieldType::ComboBox,
+        b"Ch" => FormFieldType::ListBox,
+        b"Sig" => FormFieldType::Signature,
+        _ => return,
+    };
+
+    fields.push(Field {
+        id,
+        name,
+        field_type,
+        flags,
+        widgets: if widgets.is_empty() { vec![id] } else { widgets },
+    });
+}
+
+// 读取可从父域继承的属性
+fn inherited<'a>(doc: &'a Document, id: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut dict = doc.get_dictionary(id).ok()?;
+    for _ in 0..MAX_FIELD_DEPTH {
+        if let Ok(value) = dict.get(key) {
+            return resolve(doc, value);
+        }
+        dict = dict.get(b"Parent").ok().and_then(|parent| resolve_dict(doc, parent))?;
+    }
+    None
+}
+
+// 控件所在页码 (从 1 开始)
+fn widget_pages(doc: &Document) -> HashMap<ObjectId, u32> {
+    let mut pages = HashMap::new();
+    for (number, page_id) in doc.get_pages() {
+        for annot in page_annotations(doc, page_id) {
+            pages.entry(annot).or_insert(number);
+        }
+    }
+    pages
+}
+
+fn page_annotations(doc: &Document, page_id: ObjectId) -> Vec<ObjectId> {
+    doc.get_dictionary(page_id)
+        .ok()
+        .and_then(|page| page.get(b"Annots").ok())
+        .and_then(|annots| resolve(doc, annots))
+        .and_then(|annots| annots.as_array().ok())
+        .map(|annots| annots.iter().filter_map(|annot| annot.as_reference().ok()).collect())
+        .unwrap_or_default()
+}
+
+fn field_value(doc: &Document, field: &Field) -> Value {
+    let value = inherited(doc, field.id, b"V");
+    match field.field_type {
+        FormFieldType::Text | FormFieldType::ComboBox => value.and_then(object_text).map_or(Value::Null, Value::String),
+        FormFieldType::Checkbox => match value.and_then(|v| v.as_name_str().ok()).filter(|state| *state != "Off") {
+            Some(state) if field_options(doc, field).len() > 1 => Value::String(state.to_string()),
+            state => Value::Bool(state.is_some()),
+        },
+        FormFieldType::Radio => value
+            .and_then(|v| v.as_name_str().ok())
+            .filter(|state| *state != "Off")
+            .map_or(Value::Null, |state| Value::String(state.to_string())),
+        FormFieldType::ListBox => match value {
+            Some(Object::Array(items)) => Value::Array(items.iter().filter_map(object_text).map(Value::String).collect()),
+            Some(value) => match object_text(value) {
+                Some(text) if field.flags & MULTI_SELECT != 0 => Value::Array(vec![Value::String(text)]),
+                Some(text) => Value::String(text),
+                None => Value::Null,
+            },
+            None => Value::Null,
+        },
+        FormFieldType::Button | FormFieldType::Signature => Value::Null,
+    }
+}
+
+fn object_text(object: &Object) -> Option<String> {
+    match object {
+        Object::String(bytes, _) => Some(decode_text_string(bytes)),
+        Object::Name(name) => Some(String::from_utf8_lossy(name).to_string()),
+        _ => None,
+    }
+}
+
+fn field_options(doc: &Document, field: &Field) -> Vec<String> {
+    match field.field_type {
+        FormFieldType::Checkbox | FormFieldType::Radio => on_states(doc, field),
+        FormFieldType::ComboBox | FormFieldType::ListBox => {
+            choice_options(doc, field).into_iter().map(|(value, _)| value).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+// 下拉框和列表的选项 (选项值, 显示文字)
+fn choice_options(doc: &Document, field: &Field) -> Vec<(String, String)> {
+    let Some(Object::Array(options)) = inherited(doc, field.id, b"Opt") else {
+        return Vec::new();
+    };
+    options
+        .iter()
+        .filter_map(|option| match resolve(doc, option)? {
+            Object::Array(pair) => {
+                let value = object_text(pair.first()?)?;
+                let label = pair.get(1).and_then(object_text).unwrap_or_else(|| value.clone());
+                Some((value, label))
+            }
+            option => object_text(option).map(|text| (text.clone(), text)),
+        })
+        .collect()
+}
+
+// 复选框和单选按钮的选中状态名,取自控件外观中 Off 以外的状态
+fn on_states(doc: &Document, field: &Field) -> Vec<String> {
+    let mut states: Vec<String> = Vec::new();
+    for &widget in &field.widgets {
+        for state in widget_states(doc, widget) {
+            if state != "Off" && !states.contains(&state) {
+                states.push(state);
+            }
+        }
+    }
+    states
+}
+
+fn widget_states(doc: &Document, widget: ObjectId) -> Vec<String> {
+    doc.get_dictionary(widget)
+        .ok()
+        .and_then(|widget| widget.get(b"AP").ok())
+        .and_then(|ap| resolve_dict(doc, ap))
+        .and_then(|ap| ap.get(b"N").ok())
+        .and_then(|normal| resolve_dict(doc, normal))
+        .map(|normal| normal.iter().map(|(state, _)| String::from_utf8_lossy(state).to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn max_length(doc: &Document, field: &Field) -> Option<usize> {
+    match field.field_type {
+        FormFieldType::Text => inherited(doc, field.id, b"MaxLen")
+            .and_then(|len| len.as_i64().ok())
+            .filter(|len| *len > 0)
+            .map(|len| len as usize),
+        _ => None,
+    }
+}
+
+fn has_appearance(doc: &Document, widget: ObjectId) -> bool {
+    doc.get_dictionary(widget)
+        .ok()
+        .and_then(|widget| widget.get(b"AP").ok())
+        .and_then(|ap| resolve_dict(doc, ap))
+        .is_some_and(|ap| ap.has(b"N"))
+}
+
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+fn set_field_value(doc: &mut Document, field: &Field, value: &Value) -> Result<(), String> {
+    let invalid = || format!("Invalid value for form field {}: {}", field.name, value);
+
+    match field.field_type {
+        FormFieldType::Text => {
+            let text = match value {
+                Value::Null => None,
+                value => Some(value_text(value).ok_or_else(invalid)?),
+            };
+            if let (Some(text), Some(max)) = (&text, max_length(doc, field)) {
+                if text.chars().count() > max {
+                    return Err(format!("Value for form field {} exceeds {} characters", field.name, max));
+                }
+            }
+            set_value(doc, field.id, text.map(|text| pdf_utils::text_string(&text)))
+        }
+        FormFieldType::Checkbox | FormFieldType::Radio => {
+            let states = on_states(doc, field);
+            let state = match value {
+                Value::Bool(true) if field.field_type == FormFieldType::Checkbox => {
+                    Some(states.first().cloned().ok_or_else(|| format!("Checkbox {} has no on state", field.name))?)
+                }
+                Value::Bool(false) | Value::Null => None,
+                Value::String(state) if state == "Off" => None,
+                Value::String(state) if states.contains(state) => Some(state.clone()),
+                _ => return Err(format!("{} (expected one of: {})", invalid(), states.join(", "))),
+            };
+            let state = state.unwrap_or_else(|| "Off".to_string());
+
+            // 每个控件显示自己拥有的状态,其余显示 Off
+            for &widget in &field.widgets {
+                let shown = if widget_states(doc, widget).contains(&state) { state.as_str() } else { "Off" };
+                if let Ok(widget) = doc.get_dictionary_mut(widget) {
+                    widget.set("AS", Object::Name(shown.as_bytes().to_vec()));
+                }
+            }
+            set_value(doc, field.id, Some(Object::Name(state.into_bytes())))
+        }
+        FormFieldType::ComboBox | FormFieldType::ListBox => {
+            let selected: Vec<String> = match value {
+                Value::Null => Vec::new(),
+                Value::Array(items) => items.iter().map(value_text).collect::<Option<_>>().ok_or_else(invalid)?,
+                value => vec![value_text(value).ok_or_else(invalid)?],
+            };
+            if selected.len() > 1 && (field.field_type == FormFieldType::ComboBox || field.flags & MULTI_SELECT == 0) {
+                return Err(format!("Form field {} accepts a single value", field.name));
+            }
+
+            let options: Vec<String> = choice_options(doc, field).into_iter().map(|(value, _)| value).collect();
+            let custom_allowed = field.field_type == FormFieldType::ComboBox && field.flags & EDIT != 0;
+            if let Some(unknown) = selected.iter().find(|value| !options.contains(value) && !custom_allowed) {
+                return Err(format!(
+                    "Invalid value for form field {}: {} (expected one of: {})",
+                    field.name,
+                    unknown,
+                    options.join(", ")
+                ));
+            }
+
+            // 列表同时记录选中项的下标
+            let mut indices: Vec<i64> =
+                selected.iter().filter_map(|value| options.iter().position(|o| o == value)).map(|i| i as i64).collect();
+            indices.sort_unstable();
+            if let Ok(dict) = doc.get_dictionary_mut(field.id) {
+                if field.field_type == FormFieldType::ListBox && !indices.is_empty() {
+                    dict.set("I", indices.into_iter().map(Object::Integer).collect::<Vec<_>>());
+                } else {
+                    dict.remove(b"I");
+                }
+            }
+
+            let value = match selected.as_slice() {
+                [] => None,
+                [single] => Some(pdf_utils::text_string(single)),
+                multiple => Some(Object::Array(multiple.iter().map(|value| pdf_utils::text_string(value)).collect())),
+            };
+            set_value(doc, field.id, value)
+        }
+        FormFieldType::Button | FormFieldType::Signature => {
+            Err(format!("Form field {} cannot be filled", field.name))
+        }
+    }
+}
+
+fn set_value(doc: &mut Document, field_id: ObjectId, value: Option<Object>) -> Result<(), String> {
+    let dict = doc.get_dictionary_mut(field_id).map_err(|e| format!("Failed to read form field: {}", e))?;
+    match value {
+        Some(value) => dict.set("V", value),
+        None => {
+            dict.remove(b"V");
+        }
+    }
+    Ok(())
+}
+
+// 控件的矩形区域 (左, 下, 右, 上)
+fn widget_rect(doc: &Document, widget: ObjectId) -> Option<[f32; 4]> {
+    let rect = doc.get_dictionary(widget).ok()?.get(b"Rect").ok()?;
+    let values: Vec<f32> = resolve(doc, rect)?.as_array().ok()?.iter().filter_map(|v| v.as_float().ok()).collect();
+    let [x1, y1, x2, y2] = values[..] else {
+        return None;
+    };
+    Some([x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)])
+}
+
+// 用 printpdf 绘制控件外观 (每个控件一页),再作为表单 XObject 设为控件的正常外观
+fn update_appearances(doc: &mut Document, fields: &[&Field]) -> Result<(), String> {
+    let appearances: Vec<Appearance> =
+        fields.iter().flat_map(|field| field_appearances(doc, field)).collect();
+    if appearances.is_empty() {
+        return Ok(());
+    }
+
+    let mut required = String::from("*");
+    for appearance in &appearances {
+        match &appearance.text {
+            FieldText::Line(text) | FieldText::Multiline(text) | FieldText::Comb(text, _) => required.push_str(text),
+            FieldText::List { options, .. } => options.iter().for_each(|option| required.push_str(option)),
+        }
+    }
+    let font_files = pdf_fonts::load_fonts(&[], &required)?;
+
+    let builder = PdfBuilder::new("Form", false);
+    let fonts = PdfFonts::new(&builder.doc, &font_files)?;
+    for appearance in &appearances {
+        let (page, layer) =
+            builder.doc.add_page(Mm(appearance.width / PT_PER_MM), Mm(appearance.height / PT_PER_MM), "Layer 1");
+        let layer = builder.doc.get_page(page).get_layer(layer);
+        draw_appearance(&layer, appearance, &fonts);
+    }
+    drop(fonts);
+
+    let overlay = pdf_generator::finish_document(builder)?;
+    let forms = pdf_overlay::import_pages_as_forms(doc, overlay)?;
+    for (appearance, form) in appearances.iter().zip(forms) {
+        if let Ok(widget) = doc.get_dictionary_mut(appearance.widget) {
+            widget.set("AP", Dictionary::from_iter(vec![("N", Object::Reference(form))]));
+        }
+    }
+    Ok(())
+}
+
+fn field_appearances(doc: &Document, field: &Field) -> Vec<Appearance> {
+    let form = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").ok())
+        .and_then(|form| resolve_dict(doc, form));
+    let da = inherited(doc, field.id, b"DA")
+        .or_else(|| form.and_then(|form| form.get(b"DA").ok()))
+        .and_then(object_text)
+        .unwrap_or_default();
+    let (font_size, color) = parse_default_appearance(&da);
+    let quadding = inherited(doc, field.id, b"Q")
+        .or_else(|| form.and_then(|form| form.get(b"Q").ok()))
+        .and_then(|q| q.as_i64().ok())
+        .unwrap_or(0);
+
+    let value = field_value(doc, field);
+    let text = match field.field_type {
+        FormFieldType::Text => {
+            let text = value.as_str().unwrap_or_default();
+            let text = if field.flags & PASSWORD != 0 { "*".repeat(text.chars().count()) } else { text.to_string() };
+            match max_length(doc, field) {
+                Some(cells) if field.flags & COMB != 0 => FieldText::Comb(text, cells),
+                _ if field.flags & MULTILINE != 0 => FieldText::Multiline(text),
+                _ => FieldText::Line(text),
+            }
+        }
+        FormFieldType::ComboBox => {
+            // 显示选中项的显示文字
+            let selected = value.as_str().unwrap_or_default();
+            let options = choice_options(doc, field);
+            let label = options.iter().find(|(value, _)| value == selected).map_or(selected, |(_, label)| label);
+            FieldText::Line(label.to_string())
+        }
+        _ => {
+            let selected: Vec<&str> = match &value {
+                Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+                Value::String(value) => vec![value.as_str()],
+                _ => Vec::new(),
+            };
+            let options = choice_options(doc, field);
+            FieldText::List {
+                selected: (0..options.len()).filter(|&i| selected.contains(&options[i].0.as_str())).collect(),
+                options: options.into_iter().map(|(_, label)| label).collect(),
+            }
+        }
+    };
+
+    field
+        .widgets
+        .iter()
+        .filter_map(|&widget| {
+            let [x1, y1, x2, y2] = widget_rect(doc, widget)?;
+            if x2 - x1 < 1.0 || y2 - y1 < 1.0 {
+                return None;
+            }
+
+            let dict = doc.get_dictionary(widget).ok()?;
+            let characteristics = dict.get(b"MK").ok().and_then(|mk| resolve_dict(doc, mk));
+            let mk_color = |key: &[u8]| {
+                characteristics.and_then(|mk| mk.get(key).ok()).and_then(|color| resolve(doc, color)).and_then(parse_color)
+            };
+            let border_width = dict
+                .get(b"BS")
+                .ok()
+                .and_then(|bs| resolve_dict(doc, bs))
+                .and_then(|bs| bs.get(b"W").ok())
+                .and_then(|w| w.as_float().ok())
+                .unwrap_or(1.0);
+
+            Some(Appearance {
+                widget,
+                width: x2 - x1,
+                height: y2 - y1,
+                font_size,
+                color: color.clone(),
+                background: mk_color(b"BG"),
+                border: mk_color(b"BC").filter(|_| border_width > 0.0).map(|color| (color, border_width)),
+                quadding,
+                text: text.clone(),
+            })
+        })
+        .collect()
+}
+
+// 解析默认外观字符串 (如 "/Helv 10 Tf 0 g") 中的字号和颜色,字号 0 表示自动
+fn parse_default_appearance(da: &str) -> (f32, Color) {
+    let tokens: Vec<&str> = da.split_whitespace().collect();
+    let numbers = |end: usize, count: usize| -> Option<Vec<f32>> {
+        let start = end.checked_sub(count)?;
+        tokens[start..end].iter().map(|token| token.parse().ok()).collect()
+    };
+
+    let mut font_size = 0.0;
+    let mut color = Color::Greyscale(Greyscale::new(0.0, None));
+    for (i, token) in tokens.iter().enumerate() {
+        match *token {
+            "Tf" => font_size = numbers(i, 1).map_or(0.0, |n| n[0]),
+            "g" | "rg" | "k" => {
+                let count = match *token {
+                    "g" => 1,
+                    "rg" => 3,
+                    _ => 4,
+                };
+                if let Some(components) = numbers(i, count) {
+                    color = color_from_components(&components).unwrap_or(color);
+                }
+            }
+            _ => {}
+        }
+    }
+    (font_size, color)
+}
+
+fn parse_color(object: &Object) -> Option<Color> {
+    let components: Vec<f32> = object.as_array().ok()?.iter().filter_map(|v| v.as_float().ok()).collect();
+    color_from_components(&components)
+}
+
+fn color_from_components(components: &[f32]) -> Option<Color> {
+    match *components {
+        [gray] => Some(Color::Greyscale(Greyscale::new(gray, None))),
+        [r, g, b] => Some(Color::Rgb(Rgb::new(r, g, b, None))),
+        [c, m, y, k] => Some(Color::Cmyk(Cmyk::new(c, m, y, k, None))),
+        _ => None,
+    }
+}
+
+fn draw_appearance(layer: &PdfLayerReference, appearance: &Appearance, fonts: &PdfFonts) {
+    let measurer = &fonts.measurer;
+    let (width, height) = (appearance.width, appearance.height);
+    let rect = |x1: f32, y1: f32, x2: f32, y2: f32| {
+        printpdf::Rect::new(Mm(x1 / PT_PER_MM), Mm(y1 / PT_PER_MM), Mm(x2 / PT_PER_MM), Mm(y2 / PT_PER_MM))
+    };
+
+    if let Some(background) = &appearance.background {
+        layer.set_fill_color(background.clone());
+        layer.add_rect(rect(0.0, 0.0, width, height).with_mode(PaintMode::Fill));
+    }
+    let mut padding = FIELD_PADDING;
+    if let Some((color, border_width)) = &appearance.border {
+        layer.set_outline_color(color.clone());
+        layer.set_outline_thickness(*border_width);
+        let inset = border_width / 2.0;
+        layer.add_rect(rect(inset, inset, width - inset, height - inset).with_mode(PaintMode::Stroke));
+        padding += border_width;
+    }
+
+    let inner_width = (width - 2.0 * padding).max(1.0);
+    let inner_height = (height - 2.0 * padding).max(1.0);
+    let text_x = |text: &str, font_size: f32| {
+        let free = inner_width - measurer.text_width(text, font_size);
+        match appearance.quadding {
+            1 => padding + free / 2.0,
+            2 => padding + free,
+            _ => padding,
+        }
+    };
+    let write = |text: &str, x: f32, baseline: f32, font_size: f32| {
+        if !text.is_empty() {
+            layer.set_fill_color(appearance.color.clone());
+            fonts.use_text(layer, text, font_size, Mm(x / PT_PER_MM), Mm(baseline / PT_PER_MM));
+        }
+    };
+
+    match &appearance.text {
+        FieldText::Line(text) => {
+            // 自动字号按高度取值,再缩小到能放下整行
+            let mut font_size = appearance.font_size;
+            if font_size <= 0.0 {
+                font_size = (inner_height / FIELD_LINE_SPACING).min(AUTO_FONT_SIZE);
+                let text_width = measurer.text_width(text, font_size);
+                if text_width > inner_width {
+                    font_size *= inner_width / text_width;
+                }
+                font_size = font_size.max(MIN_FONT_SIZE);
+            }
+            let baseline = (height - font_size) / 2.0 + font_size * 0.22;
+            write(text, text_x(text, font_size), baseline, font_size);
+        }
+        FieldText::Comb(text, cells) => {
+            let cell = width / *cells as f32;
+            let font_size = if appearance.font_size > 0.0 {
+                appearance.font_size
+            } else {
+                (inner_height / FIELD_LINE_SPACING).min(AUTO_FONT_SIZE).min(cell)
+            };
+            let baseline = (height - font_size) / 2.0 + font_size * 0.22;
+            for (i, c) in text.chars().take(*cells).enumerate() {
+                let c = c.to_string();
+                let x = cell * i as f32 + (cell - measurer.text_width(&c, font_size)) / 2.0;
+                write(&c, x, baseline, font_size);
+            }
+        }
+        FieldText::Multiline(text) => {
+            let font_size = if appearance.font_size > 0.0 { appearance.font_size } else { AUTO_FONT_SIZE * 0.8 };
+            let line_height = font_size * FIELD_LINE_SPACING;
+            let mut baseline = height - padding - measurer.ascent(font_size);
+            // 换行可能是 \r\n、\n 或单独的 \r (Acrobat 写入的值)
+            for paragraph in text.replace("\r\n", "\n").split(['\n', '\r']) {
+                let runs = [(paragraph.to_string(), ())];
+                for pieces in wrap_runs(&runs, inner_width, |c, _| measurer.char_width(c, font_size)) {
+                    let line: String = pieces.into_iter().map(|(text, _)| text).collect();
+                    write(&line, text_x(&line, font_size), baseline, font_size);
+                    baseline -= line_height;
+                }
+            }
+        }
+        FieldText::List { options, selected } => {
+            let font_size = if appearance.font_size > 0.0 { appearance.font_size } else { AUTO_FONT_SIZE };
+            let line_height = font_size * FIELD_LINE_SPACING;
+            for (i, option) in options.iter().enumerate() {
+                let top = height - padding - line_height * i as f32;
+                if top - line_height < 0.0 {
+                    break;
+                }
+                // 选中项使用与常见阅读器相同的高亮色
+                if selected.contains(&i) {
+                    layer.set_fill_color(Color::Rgb(Rgb::new(0.6, 0.75, 0.85, None)));
+                    layer.add_rect(rect(padding / 2.0, top - line_height, width - padding / 2.0, top).with_mode(PaintMode::Fill));
+                }
+                let baseline = top - (line_height - font_size) / 2.0 - font_size * 0.78;
+                write(option, text_x(option, font_size), baseline, font_size);
+            }
+        }
+    }
+}
+
+// 把控件的正常外观画到所在页面上,并移除控件注释和表单
+fn flatten_fields(doc: &mut Document, fields: &[Field]) -> Result<(), String> {
+    let widgets: HashSet<ObjectId> = fields.iter().flat_map(|field| field.widgets.iter().copied()).collect();
+
+    let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    for page_id in page_ids {
+        let annots = page_annotations(doc, page_id);
+        if !annots.iter().any(|annot| widgets.contains(annot)) {
+            continue;
+        }
+
+        let placements: Vec<(ObjectId, [f32; 6])> = annots
+            .iter()
+            .filter(|annot| widgets.contains(annot))
+            .filter_map(|&widget| widget_placement(doc, widget))
+            .collect();
+        pdf_overlay::place_forms(doc, page_id, &placements)?;
+
+        let remaining: Vec<Object> =
+            annots.into_iter().filter(|annot| !widgets.contains(annot)).map(Object::Reference).collect();
+        let page = doc.get_dictionary_mut(page_id).map_err(|e| format!("Failed to read page: {}", e))?;
+        if remaining.is_empty() {
+            page.remove(b"Annots");
+        } else {
+            page.set("Annots", remaining);
+        }
+    }
+
+    let catalog = doc.catalog_mut().map_err(|e| format!("Failed to read catalog: {}", e))?;
+    catalog.remove(b"AcroForm");
+    Ok(())
+}
+
+// 控件外观的放置矩阵: 外观 BBox 经其 Matrix 变换后缩放平移到控件矩形 (PDF 规范 12.5.5)
+fn widget_placement(doc: &Document, widget: ObjectId) -> Option<(ObjectId, [f32; 6])> {
+    let dict = doc.get_dictionary(widget).ok()?;
+    let flags = dict.get(b"F").and_then(Object::as_i64).unwrap_or(0);
+    if flags & HIDDEN_ANNOTATION != 0 {
+        return None;
+    }
+
+    let normal = dict.get(b"AP").ok().and_then(|ap| resolve_dict(doc, ap))?.get(b"N").ok()?;
+    let form_id = match normal {
+        Object::Reference(id) if doc.get_object(*id).is_ok_and(|object| object.as_stream().is_ok()) => *id,
+        // 复选框和单选按钮按当前状态选择外观
+        normal => {
+            let state = dict.get(b"AS").and_then(Object::as_name).ok()?;
+            resolve_dict(doc, normal)?.get(state).ok()?.as_reference().ok()?
+        }
+    };
+
+    let stream = doc.get_object(form_id).ok()?.as_stream().ok()?;
+    let numbers = |key: &[u8]| -> Option<Vec<f32>> {
+        let array = resolve(doc, stream.dict.get(key).ok()?)?.as_array().ok()?;
+        array.iter().map(|v| v.as_float().ok()).collect()
+    };
+    let bbox = numbers(b"BBox")?;
+    let matrix = numbers(b"Matrix").unwrap_or_else(|| vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+    let (&[bx1, by1, bx2, by2], &[a, b, c, d, e, f]) = (&bbox[..], &matrix[..]) else {
+        return None;
+    };
+
+    let corners = [(bx1, by1), (bx2, by1), (bx1, by2), (bx2, by2)].map(|(x, y)| (a * x + c * y + e, b * x + d * y + f));
+    let min_x = corners.iter().map(|p| p.0).fold(f32::INFINITY, f32::min);
+    let max_x = corners.iter().map(|p| p.0).fold(f32::NEG_INFINITY, f32::max);
+    let min_y = corners.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+    let max_y = corners.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+    if max_x - min_x <= 0.0 || max_y - min_y <= 0.0 {
+        return None;
+    }
+
+    let [x1, y1, x2, y2] = widget_rect(doc, widget)?;
+    let sx = (x2 - x1) / (max_x - min_x);
+    let sy = (y2 - y1) / (max_y - min_y);
+    Some((form_id, [sx, 0.0, 0.0, sy, x1 - min_x * sx, y1 - min_y * sy]))
+}
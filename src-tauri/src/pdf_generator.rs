@@ -1,175 +1,1155 @@
+use ::image::DynamicImage;
 use printpdf::*;
-use std::fs::File;
-use std::io::BufWriter;
-use std::path::PathBuf;
-
-// A4纸张尺寸 (毫米)
-const A4_WIDTH_MM: f32 = 210.0;
-const A4_HEIGHT_MM: f32 = 297.0;
-
-// 边距 (毫米)
-const MARGIN_MM: f32 = 10.0;
-
-// 文本大小 (点)
-const FONT_SIZE: f32 = 12.0;
-const LINE_HEIGHT_MM: f32 = 5.0; // 行高(毫米)
-
-pub fn generate_pdf(text: &str, image_paths: Vec<String>, output_path: &str) -> Result<(), String> {
-    // 创建PDF文档
-    let (doc, page1, layer1) = PdfDocument::new(
-        "Generated PDF",
-        Mm(A4_WIDTH_MM),
-        Mm(A4_HEIGHT_MM),
-        "Layer 1",
-    );
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
 
-    // 如果有文本,在第一页添加文本
-    if !text.is_empty() {
-        let current_layer = doc.get_page(page1).get_layer(layer1);
-        add_text_to_page(&doc, &current_layer, text)?;
-    }
+use crate::image_edit::TransformOp;
+use crate::image_scan::{self, ScanOptions};
+use crate::pdf_archive;
+use crate::pdf_fonts::{self, PdfFonts};
+use crate::pdf_image::{self, ImageEncoding, SoftMask, SourceImage};
+use crate::pdf_markdown;
+use crate::pdf_security::{self, PdfEncryption};
+use crate::pdf_utils;
+use crate::pdf_text::{find_urls, justify_spacing, wrap_runs, TextMeasurer, PT_PER_MM};
+use crate::pdf_toc::{self, TocEntry};
+
+// 默认边距 (毫米)
+const DEFAULT_MARGIN_MM: f32 = 10.0;
+
+// 多图网格的图片间距 (毫米)
+const GRID_GAP_MM: f32 = 5.0;
+
+// 默认文本大小 (点) 与行距 (字号的倍数)
+const DEFAULT_FONT_SIZE: f32 = 12.0;
+const DEFAULT_LINE_SPACING: f32 = 1.2;
+
+// 未指定标题时的文档标题
+const DEFAULT_TITLE: &str = "Generated PDF";
+
+// 页眉页脚 (以及网格图片文件名) 相对正文的字号
+const SMALL_TEXT_SCALE: f32 = 0.8;
+
+// 文本内容的格式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextFormat {
+    #[default]
+    Plain,
+    Markdown,
+}
+
+// 纸张尺寸
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageSize {
+    A3,
+    #[default]
+    A4,
+    A5,
+    Letter,
+    Legal,
+    Custom { width_mm: f32, height_mm: f32 },
+}
 
-    // 添加图片
-    for (index, image_path) in image_paths.iter().enumerate() {
-        // 每张图片一个新页面
-        let (page_index, layer_index) = if index == 0 && text.is_empty() {
-            (page1, layer1)
-        } else {
-            doc.add_page(Mm(A4_WIDTH_MM), Mm(A4_HEIGHT_MM), "Layer 1")
+impl PageSize {
+    // 纵向时的 (宽, 高),单位毫米
+    fn portrait_mm(&self) -> (f32, f32) {
+        let (a, b) = match *self {
+            PageSize::A3 => (297.0, 420.0),
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::A5 => (148.0, 210.0),
+            PageSize::Letter => (215.9, 279.4),
+            PageSize::Legal => (215.9, 355.6),
+            PageSize::Custom { width_mm, height_mm } => (width_mm, height_mm),
         };
+        (a.min(b), a.max(b))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+    // 图片页按图片宽高自动选择,文本页为纵向
+    Auto,
+}
 
-        let current_layer = doc.get_page(page_index).get_layer(layer_index);
-        add_image_to_page(&current_layer, image_path)?;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+// 图片在页面上的排布方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ImageLayout {
+    // 等比缩放到版心内并居中
+    #[default]
+    Fit,
+    // 等比缩放铺满版心,超出部分裁掉
+    Fill,
+    // 按指定 DPI 的原始尺寸居中,超出版心时缩小
+    Original { dpi: f32 },
+    // 每页排多张 (2/4/6/9),可在图片下方显示文件名
+    Grid {
+        per_page: u32,
+        #[serde(default)]
+        captions: bool,
+    },
+}
+
+// 页边距 (毫米)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Margins {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Margins {
+            top: DEFAULT_MARGIN_MM,
+            right: DEFAULT_MARGIN_MM,
+            bottom: DEFAULT_MARGIN_MM,
+            left: DEFAULT_MARGIN_MM,
+        }
     }
+}
 
-    // 保存PDF
-    doc.save(&mut BufWriter::new(
-        File::create(output_path).map_err(|e| format!("Failed to create PDF file: {}", e))?,
-    ))
-    .map_err(|e| format!("Failed to save PDF: {}", e))?;
+// 文档属性
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Vec<String>,
+}
 
-    Ok(())
+// 页眉或页脚的左、中、右三栏文字,可使用占位符:
+// {page} 当前页码, {pages} 总页数, {date} 生成日期, {file} 输出文件名
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageMarks {
+    pub left: String,
+    pub center: String,
+    pub right: String,
 }
 
-fn add_text_to_page(
-    doc: &PdfDocumentReference,
-    layer: &PdfLayerReference,
+impl PageMarks {
+    fn columns(&self) -> [(&str, TextAlign); 3] {
+        [
+            (&self.left, TextAlign::Left),
+            (&self.center, TextAlign::Center),
+            (&self.right, TextAlign::Right),
+        ]
+    }
+}
+
+// 替换页眉页脚中的占位符
+fn fill_placeholders(template: &str, page: usize, pages: usize, date: &str, file: &str) -> String {
+    template
+        .replace("{page}", &page.to_string())
+        .replace("{pages}", &pages.to_string())
+        .replace("{date}", date)
+        .replace("{file}", file)
+}
+
+// 生成 PDF 的页面与排版选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfOptions {
+    pub page_size: PageSize,
+    pub orientation: Orientation,
+    pub margins: Margins,
+    // 字号 (点)
+    pub font_size: f32,
+    // 行距,字号的倍数
+    pub line_spacing: f32,
+    pub alignment: TextAlign,
+    pub image_layout: ImageLayout,
+    // 设置后把图片当作拍摄的文档处理: 裁边、校正透视和倾斜、增强对比度或转为黑白
+    pub scan: Option<ScanOptions>,
+    // 图片分辨率上限,超过时缩小
+    pub max_dpi: Option<f32>,
+    // 图片重新编码为 JPEG 的质量 (1-100),不指定时 JPEG 原样嵌入、其他图片无损压缩
+    pub jpeg_quality: Option<u8>,
+    // 优先使用的字体 (家族名或字体文件路径),缺字时依次回退到内置字体和系统字体
+    pub font_families: Vec<String>,
+    pub metadata: DocumentMetadata,
+    // 页眉页脚,画在上下边距内
+    pub header: Option<PageMarks>,
+    pub footer: Option<PageMarks>,
+    // 设置后输出 AES-256 加密的 PDF
+    pub encryption: Option<PdfEncryption>,
+    // 输出 PDF/A-2b 归档格式: 只使用嵌入字体,附带 sRGB 输出意图和 XMP 元数据,不能加密
+    pub pdfa: bool,
+    // Markdown 含标题时在正文前生成可点击的目录页
+    pub table_of_contents: bool,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        PdfOptions {
+            page_size: PageSize::default(),
+            orientation: Orientation::default(),
+            margins: Margins::default(),
+            font_size: DEFAULT_FONT_SIZE,
+            line_spacing: DEFAULT_LINE_SPACING,
+            alignment: TextAlign::default(),
+            image_layout: ImageLayout::default(),
+            scan: None,
+            max_dpi: None,
+            jpeg_quality: None,
+            font_families: Vec::new(),
+            metadata: DocumentMetadata::default(),
+            header: None,
+            footer: None,
+            encryption: None,
+            pdfa: false,
+            table_of_contents: true,
+        }
+    }
+}
+
+impl PdfOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        let (width, height) = self.page_size.portrait_mm();
+        if !is_positive(width) || !is_positive(height) {
+            return Err(format!("Invalid page size: {} x {} mm", width, height));
+        }
+
+        let margins = self.margins;
+        if [margins.top, margins.right, margins.bottom, margins.left].iter().any(|m| !m.is_finite() || *m < 0.0) {
+            return Err("Margins must not be negative".to_string());
+        }
+
+        // 横向时宽高互换,两种方向都要留出版心
+        let short_side = width.min(height);
+        if margins.left + margins.right >= short_side || margins.top + margins.bottom >= short_side {
+            return Err("Margins leave no room for content".to_string());
+        }
+
+        if !is_positive(self.font_size) {
+            return Err(format!("Invalid font size: {}", self.font_size));
+        }
+        if !is_positive(self.line_spacing) {
+            return Err(format!("Invalid line spacing: {}", self.line_spacing));
+        }
+
+        match self.image_layout {
+            ImageLayout::Original { dpi } if !is_positive(dpi) => {
+                return Err(format!("Invalid image DPI: {}", dpi));
+            }
+            ImageLayout::Grid { per_page, .. } if grid_shape(per_page, false).is_none() => {
+                return Err(format!("Unsupported images per page: {} (expected 2, 4, 6 or 9)", per_page));
+            }
+            _ => {}
+        }
+
+        if let Some(max_dpi) = self.max_dpi {
+            if !is_positive(max_dpi) {
+                return Err(format!("Invalid max DPI: {}", max_dpi));
+            }
+        }
+        if let Some(quality) = self.jpeg_quality {
+            if !(1..=100).contains(&quality) {
+                return Err(format!("Invalid JPEG quality: {} (expected 1-100)", quality));
+            }
+        }
+        if self.pdfa && self.encryption.is_some() {
+            return Err("PDF/A output cannot be encrypted".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn layout(&self, landscape: bool) -> PageLayout {
+        let (width, height) = self.page_size.portrait_mm();
+        let (width_mm, height_mm) = if landscape { (height, width) } else { (width, height) };
+
+        PageLayout {
+            width_mm,
+            height_mm,
+            margins: self.margins,
+            font_size: self.font_size,
+            line_spacing: self.line_spacing,
+            alignment: self.alignment,
+            image_encoding: ImageEncoding {
+                max_dpi: self.max_dpi,
+                jpeg_quality: self.jpeg_quality,
+            },
+        }
+    }
+
+    // 文本页的版式,自动方向时为纵向
+    pub fn text_layout(&self) -> PageLayout {
+        self.layout(self.orientation == Orientation::Landscape)
+    }
+
+    // 单张图片页的版式
+    pub fn image_page_layout(&self, img: &SourceImage) -> PageLayout {
+        match self.orientation {
+            Orientation::Auto => self.layout(img.width() > img.height()),
+            orientation => self.layout(orientation == Orientation::Landscape),
+        }
+    }
+}
+
+fn is_positive(value: f32) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+// 单个页面的尺寸与正文排版参数
+#[derive(Debug, Clone, Copy)]
+pub struct PageLayout {
+    pub width_mm: f32,
+    pub height_mm: f32,
+    pub margins: Margins,
+    pub font_size: f32,
+    pub line_spacing: f32,
+    pub alignment: TextAlign,
+    pub image_encoding: ImageEncoding,
+}
+
+impl PageLayout {
+    pub fn content_width_mm(&self) -> f32 {
+        self.width_mm - self.margins.left - self.margins.right
+    }
+
+    pub fn content_height_mm(&self) -> f32 {
+        self.height_mm - self.margins.top - self.margins.bottom
+    }
+
+    // 版心顶部 (毫米,从页面底部算起)
+    pub fn content_top_mm(&self) -> f32 {
+        self.height_mm - self.margins.top
+    }
+
+    // 指定字号的行高 (毫米)
+    pub fn line_height_mm(&self, font_size: f32) -> f32 {
+        font_size * self.line_spacing / PT_PER_MM
+    }
+}
+
+// 书签条目,页码从 0 开始
+struct OutlineItem {
+    title: String,
+    level: usize,
+    page: usize,
+}
+
+// 文档内部链接的跳转目标: 页面下标 (从 0 开始) 或标题锚点
+pub enum LinkTarget {
+    Page(usize),
+    Anchor(String),
+}
+
+// 页面上的内部链接区域,坐标为毫米 [左, 下, 右, 上]
+struct PageLink {
+    page: usize,
+    rect: [f32; 4],
+    target: LinkTarget,
+}
+
+// 生成中的文档: 记录每页的版式和书签,内容写完后再统一绘制页眉页脚 (需要总页数)
+pub struct PdfBuilder {
+    pub doc: PdfDocumentReference,
+    pages: Vec<(PdfLayerReference, PageLayout)>,
+    outline: Vec<OutlineItem>,
+    // printpdf 只支持网址链接,内部链接在保存后另行添加
+    links: Vec<PageLink>,
+    // 锚点名称和所在页
+    anchors: Vec<(String, usize)>,
+    // 每页已添加的图片数,printpdf 按页依次把图片命名为 X0、X1...
+    image_counts: Vec<usize>,
+    // 透明图片的 alpha 通道和所在位置 (页序号, XObject 名称),保存后添加为 SMask
+    soft_masks: Vec<(usize, String, SoftMask)>,
+    // PDF/A 输出,不能使用未嵌入的内置字体
+    pub pdfa: bool,
+}
+
+impl PdfBuilder {
+    // 新建空文档。printpdf 默认按 PDF/X-3 输出,会嵌入数百 KB 的 ICC 配置文件和 XMP 元数据;
+    // PDF/A 所需的输出意图和元数据在保存前另行写入
+    pub fn new(title: &str, pdfa: bool) -> Self {
+        let doc = PdfDocument::empty(title).with_conformance(PdfConformance::Custom(CustomPdfConformance {
+            requires_icc_profile: false,
+            requires_xmp_metadata: false,
+            ..Default::default()
+        }));
+        PdfBuilder {
+            doc,
+            pages: Vec::new(),
+            outline: Vec::new(),
+            links: Vec::new(),
+            anchors: Vec::new(),
+            image_counts: Vec::new(),
+            soft_masks: Vec::new(),
+            pdfa,
+        }
+    }
+
+    pub fn add_page(&mut self, layout: &PageLayout) -> PdfLayerReference {
+        let (page, layer) = self.doc.add_page(Mm(layout.width_mm), Mm(layout.height_mm), "Layer 1");
+        let layer = self.doc.get_page(page).get_layer(layer);
+        self.pages.push((layer.clone(), *layout));
+        self.image_counts.push(0);
+        layer
+    }
+
+    // 按页面的图片压缩设置把图片放到页面上,(x, y) 为左下角坐标 (毫米)
+    pub fn place_image(
+        &mut self,
+        layer: &PdfLayerReference,
+        source: &SourceImage,
+        x_mm: f32,
+        y_mm: f32,
+        width_mm: f32,
+        height_mm: f32,
+    ) -> Result<(), String> {
+        let page = self
+            .pages
+            .iter()
+            .position(|(page_layer, _)| page_layer.page == layer.page)
+            .ok_or("Image page does not belong to this document")?;
+        let encoding = self.pages[page].1.image_encoding;
+
+        let mask = pdf_image::place_image(layer, source, x_mm, y_mm, width_mm, height_mm, &encoding)?;
+        let name = format!("X{}", self.image_counts[page]);
+        self.image_counts[page] += 1;
+        if let Some(mask) = mask {
+            self.soft_masks.push((page, name, mask));
+        }
+        Ok(())
+    }
+
+    // 在当前 (最后一) 页添加书签,层级从 0 开始
+    pub fn add_outline(&mut self, title: &str, level: usize) {
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        if title.is_empty() || self.pages.is_empty() {
+            return;
+        }
+        self.outline.push(OutlineItem {
+            title,
+            level,
+            page: self.pages.len() - 1,
+        });
+    }
+
+    // 在当前页添加锚点,重名时与 GitHub 一样依次加上 -1、-2 后缀
+    pub fn add_anchor(&mut self, name: &str) {
+        if name.is_empty() || self.pages.is_empty() {
+            return;
+        }
+        let mut anchor = name.to_string();
+        let mut suffix = 0;
+        while self.anchors.iter().any(|(existing, _)| *existing == anchor) {
+            suffix += 1;
+            anchor = format!("{}-{}", name, suffix);
+        }
+        self.anchors.push((anchor, self.pages.len() - 1));
+    }
+
+    // 在当前页添加跳转到文档内其他位置的链接区域 (毫米)
+    pub fn add_internal_link(&mut self, lower_left: (f32, f32), upper_right: (f32, f32), target: LinkTarget) {
+        if self.pages.is_empty() {
+            return;
+        }
+        self.links.push(PageLink {
+            page: self.pages.len() - 1,
+            rect: [lower_left.0, lower_left.1, upper_right.0, upper_right.1],
+            target,
+        });
+    }
+
+    // 在预留的目录页上列出已添加的标题书签,每个条目链接到标题所在页
+    fn add_toc(&mut self, pages: Range<usize>, title: &str, layout: &PageLayout, fonts: &PdfFonts) {
+        let entries: Vec<TocEntry> = self
+            .outline
+            .iter()
+            .filter(|item| item.level <= pdf_toc::MAX_LEVEL)
+            .map(|item| TocEntry {
+                title: item.title.clone(),
+                level: item.level,
+                page: item.page,
+            })
+            .collect();
+        let layers: Vec<PdfLayerReference> = self.pages[pages.clone()].iter().map(|(layer, _)| layer.clone()).collect();
+
+        let rects = pdf_toc::draw_toc(&layers, title, &entries, layout, fonts);
+        for ((index, rect), entry) in rects.into_iter().zip(&entries) {
+            self.links.push(PageLink {
+                page: pages.start + index,
+                rect,
+                target: LinkTarget::Page(entry.page),
+            });
+        }
+    }
+
+    // 在每页的上下边距内绘制页眉页脚
+    fn draw_page_marks(
+        &self,
+        header: Option<&PageMarks>,
+        footer: Option<&PageMarks>,
+        fonts: &PdfFonts,
+        date: &str,
+        file: &str,
+    ) {
+        let total = self.pages.len();
+
+        for (index, (layer, layout)) in self.pages.iter().enumerate() {
+            let font_size = layout.font_size * SMALL_TEXT_SCALE;
+            let ascent = fonts.measurer.ascent(font_size) / PT_PER_MM;
+            // 文字在边距内垂直居中
+            let header_baseline = layout.height_mm - layout.margins.top / 2.0 - ascent / 2.0;
+            let footer_baseline = layout.margins.bottom / 2.0 - ascent / 2.0;
+
+            for (marks, baseline) in [(header, header_baseline), (footer, footer_baseline)] {
+                let Some(marks) = marks else {
+                    continue;
+                };
+                for (template, align) in marks.columns() {
+                    let text = fill_placeholders(template, index + 1, total, date, file);
+                    if text.is_empty() {
+                        continue;
+                    }
+                    let width = fonts.measurer.text_width(&text, font_size) / PT_PER_MM;
+                    let x = match align {
+                        TextAlign::Center => (layout.width_mm - width) / 2.0,
+                        TextAlign::Right => layout.width_mm - layout.margins.right - width,
+                        _ => layout.margins.left,
+                    };
+                    fonts.use_text(layer, &text, font_size, Mm(x), Mm(baseline));
+                }
+            }
+        }
+    }
+}
+
+// 图片输入: 兼容纯路径,也可附带编辑操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PdfImageInput {
+    Path(String),
+    Edited {
+        path: String,
+        #[serde(default)]
+        operations: Vec<TransformOp>,
+    },
+}
+
+impl PdfImageInput {
+    pub fn path(&self) -> &str {
+        match self {
+            PdfImageInput::Path(path) => path,
+            PdfImageInput::Edited { path, .. } => path,
+        }
+    }
+
+    pub fn operations(&self) -> &[TransformOp] {
+        match self {
+            PdfImageInput::Path(_) => &[],
+            PdfImageInput::Edited { operations, .. } => operations,
+        }
+    }
+}
+
+pub fn generate_pdf(
     text: &str,
+    text_format: TextFormat,
+    base_dir: Option<&str>,
+    images: Vec<PdfImageInput>,
+    options: &PdfOptions,
+    output_path: &str,
 ) -> Result<(), String> {
-    // 获取字体文件路径 (在编译后的可执行文件旁边的assets目录)
-    let font_path = if cfg!(debug_assertions) {
-        // 开发模式：使用src-tauri/assets下的字体
-        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/AlibabaPuHuiTi-3-65-Medium.ttf")
+    options.validate()?;
+
+    // 创建PDF文档,页面按内容逐个添加
+    let mut builder = PdfBuilder::new(DEFAULT_TITLE, options.pdfa);
+    let file_name = Path::new(output_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(output_path);
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let page_marks: Vec<&PageMarks> = options.header.iter().chain(options.footer.iter()).collect();
+
+    // 目录只用于 Markdown 标题
+    let toc_entries = match text_format {
+        TextFormat::Markdown if options.table_of_contents => pdf_markdown::heading_count(text, pdf_toc::MAX_LEVEL),
+        _ => 0,
+    };
+    let toc_title = pdf_toc::toc_title(text);
+
+    // 按要输出的文字选择字体,只有图片时不加载字体
+    let captions = matches!(options.image_layout, ImageLayout::Grid { captions: true, .. }) && !images.is_empty();
+    let font_files = if !text.is_empty() || captions || !page_marks.is_empty() {
+        let mut required = match text_format {
+            TextFormat::Plain => text.to_string(),
+            TextFormat::Markdown => pdf_markdown::plain_text(text),
+        };
+        if captions {
+            required.extend(images.iter().map(|image| pdf_utils::file_stem(image.path())));
+        }
+        if toc_entries > 0 {
+            required.push_str(toc_title);
+            required.push_str(".…0123456789");
+        }
+        // 页码只会用到数字
+        for marks in &page_marks {
+            for (template, _) in marks.columns() {
+                required.push_str(&fill_placeholders(template, 0, 0, &date, file_name));
+                required.push_str("0123456789");
+            }
+        }
+        pdf_fonts::load_fonts(&options.font_families, &required)?
     } else {
-        // 生产模式：使用可执行文件旁边的assets目录
-        std::env::current_exe()
-            .map_err(|e| format!("Failed to get exe path: {}", e))?
-            .parent()
-            .ok_or("Failed to get parent directory")?
-            .join("assets/AlibabaPuHuiTi-3-65-Medium.ttf")
+        Vec::new()
     };
+    let fonts = if font_files.is_empty() { None } else { Some(PdfFonts::new(&builder.doc, &font_files)?) };
 
-    // 读取字体文件
-    let font_data = std::fs::read(&font_path)
-        .map_err(|e| format!("Failed to read font file {:?}: {}", font_path, e))?;
+    // 如果有文本,从第一页开始添加文本
+    if let Some(fonts) = fonts.as_ref().filter(|_| !text.is_empty()) {
+        let layout = options.text_layout();
+        // 有标题时在正文前预留目录页,正文排完、标题页码确定后再填写
+        let toc_pages = pdf_toc::page_count(toc_entries, &layout);
+        for _ in 0..toc_pages {
+            builder.add_page(&layout);
+        }
 
-    // 加载外部字体 (使用Cursor包装字节数据)
-    let font = doc
-        .add_external_font(std::io::Cursor::new(font_data))
-        .map_err(|e| format!("Failed to load font: {}", e))?;
+        let layer = builder.add_page(&layout);
+        match text_format {
+            TextFormat::Plain => add_text_to_pages(&mut builder, layer, text, &layout, fonts),
+            TextFormat::Markdown => {
+                pdf_markdown::render_markdown(&mut builder, layer, text, base_dir, &layout, fonts)?
+            }
+        }
+        if toc_pages > 0 {
+            builder.add_toc(0..toc_pages, toc_title, &layout, fonts);
+        }
+    }
 
-    // 起始位置 (从页面顶部开始)
-    let mut y_position = A4_HEIGHT_MM - MARGIN_MM;
+    // 添加图片,每张图片一个书签
+    match options.image_layout {
+        ImageLayout::Grid { per_page, .. } => {
+            let caption_fonts = if captions { fonts.as_ref() } else { None };
+            add_image_grid_pages(&mut builder, &images, per_page, caption_fonts, options)?;
+        }
+        mode => {
+            // 每张图片一个新页面
+            for image in &images {
+                let img = open_image(image, options.scan.as_ref())?;
+                let layout = options.image_page_layout(&img);
+                let layer = builder.add_page(&layout);
+                add_image_to_page(&mut builder, &layer, &img, &layout, mode)?;
+                builder.add_outline(&pdf_utils::file_stem(image.path()), 0);
+            }
+        }
+    }
 
-    // 分行处理文本
-    for line in text.lines() {
-        if y_position < MARGIN_MM {
-            break;
+    // 没有内容时输出一张空白页
+    if text.is_empty() && images.is_empty() {
+        builder.add_page(&options.text_layout());
+    }
+
+    if let Some(fonts) = fonts.as_ref() {
+        builder.draw_page_marks(options.header.as_ref(), options.footer.as_ref(), fonts, &date, file_name);
+    }
+
+    // 保存PDF
+    let outline = std::mem::take(&mut builder.outline);
+    let links = std::mem::take(&mut builder.links);
+    let anchors = std::mem::take(&mut builder.anchors);
+    let mut document = finish_document(builder)?;
+    set_metadata(&mut document, &options.metadata)?;
+    add_outlines(&mut document, &outline)?;
+    add_internal_links(&mut document, &links, &anchors)?;
+    if options.pdfa {
+        pdf_archive::convert_to_pdfa(&mut document)?;
+        return pdf_archive::save_pdfa(&document, output_path);
+    }
+    if let Some(encryption) = &options.encryption {
+        pdf_security::encrypt_document(&mut document, encryption)?;
+    }
+    pdf_utils::save_pdf(&mut document, output_path)
+}
+
+// 写入文档属性。printpdf 按 UTF-8 原样写入信息字典,中文会显示为乱码,这里改写为 PDF 文本字符串
+fn set_metadata(document: &mut lopdf::Document, metadata: &DocumentMetadata) -> Result<(), String> {
+    let info_id = document
+        .trailer
+        .get(b"Info")
+        .and_then(lopdf::Object::as_reference)
+        .map_err(|e| format!("Failed to read document info: {}", e))?;
+    let info = document
+        .get_dictionary_mut(info_id)
+        .map_err(|e| format!("Failed to read document info: {}", e))?;
+
+    // 去掉 printpdf 写入的空字段
+    let empty: Vec<Vec<u8>> = info
+        .iter()
+        .filter(|(_, value)| value.as_str().map(|s| s.is_empty()).unwrap_or(false))
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in empty {
+        info.remove(&key);
+    }
+
+    let keywords = metadata.keywords.iter().map(|k| k.trim()).filter(|k| !k.is_empty()).collect::<Vec<_>>().join(", ");
+    let fields = [
+        ("Title", metadata.title.as_deref()),
+        ("Author", metadata.author.as_deref()),
+        ("Subject", metadata.subject.as_deref()),
+        ("Keywords", Some(keywords.as_str())),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) {
+            info.set(key, pdf_utils::text_string(value));
         }
+    }
+    Ok(())
+}
 
-        // 使用中文字体渲染文本
-        layer.use_text(
-            line,
-            FONT_SIZE,
-            Mm(MARGIN_MM),
-            Mm(y_position),
-            &font,
-        );
+// 根据记录的书签建立书签树
+fn add_outlines(document: &mut lopdf::Document, outline: &[OutlineItem]) -> Result<(), String> {
+    let page_ids: Vec<lopdf::ObjectId> = document.get_pages().into_values().collect();
+    let entries: Vec<(String, usize, lopdf::ObjectId)> = outline
+        .iter()
+        .filter_map(|item| Some((item.title.clone(), item.level, *page_ids.get(item.page)?)))
+        .collect();
 
-        y_position -= LINE_HEIGHT_MM;
+    if let Some(outlines_id) = pdf_utils::build_outline_tree(document, &entries) {
+        let catalog = document.catalog_mut().map_err(|e| format!("Failed to read catalog: {}", e))?;
+        catalog.set("Outlines", outlines_id);
+        catalog.set("PageMode", "UseOutlines");
     }
+    Ok(())
+}
+
+// 添加内部链接注释,跳转方式与书签相同 (整页显示);找不到锚点的链接忽略
+fn add_internal_links(
+    document: &mut lopdf::Document,
+    links: &[PageLink],
+    anchors: &[(String, usize)],
+) -> Result<(), String> {
+    let page_ids: Vec<lopdf::ObjectId> = document.get_pages().into_values().collect();
 
+    for link in links {
+        let target = match &link.target {
+            LinkTarget::Page(page) => Some(*page),
+            LinkTarget::Anchor(name) => anchors.iter().find(|(anchor, _)| anchor == name).map(|(_, page)| *page),
+        };
+        let (Some(&page_id), Some(&target_id)) = (page_ids.get(link.page), target.and_then(|t| page_ids.get(t))) else {
+            continue;
+        };
+
+        let rect: Vec<lopdf::Object> = link.rect.iter().map(|v| lopdf::Object::Real(v * PT_PER_MM)).collect();
+        let annotation = lopdf::Dictionary::from_iter(vec![
+            ("Type", "Annot".into()),
+            ("Subtype", "Link".into()),
+            ("Rect", rect.into()),
+            ("Border", vec![0.into(), 0.into(), 0.into()].into()),
+            // 打印标志
+            ("F", 4.into()),
+            ("Dest", vec![lopdf::Object::Reference(target_id), "Fit".into()].into()),
+        ]);
+        let annotation_id = document.add_object(annotation);
+
+        // Annots 可能是间接引用的数组
+        let annots = document
+            .get_dictionary(page_id)
+            .and_then(|page| page.get(b"Annots"))
+            .ok()
+            .and_then(|annots| annots.as_reference().ok());
+        let annots = match annots {
+            Some(annots_id) => document.get_object_mut(annots_id),
+            None => {
+                let page = document
+                    .get_dictionary_mut(page_id)
+                    .map_err(|e| format!("Failed to read page: {}", e))?;
+                if !page.has(b"Annots") {
+                    page.set("Annots", lopdf::Object::Array(Vec::new()));
+                }
+                page.get_mut(b"Annots")
+            }
+        };
+        annots
+            .and_then(lopdf::Object::as_array_mut)
+            .map_err(|e| format!("Failed to read page annotations: {}", e))?
+            .push(annotation_id.into());
+    }
     Ok(())
 }
 
-fn add_image_to_page(
+// 转为 lopdf 文档做后处理: 添加透明图片的 SMask、精简字体,并统一压缩
+// (printpdf 只在 release 构建中压缩,图片像素和内容流都需要 Flate 压缩)
+pub fn finish_document(builder: PdfBuilder) -> Result<lopdf::Document, String> {
+    let bytes = builder.doc.save_to_bytes().map_err(|e| format!("Failed to save PDF: {}", e))?;
+    let mut document = lopdf::Document::load_mem(&bytes).map_err(|e| format!("Failed to save PDF: {}", e))?;
+    pdf_image::attach_soft_masks(&mut document, builder.soft_masks)?;
+    pdf_fonts::subset_fonts(&mut document)?;
+    document.compress();
+    Ok(document)
+}
+
+// 单张图片生成一页 PDF (内存中),供合并 PDF 时把图片作为页面插入
+pub fn image_to_pdf_document(image: &PdfImageInput) -> Result<lopdf::Document, String> {
+    let img = open_image(image, None)?;
+
+    let options = PdfOptions::default();
+    let mut builder = PdfBuilder::new("Image", false);
+    let layout = options.image_page_layout(&img);
+    let layer = builder.add_page(&layout);
+    add_image_to_page(&mut builder, &layer, &img, &layout, options.image_layout)?;
+
+    finish_document(builder)
+}
+
+// 从指定页面开始排版文本,按字形宽度自动换行,写满后自动新建页面;网址添加可点击的链接
+fn add_text_to_pages(
+    builder: &mut PdfBuilder,
+    first_layer: PdfLayerReference,
+    text: &str,
+    layout: &PageLayout,
+    fonts: &PdfFonts,
+) {
+    let measurer = &fonts.measurer;
+    let font_size = layout.font_size;
+    let max_width_pt = layout.content_width_mm() * PT_PER_MM;
+    let line_height = layout.line_height_mm(font_size);
+    let ascent = measurer.ascent(font_size) / PT_PER_MM;
+    let mut layer = first_layer;
+
+    // 起始基线 (从版心顶部开始)
+    let first_baseline = layout.content_top_mm() - ascent;
+    let mut y_position = first_baseline;
+
+    // 分行处理文本,长行按宽度折行
+    for raw_line in text.lines() {
+        let raw_line = raw_line.replace('\t', "    ");
+        let lines = wrap_runs(&url_runs(&raw_line), max_width_pt, |c, _| measurer.char_width(c, font_size));
+        let line_count = lines.len();
+
+        for (index, pieces) in lines.into_iter().enumerate() {
+            // 当前页写满,继续写到新页面
+            if y_position < layout.margins.bottom {
+                layer = builder.add_page(layout);
+                y_position = first_baseline;
+            }
+
+            let line: String = pieces.iter().map(|(text, _)| text.as_str()).collect();
+            if !line.is_empty() {
+                // 段落最后一行不做两端对齐
+                let last_line = index + 1 == line_count;
+                let (mut x, space_extra, char_extra) =
+                    write_aligned_line(&layer, fonts, &line, y_position, layout, last_line);
+
+                for (text, url) in &pieces {
+                    let spaces = text.chars().filter(|c| *c == ' ').count() as f32;
+                    let chars = text.chars().count() as f32;
+                    let width =
+                        (measurer.text_width(text, font_size) + space_extra * spaces + char_extra * chars) / PT_PER_MM;
+                    if let Some(url) = url {
+                        add_url_link(&layer, url, (x, y_position), width, y_position + ascent, layout);
+                    }
+                    x += width;
+                }
+            }
+
+            y_position -= line_height;
+        }
+    }
+}
+
+// 按网址拆分一行文本,网址片段附带链接地址
+fn url_runs(line: &str) -> Vec<(String, Option<String>)> {
+    let mut runs = Vec::new();
+    let mut pos = 0;
+    for (range, url) in find_urls(line) {
+        if range.start > pos {
+            runs.push((line[pos..range.start].to_string(), None));
+        }
+        runs.push((line[range.clone()].to_string(), Some(url)));
+        pos = range.end;
+    }
+    if pos < line.len() || runs.is_empty() {
+        runs.push((line[pos..].to_string(), None));
+    }
+    runs
+}
+
+// 在网址下方画下划线,并添加打开网址的链接注释 (从行顶覆盖一个行高)
+fn add_url_link(
     layer: &PdfLayerReference,
-    image_path: &str,
-) -> Result<(), String> {
-    // 读取图片
-    let img = ::image::open(image_path)
-        .map_err(|e| format!("Failed to open image {}: {}", image_path, e))?;
+    url: &str,
+    (x, baseline): (f32, f32),
+    width: f32,
+    top: f32,
+    layout: &PageLayout,
+) {
+    let font_size = layout.font_size;
+    let font_size_mm = font_size / PT_PER_MM;
+    let line_height = layout.line_height_mm(font_size);
 
-    // 转换为RGB
-    let rgb_image = img.to_rgb8();
-    let (img_width, img_height) = rgb_image.dimensions();
+    let y = baseline - font_size_mm * 0.12;
+    layer.set_outline_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+    layer.set_outline_thickness(font_size * 0.05);
+    layer.add_line(Line {
+        points: vec![(Point::new(Mm(x), Mm(y)), false), (Point::new(Mm(x + width), Mm(y)), false)],
+        is_closed: false,
+    });
 
-    // 图片宽度撑满A4纸宽度
-    let target_width_mm = A4_WIDTH_MM;
+    layer.add_link_annotation(LinkAnnotation::new(
+        printpdf::Rect::new(Mm(x), Mm(top - line_height), Mm(x + width), Mm(top)),
+        Some(BorderArray::Solid([0.0, 0.0, 0.0])),
+        Some(ColorArray::Transparent),
+        Actions::uri(url.to_string()),
+        None,
+    ));
+}
 
-    // 计算图片的宽高比
-    let aspect_ratio = img_width as f32 / img_height as f32;
+// 按对齐方式输出一行文本,返回 (起始横坐标, 每个空格增加的宽度, 每个字符后增加的间距)
+fn write_aligned_line(
+    layer: &PdfLayerReference,
+    fonts: &PdfFonts,
+    line: &str,
+    y: f32,
+    layout: &PageLayout,
+    last_line: bool,
+) -> (f32, f32, f32) {
+    let measurer = &fonts.measurer;
+    let font_size = layout.font_size;
+    let left = layout.margins.left;
+    let free_pt = layout.content_width_mm() * PT_PER_MM - measurer.text_width(line, font_size);
 
-    // 根据宽度计算高度
-    let target_height_mm = target_width_mm / aspect_ratio;
+    let x = match layout.alignment {
+        TextAlign::Left => left,
+        TextAlign::Center => left + free_pt / 2.0 / PT_PER_MM,
+        TextAlign::Right => left + free_pt / PT_PER_MM,
+        TextAlign::Justify => {
+            let (space_extra, char_extra) = if last_line { (0.0, 0.0) } else { justify_spacing(line, free_pt) };
 
-    // 检查图片是否超出页面高度
-    let (final_width_mm, final_height_mm) = if target_height_mm > A4_HEIGHT_MM {
-        // 如果高度超出,按高度缩放
-        (A4_HEIGHT_MM * aspect_ratio, A4_HEIGHT_MM)
-    } else {
-        (target_width_mm, target_height_mm)
+            if space_extra > 0.0 {
+                // 外部字体不支持词间距,逐词定位
+                let mut x = left;
+                for word in line.split_inclusive(' ') {
+                    fonts.use_text(layer, word, font_size, Mm(x), Mm(y));
+                    x += measurer.text_width(word, font_size) / PT_PER_MM;
+                    if word.ends_with(' ') {
+                        x += space_extra / PT_PER_MM;
+                    }
+                }
+            } else {
+                layer.set_character_spacing(char_extra);
+                fonts.use_text(layer, line, font_size, Mm(left), Mm(y));
+                layer.set_character_spacing(0.0);
+            }
+            return (left, space_extra, char_extra);
+        }
     };
 
-    // 图片放在页面左上角 (0, 从顶部计算的位置)
-    let x_position_mm = 0.0;
-    let y_position_mm = A4_HEIGHT_MM - final_height_mm;
-
-    // 从RGB图片创建图片对象
-    let image_data = rgb_image.into_raw();
-
-    let image_pdf = Image::from(ImageXObject {
-        width: Px(img_width as usize),
-        height: Px(img_height as usize),
-        color_space: ColorSpace::Rgb,
-        bits_per_component: ColorBits::Bit8,
-        interpolate: true,
-        image_data,
-        image_filter: None,
-        clipping_bbox: None,
-        smask: None,
-    });
+    fonts.use_text(layer, line, font_size, Mm(x), Mm(y));
+    (x, 0.0, 0.0)
+}
+
+fn open_image(image: &PdfImageInput, scan: Option<&ScanOptions>) -> Result<SourceImage, String> {
+    let source = SourceImage::open(image.path(), image.operations()).map_err(|e| format!("{} ({})", e, image.path()))?;
+    Ok(match scan {
+        Some(scan) => source.with_image(image_scan::process_scan(&source.image, scan)),
+        None => source,
+    })
+}
+
+// 版心区域 (x, y, 宽, 高),(x, y) 为左下角
+type Area = (f32, f32, f32, f32);
 
-    // 计算DPI：我们希望图片宽度为final_width_mm毫米
-    // 公式：dpi = (pixels * 25.4) / mm
-    let dpi_x = (img_width as f32 * 25.4) / final_width_mm;
-    let dpi_y = (img_height as f32 * 25.4) / final_height_mm;
-    
-    // 使用平均DPI
-    let dpi = (dpi_x + dpi_y) / 2.0;
-
-    // 添加图片到页面
-    image_pdf.add_to_layer(
-        layer.clone(),
-        ImageTransform {
-            translate_x: Some(Mm(x_position_mm)),
-            translate_y: Some(Mm(y_position_mm)),
-            scale_x: Some(1.0),
-            scale_y: Some(1.0),
-            rotate: None,
-            dpi: Some(dpi),
-        },
+fn add_image_to_page(
+    builder: &mut PdfBuilder,
+    layer: &PdfLayerReference,
+    img: &SourceImage,
+    layout: &PageLayout,
+    mode: ImageLayout,
+) -> Result<(), String> {
+    let area = (
+        layout.margins.left,
+        layout.margins.bottom,
+        layout.content_width_mm(),
+        layout.content_height_mm(),
     );
 
+    match mode {
+        ImageLayout::Fill => {
+            // 按版心宽高比居中裁剪,再铺满版心
+            let cropped = img.with_image(crop_to_aspect(&img.image, area.2 / area.3));
+            builder.place_image(layer, &cropped, area.0, area.1, area.2, area.3)
+        }
+        ImageLayout::Original { dpi } => {
+            let width = img.width() as f32 * 25.4 / dpi;
+            let height = img.height() as f32 * 25.4 / dpi;
+            let scale = (area.2 / width).min(area.3 / height).min(1.0);
+            let (x, y, width, height) = center_in(area, width * scale, height * scale);
+            builder.place_image(layer, img, x, y, width, height)
+        }
+        _ => {
+            let (x, y, width, height) = fit_in(area, img);
+            builder.place_image(layer, img, x, y, width, height)
+        }
+    }
+}
+
+// 每页多张图片,按网格从左到右、从上到下排列
+fn add_image_grid_pages(
+    builder: &mut PdfBuilder,
+    images: &[PdfImageInput],
+    per_page: u32,
+    caption_fonts: Option<&PdfFonts>,
+    options: &PdfOptions,
+) -> Result<(), String> {
+    let text_layout = options.text_layout();
+    let caption_size = text_layout.font_size * SMALL_TEXT_SCALE;
+    let caption_height = if caption_fonts.is_some() { text_layout.line_height_mm(caption_size) + 1.0 } else { 0.0 };
+
+    // 自动方向时每页分别选择图片显示面积更大的方向 (相同时纵向)
+    let layouts = match options.orientation {
+        Orientation::Auto => vec![options.layout(false), options.layout(true)],
+        _ => vec![text_layout],
+    };
+    let grids = layouts
+        .into_iter()
+        .map(|layout| Ok((layout, grid_cells(&layout, per_page, caption_height)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    for page_images in images.chunks(per_page as usize) {
+        let imgs = page_images
+            .iter()
+            .map(|image| open_image(image, options.scan.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut grid = &grids[0];
+        for candidate in &grids[1..] {
+            if shown_area(&candidate.1, &imgs) > shown_area(&grid.1, &imgs) {
+                grid = candidate;
+            }
+        }
+        let (layout, cells) = grid;
+
+        let layer = builder.add_page(layout);
+        for ((image, img), &area) in page_images.iter().zip(&imgs).zip(cells) {
+            let (x, y, width, height) = fit_in(area, img);
+            builder.place_image(&layer, img, x, y, width, height)?;
+            builder.add_outline(&pdf_utils::file_stem(image.path()), 0);
+
+            if let Some(fonts) = caption_fonts {
+                let measurer = &fonts.measurer;
+                let caption = fit_caption(&pdf_utils::file_stem(image.path()), area.2, caption_size, measurer);
+                let caption_width = measurer.text_width(&caption, caption_size) / PT_PER_MM;
+                let baseline = y - measurer.ascent(caption_size) / PT_PER_MM - 1.0;
+                fonts.use_text(
+                    &layer,
+                    &caption,
+                    caption_size,
+                    Mm(area.0 + (area.2 - caption_width) / 2.0),
+                    Mm(baseline),
+                );
+            }
+        }
+    }
+
     Ok(())
 }
+
+// 网格中每个单元格的图片区域,从左到右、从上到下排列;文件名占用单元格底部一行
+fn grid_cells(layout: &PageLayout, per_page: u32, caption_height: f32) -> Result<Vec<Area>, String> {
+    let landscape = layout.width_mm > layout.height_mm;
+    let (columns, rows) = grid_shape(per_page, landscape)
+        .ok_or_else(|| format!("Unsupported images per page: {}", per_page))?;
+
+    let cell_width = (layout.content_width_mm() - GRID_GAP_MM * (columns - 1) as f32) / columns as f32;
+    let cell_height = (layout.content_height_mm() - GRID_GAP_MM * (rows - 1) as f32) / rows as f32;
+
+    Ok((0..columns * rows)
+        .map(|index| {
+            let cell_x = layout.margins.left + (index % columns) as f32 * (cell_width + GRID_GAP_MM);
+            let cell_top = layout.content_top_mm() - (index / columns) as f32 * (cell_height + GRID_GAP_MM);
+            (cell_x, cell_top - cell_height + caption_height, cell_width, cell_height - caption_height)
+        })
+        .collect())
+}
+
+// 图片依次放入单元格后的总显示面积
+fn shown_area(cells: &[Area], images: &[SourceImage]) -> f32 {
+    cells
+        .iter()
+        .zip(images)
+        .map(|(&cell, img)| {
+            let (_, _, width, height) = fit_in(cell, img);
+            width * height
+        })
+        .sum()
+}
+
+// 每页图片数对应的 (列数, 行数),横向页面时行列互换
+fn grid_shape(per_page: u32, landscape: bool) -> Option<(usize, usize)> {
+    let (columns, rows) = match per_page {
+        2 => (1, 2),
+        4 => (2, 2),
+        6 => (2, 3),
+        9 => (3, 3),
+        _ => return None,
+    };
+    Some(if landscape { (rows, columns) } else { (columns, rows) })
+}
+
+// 文件名超出单元格宽度时截断并加省略号
+pub fn fit_caption(name: &str, max_width_mm: f32, font_size: f32, measurer: &TextMeasurer) -> String {
+    let max_width = max_width_mm * PT_PER_MM;
+    if measurer.text_width(name, font_size) <= max_width {
+        return name.to_string();
+    }
+
+    let ellipsis = if measurer.has_glyph('…') { "…" } else { "..." };
+    let mut caption = String::new();
+    let mut width = measurer.text_width(ellipsis, font_size);
+    for c in name.chars() {
+        width += measurer.char_width(c, font_size);
+        if width > max_width {
+            break;
+        }
+        caption.push(c);
+    }
+    caption + ellipsis
+}
+
+// 等比缩放到区域内并居中
+fn fit_in(area: Area, img: &SourceImage) -> Area {
+    let (width, height) = (img.width() as f32, img.height() as f32);
+    let scale = (area.2 / width).min(area.3 / height);
+    center_in(area, width * scale, height * scale)
+}
+
+fn center_in(area: Area, width: f32, height: f32) -> Area {
+    (area.0 + (area.2 - width) / 2.0, area.1 + (area.3 - height) / 2.0, width, height)
+}
+
+// 居中裁剪到指定宽高比
+fn crop_to_aspect(img: &DynamicImage, aspect: f32) -> DynamicImage {
+    let (width, height) = (img.width(), img.height());
+    if (width as f32 / height as f32) > aspect {
+        let new_width = ((height as f32 * aspect).round() as u32).clamp(1, width);
+        img.crop_imm((width - new_width) / 2, 0, new_width, height)
+    } else {
+        let new_height = ((width as f32 / aspect).round() as u32).clamp(1, height);
+        img.crop_imm(0, (height - new_height) / 2, width, new_height)
+    }
+}
@@ -0,0 +1,328 @@
+use ::image::imageops::FilterType;
+use ::image::{DynamicImage, ImageFormat};
+use flate2::read::ZlibDecoder;
+use printpdf::lopdf::{self, Dictionary, Object, ObjectId, Stream};
+use printpdf::*;
+use std::fs;
+use std::io::Read;
+
+use crate::image_compress;
+use crate::image_edit::{self, TransformOp};
+use crate::pdf_utils;
+
+// 需要重新编码 JPEG 且未指定质量时使用
+pub(crate) const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+// 图片嵌入 PDF 时的压缩设置
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImageEncoding {
+    // 超过该分辨率时缩小
+    pub max_dpi: Option<f32>,
+    // 指定后所有图片按该质量重新编码为 JPEG
+    pub jpeg_quality: Option<u8>,
+}
+
+// 待嵌入的图片,未经修改的 JPEG 保留原始数据以便直接嵌入
+pub struct SourceImage {
+    pub image: DynamicImage,
+    jpeg_data: Option<Vec<u8>>,
+    // 来源是 JPEG,重新编码时继续使用有损压缩
+    is_jpeg: bool,
+}
+
+impl SourceImage {
+    pub fn open(path: &str, ops: &[TransformOp]) -> Result<Self, String> {
+        let image = image_edit::open_and_transform(path, ops)?;
+        let data = fs::read(path).map_err(|e| format!("Failed to read image: {}", e))?;
+        let is_jpeg = matches!(::image::guess_format(&data), Ok(ImageFormat::Jpeg));
+
+        let jpeg_data = if is_jpeg && ops.is_empty() && jpeg_components(&data).is_some() {
+            Some(data)
+        } else {
+            None
+        };
+
+        Ok(SourceImage { image, jpeg_data, is_jpeg })
+    }
+
+    // 裁剪等处理后的图片不能再直接嵌入原始数据
+    pub fn with_image(&self, image: DynamicImage) -> SourceImage {
+        SourceImage {
+            image,
+            jpeg_data: None,
+            is_jpeg: self.is_jpeg,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+}
+
+impl From<DynamicImage> for SourceImage {
+    fn from(image: DynamicImage) -> Self {
+        SourceImage {
+            image,
+            jpeg_data: None,
+            is_jpeg: false,
+        }
+    }
+}
+
+// 透明图片的 alpha 通道。printpdf 写出的 SMask 不可用,
+// 由调用方记下图片所在页和 XObject 名称,保存后用 attach_soft_masks 添加
+pub struct SoftMask {
+    width: u32,
+    height: u32,
+    alpha: Vec<u8>,
+}
+
+// 把图片按指定尺寸放到页面上,(x, y) 为左下角坐标 (毫米);透明图片返回其 alpha 通道
+pub fn place_image(
+    layer: &PdfLayerReference,
+    source: &SourceImage,
+    x_mm: f32,
+    y_mm: f32,
+    width_mm: f32,
+    height_mm: f32,
+    encoding: &ImageEncoding,
+) -> Result<Option<SoftMask>, String> {
+    // 黑白图片缩小后重新二值化,保持 1 位深度
+    let bilevel = is_bilevel(&source.image);
+    let resized = encoding
+        .max_dpi
+        .and_then(|max_dpi| downsample(&source.image, width_mm, height_mm, max_dpi))
+        .map(|img| if bilevel { threshold(&img) } else { img });
+    let image = resized.as_ref().unwrap_or(&source.image);
+
+    let image_xobject = match (&source.jpeg_data, &resized, encoding.jpeg_quality) {
+        // 未修改的 JPEG 直接嵌入原始数据,不重新压缩
+        (Some(data), None, None) => {
+            let color_space = match jpeg_components(data) {
+                Some(1) => ColorSpace::Greyscale,
+                _ => ColorSpace::Rgb,
+            };
+            encoded_xobject(image, color_space, data.clone())
+        }
+        _ if bilevel => bilevel_xobject(image),
+        (_, _, Some(quality)) => jpeg_xobject(image, quality)?,
+        _ if source.is_jpeg => jpeg_xobject(image, DEFAULT_JPEG_QUALITY)?,
+        // 其他图片保存原始像素,保存时统一 Flate 压缩
+        _ => raw_xobject(image),
+    };
+
+    // 计算DPI：我们希望图片宽度为width_mm毫米
+    // 公式：dpi = (pixels * 25.4) / mm
+    let dpi_x = (image.width() as f32 * 25.4) / width_mm;
+    let dpi_y = (image.height() as f32 * 25.4) / height_mm;
+
+    // 使用平均DPI
+    let dpi = (dpi_x + dpi_y) / 2.0;
+
+    // 添加图片到页面
+    Image::from(image_xobject).add_to_layer(
+        layer.clone(),
+        ImageTransform {
+            translate_x: Some(Mm(x_mm)),
+            translate_y: Some(Mm(y_mm)),
+            scale_x: Some(1.0),
+            scale_y: Some(1.0),
+            rotate: None,
+            dpi: Some(dpi),
+        },
+    );
+
+    Ok(alpha_channel(image).map(|alpha| SoftMask {
+        width: image.width(),
+        height: image.height(),
+        alpha,
+    }))
+}
+
+// 分辨率超过 max_dpi 时缩小到 max_dpi,否则返回 None
+fn downsample(img: &DynamicImage, width_mm: f32, height_mm: f32, max_dpi: f32) -> Option<DynamicImage> {
+    let max_width = ((width_mm / 25.4 * max_dpi).round() as u32).max(1);
+    let max_height = ((height_mm / 25.4 * max_dpi).round() as u32).max(1);
+
+    if img.width() <= max_width && img.height() <= max_height {
+        return None;
+    }
+    Some(img.resize(max_width, max_height, FilterType::Lanczos3))
+}
+
+fn jpeg_xobject(img: &DynamicImage, quality: u8) -> Result<ImageXObject, String> {
+    let data = image_compress::encode_jpeg(&img.to_rgb8(), quality)?;
+    Ok(encoded_xobject(img, ColorSpace::Rgb, data))
+}
+
+fn encoded_xobject(img: &DynamicImage, color_space: ColorSpace, data: Vec<u8>) -> ImageXObject {
+    ImageXObject {
+        width: Px(img.width() as usize),
+        height: Px(img.height() as usize),
+        color_space,
+        bits_per_component: ColorBits::Bit8,
+        interpolate: true,
+        image_data: data,
+        image_filter: Some(ImageFilter::DCT),
+        clipping_bbox: None,
+        smask: None,
+    }
+}
+
+fn raw_xobject(img: &DynamicImage) -> ImageXObject {
+    // 灰度图只保存一个通道
+    let (color_space, image_data) = if img.color().has_color() {
+        (ColorSpace::Rgb, img.to_rgb8().into_raw())
+    } else {
+        (ColorSpace::Greyscale, img.to_luma8().into_raw())
+    };
+
+    ImageXObject {
+        width: Px(img.width() as usize),
+        height: Px(img.height() as usize),
+        color_space,
+        bits_per_component: ColorBits::Bit8,
+        interpolate: true,
+        image_data,
+        image_filter: None,
+        clipping_bbox: None,
+        smask: None,
+    }
+}
+
+// 只有纯黑和纯白像素的灰度图 (如黑白扫描件)
+fn is_bilevel(img: &DynamicImage) -> bool {
+    matches!(img, DynamicImage::ImageLuma8(gray) if gray.pixels().all(|p| p[0] == 0 || p[0] == 255))
+}
+
+fn threshold(img: &DynamicImage) -> DynamicImage {
+    let mut gray = img.to_luma8();
+    for pixel in gray.pixels_mut() {
+        pixel[0] = if pixel[0] < 128 { 0 } else { 255 };
+    }
+    DynamicImage::ImageLuma8(gray)
+}
+
+// 黑白图片按 1 位深度保存,每行按字节对齐,0 为黑
+fn bilevel_xobject(img: &DynamicImage) -> ImageXObject {
+    let gray = img.to_luma8();
+    let row_bytes = (gray.width() as usize).div_ceil(8);
+    let mut image_data = vec![0u8; row_bytes * gray.height() as usize];
+    for (x, y, pixel) in gray.enumerate_pixels() {
+        if pixel[0] != 0 {
+            image_data[y as usize * row_bytes + x as usize / 8] |= 0x80 >> (x % 8);
+        }
+    }
+
+    ImageXObject {
+        width: Px(img.width() as usize),
+        height: Px(img.height() as usize),
+        color_space: ColorSpace::Greyscale,
+        bits_per_component: ColorBits::Bit1,
+        interpolate: true,
+        image_data,
+        image_filter: None,
+        clipping_bbox: None,
+        smask: None,
+    }
+}
+
+// 图片的 alpha 通道,没有透明像素时返回 None
+fn alpha_channel(img: &DynamicImage) -> Option<Vec<u8>> {
+    if !img.color().has_alpha() {
+        return None;
+    }
+
+    let alpha: Vec<u8> = img.to_rgba8().pixels().map(|p| p[3]).collect();
+    alpha.iter().any(|&a| a < 255).then_some(alpha)
+}
+
+// 给保存后的文档中的透明图片添加 SMask,图片按 (页序号, XObject 名称) 查找
+pub fn attach_soft_masks(doc: &mut lopdf::Document, masks: Vec<(usize, String, SoftMask)>) -> Result<(), String> {
+    let pages = doc.get_pages();
+
+    for (page, name, mask) in masks {
+        let image_id = pages
+            .get(&(page as u32 + 1))
+            .and_then(|&page_id| xobject_id(doc, page_id, &name))
+            .ok_or_else(|| format!("Failed to find image {} on page {}", name, page + 1))?;
+
+        let mask_id = doc.add_object(Stream::new(
+            Dictionary::from_iter(vec![
+                ("Type", Object::Name(b"XObject".to_vec())),
+                ("Subtype", Object::Name(b"Image".to_vec())),
+                ("Width", Object::Integer(mask.width as i64)),
+                ("Height", Object::Integer(mask.height as i64)),
+                ("ColorSpace", Object::Name(b"DeviceGray".to_vec())),
+                ("BitsPerComponent", Object::Integer(8)),
+            ]),
+            mask.alpha,
+        ));
+        doc.get_object_mut(image_id)
+            .and_then(Object::as_stream_mut)
+            .map_err(|e| format!("Failed to read image: {}", e))?
+            .dict
+            .set("SMask", mask_id);
+    }
+
+    Ok(())
+}
+
+fn xobject_id(doc: &lopdf::Document, page_id: ObjectId, name: &str) -> Option<ObjectId> {
+    let page = doc.get_dictionary(page_id).ok()?;
+    let resources = pdf_utils::resolve_dict(doc, page.get(b"Resources").ok()?)?;
+    let xobjects = pdf_utils::resolve_dict(doc, resources.get(b"XObject").ok()?)?;
+    xobjects.get(name.as_bytes()).and_then(Object::as_reference).ok()
+}
+
+pub(crate) fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut output = Vec::new();
+    ZlibDecoder::new(data)
+        .read_to_end(&mut output)
+        .map_err(|e| format!("Failed to read image data: {}", e))?;
+    Ok(output)
+}
+
+// 读取 JPEG 帧头中的颜色分量数; 只接受 8 位灰度或 YCbCr/RGB,CMYK 等需要重新编码
+pub(crate) fn jpeg_components(data: &[u8]) -> Option<u8> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut i = 2;
+    while i + 4 <= data.len() {
+        if data[i] != 0xFF {
+            return None;
+        }
+        let marker = data[i + 1];
+        // 填充字节
+        if marker == 0xFF {
+            i += 1;
+            continue;
+        }
+        // 没有长度字段的标记
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            i += 2;
+            continue;
+        }
+
+        let length = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        // SOF0-SOF15,排除 DHT/JPG/DAC
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let precision = *data.get(i + 4)?;
+            let components = *data.get(i + 9)?;
+            return (precision == 8 && matches!(components, 1 | 3)).then_some(components);
+        }
+        // 扫描数据开始后不会再有帧头
+        if marker == 0xDA {
+            return None;
+        }
+        i += 2 + length;
+    }
+
+    None
+}
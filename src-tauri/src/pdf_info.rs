@@ -0,0 +1,373 @@
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+
+use crate::pdf_extract;
+use crate::pdf_optimize;
+use crate::pdf_security;
+use crate::pdf_text::PT_PER_MM;
+use crate::pdf_utils::{self, resolve, resolve_dict};
+
+// 嵌套表单 XObject 的最大深度,防止循环引用
+const MAX_FORM_DEPTH: usize = 8;
+
+#[derive(Debug, Serialize)]
+pub struct PdfInfo {
+    pub file_size: u64,
+    pub version: String,
+    pub page_count: usize,
+    pub pages: Vec<PageInfo>,
+    pub metadata: PdfMetadata,
+    pub fonts: Vec<FontInfo>,
+    pub images: Vec<ImageInfo>,
+    // 加密方式,未加密时为 None
+    pub encryption: Option<String>,
+    // 需要用户密码才能读取内容,此时元数据等信息不可用
+    pub password_required: bool,
+    pub tagged: bool,
+    // PDF/A 标识,如 "PDF/A-2B"
+    pub pdfa: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PdfMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    pub creation_date: Option<String>,
+    pub modification_date: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PageInfo {
+    pub width_mm: f32,
+    pub height_mm: f32,
+    pub rotation: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FontInfo {
+    pub name: String,
+    pub font_type: String,
+    pub embedded: bool,
+    // 子集字体的名称带有 "ABCDEF+" 前缀
+    pub subset: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageInfo {
+    // 首次使用该图片的页码
+    pub page: u32,
+    pub width: i64,
+    pub height: i64,
+    pub color_space: String,
+    pub bits_per_component: i64,
+    pub filter: String,
+    // 按页面上的最大显示尺寸计算的分辨率
+    pub dpi: Option<f32>,
+}
+
+pub fn pdf_info(path: &str) -> Result<PdfInfo, String> {
+    let file_size = fs::metadata(path).map_err(|e| format!("Failed to read PDF: {}", e))?.len();
+    let raw = Document::load(path).map_err(|e| format!("Failed to load PDF {}: {}", path, e))?;
+
+    // 只设置了所有者密码 (用户密码为空) 的文件可以直接解密读取
+    let encryption = pdf_security::encryption_method(&raw);
+    let (mut doc, password_required) = match encryption {
+        Some(_) => match pdf_security::load_encrypted_pdf(path, "") {
+            Ok(doc) => (doc, false),
+            Err(_) => (raw, true),
+        },
+        None => (raw, false),
+    };
+    pdf_utils::flatten_inherited_page_attributes(&mut doc);
+
+    let catalog = doc.catalog().ok();
+    let tagged = catalog.is_some_and(|catalog| {
+        let marked = catalog
+            .get(b"MarkInfo")
+            .ok()
+            .and_then(|info| resolve_dict(&doc, info))
+            .and_then(|info| info.get(b"Marked").and_then(Object::as_bool).ok());
+        marked.unwrap_or(false) || catalog.has(b"StructTreeRoot")
+    });
+    // 目录中的 Version 可以覆盖文件头中的版本
+    let version = catalog
+        .and_then(|catalog| catalog.get(b"Version").and_then(Object::as_name_str).ok())
+        .filter(|version| *version > doc.version.as_str())
+        .unwrap_or(doc.version.as_str())
+        .to_string();
+
+    let pages: Vec<PageInfo> = doc.get_pages().into_values().map(|page_id| page_info(&doc, page_id)).collect();
+    let (metadata, pdfa) = if password_required {
+        (PdfMetadata::default(), None)
+    } else {
+        (read_metadata(&doc), pdfa_conformance(&doc))
+    };
+
+    let info = PdfInfo {
+        file_size,
+        version,
+        page_count: pages.len(),
+        pages,
+        metadata,
+        fonts: collect_fonts(&doc),
+        images: collect_images(&doc),
+        encryption,
+        password_required,
+        tagged,
+        pdfa,
+    };
+    println!("读取 PDF 信息: {}, {} 页", path, info.page_count);
+    Ok(info)
+}
+
+// 页面尺寸优先取 CropBox
+fn page_info(doc: &Document, page_id: ObjectId) -> PageInfo {
+    let page = doc.get_dictionary(page_id).ok();
+    let bbox = page
+        .and_then(|page| page.get(b"CropBox").or_else(|_| page.get(b"MediaBox")).ok())
+        .and_then(|bbox| resolve(doc, bbox))
+        .and_then(|bbox| bbox.as_array().ok())
+        .and_then(|bbox| bbox.iter().map(|value| value.as_float().ok()).collect::<Option<Vec<f32>>>())
+        .filter(|bbox| bbox.len() == 4)
+        .unwrap_or_else(|| vec![0.0, 0.0, 595.0, 842.0]);
+
+    PageInfo {
+        width_mm: (bbox[2] - bbox[0]).abs() / PT_PER_MM,
+        height_mm: (bbox[3] - bbox[1]).abs() / PT_PER_MM,
+        rotation: page
+            .and_then(|page| page.get(b"Rotate").and_then(Object::as_i64).ok())
+            .unwrap_or(0)
+            .rem_euclid(360),
+    }
+}
+
+fn read_metadata(doc: &Document) -> PdfMetadata {
+    let info = doc.trailer.get(b"Info").ok().and_then(|info| resolve_dict(doc, info));
+    let text = |key: &[u8]| {
+        let value = info?.get(key).ok().and_then(|value| resolve(doc, value))?.as_str().ok()?;
+        Some(pdf_utils::decode_text_string(value)).filter(|text| !text.trim().is_empty())
+    };
+
+    PdfMetadata {
+        title: text(b"Title"),
+        author: text(b"Author"),
+        subject: text(b"Subject"),
+        keywords: text(b"Keywords"),
+        creator: text(b"Creator"),
+        producer: text(b"Producer"),
+        creation_date: text(b"CreationDate").map(|date| format_date(&date)),
+        modification_date: text(b"ModDate").map(|date| format_date(&date)),
+    }
+}
+
+// PDF 日期 "D:YYYYMMDDHHmmSS..." 转为 "YYYY-MM-DD HH:mm:SS",忽略时区; 无法解析时原样返回
+fn format_date(date: &str) -> String {
+    let digits = date.trim_start_matches("D:");
+    let part = |start: usize, end: usize| digits.get(start..end).filter(|part| part.bytes().all(|b| b.is_ascii_digit()));
+
+    let Some(year) = part(0, 4) else {
+        return date.to_string();
+    };
+    let mut formatted = year.to_string();
+    for (start, separator) in [(4, "-"), (6, "-"), (8, " "), (10, ":"), (12, ":")] {
+        let Some(value) = part(start, start + 2) else {
+            break;
+        };
+        formatted.push_str(separator);
+        formatted.push_str(value);
+    }
+    formatted
+}
+
+// 从 XMP 元数据的 pdfaid:part 和 pdfaid:conformance 得到 PDF/A 标识
+fn pdfa_conformance(doc: &Document) -> Option<String> {
+    let metadata = doc.catalog().ok()?.get(b"Metadata").ok().and_then(|m| resolve(doc, m))?.as_stream().ok()?;
+    let data = if metadata.dict.has(b"Filter") {
+        metadata.decompressed_content().ok()?
+    } else {
+        metadata.content.clone()
+    };
+    let xmp = String::from_utf8_lossy(&data);
+
+    let part = xmp_property(&xmp, "pdfaid:part")?;
+    let conformance = xmp_property(&xmp, "pdfaid:conformance").unwrap_or_default();
+    Some(format!("PDF/A-{}{}", part, conformance.to_uppercase()))
+}
+
+// XMP 属性值,支持属性写法 name="value" 和元素写法 <name>value</name>
+fn xmp_property(xmp: &str, name: &str) -> Option<String> {
+    let start = xmp.find(name)? + name.len();
+    let rest = xmp[start..].trim_start();
+    let value = match rest.strip_prefix('=') {
+        Some(rest) => {
+            let rest = rest.trim_start();
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            rest[1..].split(quote).next()?
+        }
+        None => rest.strip_prefix('>')?.split('<').next()?,
+    };
+    Some(value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+// 页面和表单 XObject 资源中使用的字体
+fn collect_fonts(doc: &Document) -> Vec<FontInfo> {
+    let mut font_ids: Vec<ObjectId> = Vec::new();
+    let mut visited: HashSet<ObjectId> = HashSet::new();
+    for page_id in doc.get_pages().into_values() {
+        for resources in pdf_extract::page_resources(doc, page_id) {
+            collect_font_ids(doc, resources, 0, &mut font_ids, &mut visited);
+        }
+    }
+    font_ids.iter().filter_map(|&id| doc.get_dictionary(id).ok()).map(|font| font_info(doc, font)).collect()
+}
+
+fn collect_font_ids(
+    doc: &Document,
+    resources: &Dictionary,
+    depth: usize,
+    font_ids: &mut Vec<ObjectId>,
+    visited: &mut HashSet<ObjectId>,
+) {
+    if let Some(fonts) = resources.get(b"Font").ok().and_then(|fonts| resolve_dict(doc, fonts)) {
+        for (_, font) in fonts.iter() {
+            if let Ok(id) = font.as_reference() {
+                if !font_ids.contains(&id) {
+                    font_ids.push(id);
+                }
+            }
+        }
+    }
+
+    if depth >= MAX_FORM_DEPTH {
+        return;
+    }
+    let Some(xobjects) = resources.get(b"XObject").ok().and_then(|xobjects| resolve_dict(doc, xobjects)) else {
+        return;
+    };
+    for (_, xobject) in xobjects.iter() {
+        let Ok(id) = xobject.as_reference() else {
+            continue;
+        };
+        // 多个页面共用的表单只处理一次
+        if !visited.insert(id) {
+            continue;
+        }
+        let form_resources = doc
+            .get_object(id)
+            .and_then(Object::as_stream)
+            .ok()
+            .filter(|stream| stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Form"))
+            .and_then(|stream| stream.dict.get(b"Resources").ok())
+            .and_then(|resources| resolve_dict(doc, resources));
+        if let Some(form_resources) = form_resources {
+            collect_font_ids(doc, form_resources, depth + 1, font_ids, visited);
+        }
+    }
+}
+
+fn font_info(doc: &Document, font: &Dictionary) -> FontInfo {
+    let name = |dict: &Dictionary| dict.get(b"BaseFont").and_then(Object::as_name_str).ok().map(str::to_string);
+    let subtype = font.get(b"Subtype").and_then(Object::as_name_str).unwrap_or("Unknown");
+
+    // Type0 字体的字体描述在 CID 字体中
+    let descendant = font
+        .get(b"DescendantFonts")
+        .ok()
+        .and_then(|fonts| resolve(doc, fonts))
+        .and_then(|fonts| fonts.as_array().ok())
+        .and_then(|fonts| fonts.first())
+        .and_then(|cid_font| resolve_dict(doc, cid_font));
+    let font_type = match descendant.and_then(|cid_font| cid_font.get(b"Subtype").and_then(Object::as_name_str).ok()) {
+        Some(cid_subtype) => format!("{}/{}", subtype, cid_subtype),
+        None => subtype.to_string(),
+    };
+
+    let embedded = subtype == "Type3"
+        || descendant
+            .unwrap_or(font)
+            .get(b"FontDescriptor")
+            .ok()
+            .and_then(|descriptor| resolve_dict(doc, descriptor))
+            .is_some_and(|descriptor| [b"FontFile".as_slice(), b"FontFile2", b"FontFile3"].iter().any(|key| descriptor.has(key)));
+
+    let full_name = name(font).or_else(|| descendant.and_then(name)).unwrap_or_else(|| "Unnamed".to_string());
+    // 子集前缀为 6 个大写字母加 "+"
+    let (name, subset) = match full_name.split_once('+') {
+        Some((tag, name)) if tag.len() == 6 && tag.bytes().all(|b| b.is_ascii_uppercase()) => (name.to_string(), true),
+        _ => (full_name, false),
+    };
+
+    FontInfo {
+        name,
+        font_type,
+        embedded,
+        subset,
+    }
+}
+
+fn collect_images(doc: &Document) -> Vec<ImageInfo> {
+    let display_sizes = pdf_optimize::image_display_sizes(doc);
+    let mut seen: HashSet<ObjectId> = HashSet::new();
+    let mut images = Vec::new();
+
+    for (page_number, page_id) in doc.get_pages() {
+        let mut image_ids = Vec::new();
+        for resources in pdf_extract::page_resources(doc, page_id) {
+            pdf_extract::collect_images(doc, resources, 0, &mut image_ids);
+        }
+
+        for id in image_ids {
+            if !seen.insert(id) {
+                continue;
+            }
+            let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+                continue;
+            };
+            let dict = &stream.dict;
+            let width = dict.get(b"Width").and_then(Object::as_i64).unwrap_or(0);
+            let height = dict.get(b"Height").and_then(Object::as_i64).unwrap_or(0);
+
+            // 横纵方向取较低的分辨率
+            let dpi = display_sizes
+                .get(&id)
+                .filter(|(display_width, display_height)| *display_width > 0.0 && *display_height > 0.0)
+                .map(|(display_width, display_height)| {
+                    (width as f32 / (display_width / 72.0)).min(height as f32 / (display_height / 72.0))
+                });
+
+            images.push(ImageInfo {
+                page: page_number,
+                width,
+                height,
+                color_space: dict
+                    .get(b"ColorSpace")
+                    .ok()
+                    .and_then(|color_space| resolve(doc, color_space))
+                    .map(color_space_name)
+                    .unwrap_or_default(),
+                bits_per_component: dict.get(b"BitsPerComponent").and_then(Object::as_i64).unwrap_or(0),
+                filter: stream.filters().unwrap_or_default().join(", "),
+                dpi,
+            });
+        }
+    }
+    images
+}
+
+fn color_space_name(color_space: &Object) -> String {
+    match color_space {
+        Object::Name(name) => String::from_utf8_lossy(name).to_string(),
+        Object::Array(items) => items
+            .first()
+            .and_then(|name| name.as_name_str().ok())
+            .unwrap_or("Unknown")
+            .to_string(),
+        _ => "Unknown".to_string(),
+    }
+}
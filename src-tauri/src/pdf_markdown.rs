@@ -0,0 +1,877 @@
+use printpdf::path::PaintMode;
+use printpdf::*;
+use pulldown_cmark::{Alignment, Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd};
+use std::path::{Path, PathBuf};
+
+use crate::pdf_fonts::PdfFonts;
+use crate::pdf_generator::{LinkTarget, PageLayout, PdfBuilder, TextAlign};
+use crate::pdf_image::SourceImage;
+use crate::pdf_text::{find_urls, justify_spacing, wrap_runs, PT_PER_MM};
+
+// 每级列表 / 引用的缩进 (毫米)
+const LIST_INDENT_MM: f32 = 7.0;
+const QUOTE_INDENT_MM: f32 = 6.0;
+// 代码块与表格单元格的内边距 (毫米)
+const CODE_PADDING_MM: f32 = 2.0;
+const CELL_PADDING_MM: f32 = 1.5;
+// Courier 为等宽字体,字宽固定为 0.6 em
+const MONO_CHAR_EM: f32 = 0.6;
+// 没有斜体字体,通过倾斜文本矩阵模拟
+const ITALIC_SKEW: f32 = 0.21;
+// 图片按 96 DPI 计算原始尺寸,超出版心时缩小
+const IMAGE_DPI: f32 = 96.0;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    code: bool,
+    strike: bool,
+    link: Option<String>,
+}
+
+type Run = (String, Style);
+
+// 两端对齐时额外的间距 (pt)
+#[derive(Debug, Clone, Copy, Default)]
+struct Spacing {
+    // 每个空格增加的宽度
+    space: f32,
+    // 每个字符后增加的间距
+    char: f32,
+}
+
+struct Table {
+    alignments: Vec<Alignment>,
+    // 每行每个单元格的内容
+    rows: Vec<Vec<Vec<Run>>>,
+    header_rows: usize,
+}
+
+struct Renderer<'a> {
+    builder: &'a mut PdfBuilder,
+    layer: PdfLayerReference,
+    layout: &'a PageLayout,
+    base_dir: Option<&'a Path>,
+    fonts: &'a PdfFonts<'a>,
+    // 代码使用的 Courier,PDF/A 不允许未嵌入的字体,此时代码也用正文字体
+    mono_font: Option<IndirectFontRef>,
+    // 下一行顶部的位置 (毫米,从页面底部算起)
+    y: f32,
+    // 当前块相对左边距的缩进 (毫米)
+    indent: f32,
+    // 引用竖线相对左边距的位置
+    quote_bars: Vec<f32>,
+    // 每级列表的下一个序号,无序列表为 None
+    lists: Vec<Option<u64>>,
+    // 列表项标记,随该项第一行一起绘制
+    pending_marker: Option<String>,
+    runs: Vec<Run>,
+    bold: usize,
+    italic: usize,
+    strike: usize,
+    link: Option<String>,
+    heading: Option<HeadingLevel>,
+    code_block: Option<String>,
+    table: Option<Table>,
+    // 正在读取的图片 (地址, 替代文本)
+    image: Option<(String, String)>,
+}
+
+// 从指定页面开始渲染 Markdown,写满后自动新建页面
+pub fn render_markdown(
+    builder: &mut PdfBuilder,
+    first_layer: PdfLayerReference,
+    markdown: &str,
+    base_dir: Option<&str>,
+    layout: &PageLayout,
+    fonts: &PdfFonts,
+) -> Result<(), String> {
+    let mono_font = if builder.pdfa {
+        None
+    } else {
+        let font = builder
+            .doc
+            .add_builtin_font(BuiltinFont::Courier)
+            .map_err(|e| format!("Failed to load font: {}", e))?;
+        Some(font)
+    };
+
+    let mut renderer = Renderer {
+        builder,
+        layer: first_layer,
+        layout,
+        base_dir: base_dir.map(Path::new),
+        fonts,
+        mono_font,
+        y: layout.content_top_mm(),
+        indent: 0.0,
+        quote_bars: Vec::new(),
+        lists: Vec::new(),
+        pending_marker: None,
+        runs: Vec::new(),
+        bold: 0,
+        italic: 0,
+        strike: 0,
+        link: None,
+        heading: None,
+        code_block: None,
+        table: None,
+        image: None,
+    };
+
+    for event in Parser::new_ext(markdown, parser_options()) {
+        renderer.handle(event)?;
+    }
+    renderer.flush()
+}
+
+// 渲染后显示的文字 (不含 Markdown 标记),用于选择字体
+pub fn plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Text(content)
+            | Event::Code(content)
+            | Event::InlineMath(content)
+            | Event::DisplayMath(content) => text.push_str(&content),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
+
+// 不超过 max_level 的非空标题数量 (层级从 0 开始),用于预留目录页
+pub fn heading_count(markdown: &str, max_level: usize) -> usize {
+    let mut count = 0;
+    let mut heading: Option<(usize, bool)> = None;
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => heading = Some((level as usize - 1, false)),
+            Event::Text(content) | Event::Code(content) | Event::InlineMath(content) => {
+                if let Some((_, has_text)) = heading.as_mut() {
+                    *has_text |= !content.trim().is_empty();
+                }
+            }
+            Event::FootnoteReference(_) => {
+                if let Some((_, has_text)) = heading.as_mut() {
+                    *has_text = true;
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, true)) = heading.take() {
+                    count += usize::from(level <= max_level);
+                }
+            }
+            _ => {}
+        }
+    }
+    count
+}
+
+// 标题的锚点名称,与 GitHub 相同: 转小写,去掉标点,空格换成连字符
+fn heading_anchor(title: &str) -> String {
+    title
+        .trim()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+impl<'a> Renderer<'a> {
+    fn handle(&mut self, event: Event) -> Result<(), String> {
+        match event {
+            Event::Start(tag) => self.start(tag)?,
+            Event::End(tag) => self.end(tag)?,
+            Event::Text(text) => self.text(&text),
+            Event::Code(code) => {
+                let style = Style { code: true, ..self.style() };
+                self.runs.push((code.to_string(), style));
+            }
+            Event::InlineMath(text) | Event::DisplayMath(text) => self.text(&text),
+            Event::FootnoteReference(name) => self.text(&format!("[{}]", name)),
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.text("\n"),
+            Event::Rule => {
+                self.flush()?;
+                self.draw_rule();
+            }
+            Event::TaskListMarker(checked) => {
+                self.pending_marker = Some(if checked { "[x]" } else { "[ ]" }.to_string());
+            }
+            // HTML 不渲染
+            Event::Html(_) | Event::InlineHtml(_) => {}
+        }
+        Ok(())
+    }
+
+    fn start(&mut self, tag: Tag) -> Result<(), String> {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush()?;
+                self.heading = Some(level);
+                self.space(self.font_size() * 0.6);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush()?;
+                self.quote_bars.push(self.indent + 1.0);
+                self.indent += QUOTE_INDENT_MM;
+            }
+            Tag::CodeBlock(_) => {
+                self.flush()?;
+                self.code_block = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.flush()?;
+                self.lists.push(start);
+                self.indent += LIST_INDENT_MM;
+            }
+            Tag::Item => {
+                self.flush()?;
+                let bullet = if self.fonts.measurer.has_glyph('•') { "•" } else { "-" };
+                self.pending_marker = Some(match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => bullet.to_string(),
+                });
+            }
+            Tag::Table(alignments) => {
+                self.flush()?;
+                self.table = Some(Table {
+                    alignments,
+                    rows: Vec::new(),
+                    header_rows: 0,
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Strikethrough => self.strike += 1,
+            Tag::Link { link_type, dest_url, .. } => {
+                self.link = Some(match link_type {
+                    LinkType::Email => format!("mailto:{}", dest_url),
+                    _ => dest_url.to_string(),
+                });
+            }
+            Tag::Image { dest_url, .. } => self.image = Some((dest_url.to_string(), String::new())),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, tag: TagEnd) -> Result<(), String> {
+        match tag {
+            TagEnd::Paragraph => {
+                self.flush()?;
+                self.space(self.layout.font_size * 0.5);
+            }
+            TagEnd::Heading(level) => {
+                // 书签指向标题所在页,先确定标题第一行是否需要换页
+                let title: String = self.runs.iter().map(|(text, _)| text.as_str()).collect();
+                if !title.trim().is_empty() {
+                    self.ensure_space(self.line_height(self.font_size()));
+                    self.builder.add_outline(&title, level as usize - 1);
+                    self.builder.add_anchor(&heading_anchor(&title));
+                }
+                self.flush()?;
+                self.space(self.font_size() * 0.4);
+                self.heading = None;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush()?;
+                self.quote_bars.pop();
+                self.indent -= QUOTE_INDENT_MM;
+                self.space(self.layout.font_size * 0.5);
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code_block.take().unwrap_or_default();
+                self.draw_code_block(&code);
+                self.space(self.layout.font_size * 0.5);
+            }
+            TagEnd::List(_) => {
+                self.flush()?;
+                self.lists.pop();
+                self.indent -= LIST_INDENT_MM;
+                if self.lists.is_empty() {
+                    self.space(self.layout.font_size * 0.5);
+                }
+            }
+            TagEnd::Item => self.flush()?,
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.runs);
+                if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(cell);
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = self.table.as_mut() {
+                    table.header_rows = table.rows.len();
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.draw_table(&table);
+                }
+                self.space(self.layout.font_size * 0.5);
+            }
+            TagEnd::Emphasis => self.italic = self.italic.saturating_sub(1),
+            TagEnd::Strong => self.bold = self.bold.saturating_sub(1),
+            TagEnd::Strikethrough => self.strike = self.strike.saturating_sub(1),
+            TagEnd::Link => self.link = None,
+            TagEnd::Image => {
+                if let Some((url, alt)) = self.image.take() {
+                    self.draw_image(&url, &alt)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some((_, alt)) = self.image.as_mut() {
+            alt.push_str(text);
+        } else if let Some(code) = self.code_block.as_mut() {
+            code.push_str(text);
+        } else if self.link.is_some() {
+            self.push_run(text, self.style());
+        } else {
+            // 正文中的网址自动转为链接
+            let mut pos = 0;
+            for (range, url) in find_urls(text) {
+                self.push_run(&text[pos..range.start], self.style());
+                self.push_run(&text[range.clone()], Style { link: Some(url), ..self.style() });
+                pos = range.end;
+            }
+            self.push_run(&text[pos..], self.style());
+        }
+    }
+
+    fn push_run(&mut self, text: &str, style: Style) {
+        if text.is_empty() {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some((last, last_style)) if *last_style == style => last.push_str(text),
+            _ => self.runs.push((text.to_string(), style)),
+        }
+    }
+
+    fn style(&self) -> Style {
+        Style {
+            bold: self.bold > 0 || self.heading.is_some(),
+            italic: self.italic > 0,
+            code: false,
+            strike: self.strike > 0,
+            link: self.link.clone(),
+        }
+    }
+
+    fn font_size(&self) -> f32 {
+        let scale = match self.heading {
+            Some(HeadingLevel::H1) => 2.0,
+            Some(HeadingLevel::H2) => 1.6,
+            Some(HeadingLevel::H3) => 1.3,
+            Some(HeadingLevel::H4) => 1.15,
+            _ => 1.0,
+        };
+        self.layout.font_size * scale
+    }
+
+    fn line_height(&self, font_size: f32) -> f32 {
+        self.layout.line_height_mm(font_size)
+    }
+
+    fn left(&self) -> f32 {
+        self.layout.margins.left + self.indent
+    }
+
+    fn available_width(&self) -> f32 {
+        self.layout.content_width_mm() - self.indent
+    }
+
+    // 单个字符宽度 (pt),代码中的 ASCII 字符使用等宽字体
+    fn char_width(&self, c: char, style: &Style, font_size: f32) -> f32 {
+        if style.code && c.is_ascii() && self.mono_font.is_some() {
+            MONO_CHAR_EM * font_size
+        } else {
+            self.fonts.measurer.char_width(c, font_size)
+        }
+    }
+
+    // 文本宽度 (毫米)
+    fn text_width(&self, text: &str, style: &Style, font_size: f32) -> f32 {
+        text.chars().map(|c| self.char_width(c, style, font_size)).sum::<f32>() / PT_PER_MM
+    }
+
+    fn wrap(&self, runs: &[Run], width_mm: f32, font_size: f32) -> Vec<Vec<Run>> {
+        self.wrap_segments(runs, width_mm, font_size).into_iter().flatten().collect()
+    }
+
+    // 按硬换行拆分成段,每段分别断行
+    fn wrap_segments(&self, runs: &[Run], width_mm: f32, font_size: f32) -> Vec<Vec<Vec<Run>>> {
+        let mut segments = Vec::new();
+        let mut segment: Vec<Run> = Vec::new();
+        for (text, style) in runs {
+            let mut parts = text.split('\n');
+            if let Some(first) = parts.next() {
+                segment.push((first.to_string(), style.clone()));
+            }
+            for part in parts {
+                segments.push(std::mem::replace(&mut segment, vec![(part.to_string(), style.clone())]));
+            }
+        }
+        segments.push(segment);
+
+        segments
+            .iter()
+            .map(|segment| wrap_runs(segment, width_mm * PT_PER_MM, |c, s| self.char_width(c, s, font_size)))
+            .collect()
+    }
+
+    fn new_page(&mut self) {
+        self.layer = self.builder.add_page(self.layout);
+        self.y = self.layout.content_top_mm();
+    }
+
+    // 剩余空间不足时换页,返回是否换了页
+    fn ensure_space(&mut self, height: f32) -> bool {
+        if self.y - height < self.layout.margins.bottom && self.y < self.layout.content_top_mm() {
+            self.new_page();
+            true
+        } else {
+            false
+        }
+    }
+
+    // 段落间距 (pt),页首不留空
+    fn space(&mut self, points: f32) {
+        if self.y < self.layout.content_top_mm() {
+            self.y = (self.y - points / PT_PER_MM).max(self.layout.margins.bottom);
+        }
+    }
+
+    // 绘制当前累积的文本
+    fn flush(&mut self) -> Result<(), String> {
+        if self.runs.is_empty() && self.pending_marker.is_none() {
+            return Ok(());
+        }
+
+        let runs = std::mem::take(&mut self.runs);
+        let font_size = self.font_size();
+        let available = self.available_width();
+
+        for lines in self.wrap_segments(&runs, available, font_size) {
+            let line_count = lines.len();
+            for (index, line) in lines.into_iter().enumerate() {
+                let (top, baseline) = self.begin_line(font_size);
+                if let Some(marker) = self.pending_marker.take() {
+                    let style = Style::default();
+                    let x = self.left() - self.text_width(&marker, &style, font_size) - 1.5;
+                    self.write_text(&marker, x, baseline, font_size, &style, 0.0);
+                }
+
+                let width: f32 = line.iter().map(|(text, style)| self.text_width(text, style, font_size)).sum();
+                let free = available - width;
+                let (offset, spacing) = match self.layout.alignment {
+                    TextAlign::Left => (0.0, Spacing::default()),
+                    TextAlign::Center => (free / 2.0, Spacing::default()),
+                    TextAlign::Right => (free, Spacing::default()),
+                    // 段落最后一行不做两端对齐
+                    TextAlign::Justify if index + 1 < line_count => {
+                        let text: String = line.iter().map(|(text, _)| text.as_str()).collect();
+                        let (space, char) = justify_spacing(&text, free * PT_PER_MM);
+                        (0.0, Spacing { space, char })
+                    }
+                    TextAlign::Justify => (0.0, Spacing::default()),
+                };
+
+                self.draw_pieces(&line, self.left() + offset, top, baseline, font_size, spacing);
+                self.y -= self.line_height(font_size);
+            }
+        }
+        Ok(())
+    }
+
+    // 开始新的一行: 必要时换页并绘制引用竖线,返回 (行顶, 基线)
+    fn begin_line(&mut self, font_size: f32) -> (f32, f32) {
+        let line_height = self.line_height(font_size);
+        self.ensure_space(line_height);
+
+        let top = self.y;
+        for &bar in &self.quote_bars {
+            let x = self.layout.margins.left + bar;
+            self.stroke_line((x, top), (x, top - line_height), 2.0, gray(0.75));
+        }
+
+        let font_size_mm = font_size / PT_PER_MM;
+        let ascent = self.fonts.measurer.ascent(font_size) / PT_PER_MM;
+        (top, top - (line_height - font_size_mm) / 2.0 - ascent.min(font_size_mm))
+    }
+
+    fn draw_pieces(&mut self, pieces: &[Run], x: f32, top: f32, baseline: f32, font_size: f32, spacing: Spacing) {
+        let line_height = self.line_height(font_size);
+        let mut x = x;
+
+        for (text, style) in pieces {
+            let spaces = text.chars().filter(|c| *c == ' ').count() as f32;
+            let chars = text.chars().count() as f32;
+            let width =
+                self.text_width(text, style, font_size) + (spacing.space * spaces + spacing.char * chars) / PT_PER_MM;
+
+            if style.code {
+                self.fill_rect((x, top - line_height + 0.3), (x + width, top - 0.3), gray(0.92));
+            }
+
+            // 外部字体不支持词间距,两端对齐时逐词定位
+            let words: Vec<&str> = if spacing.space > 0.0 { text.split_inclusive(' ').collect() } else { vec![text] };
+            let mut segment_x = x;
+            for word in words {
+                // 代码中的非 ASCII 字符不在 Courier 中,改用正文字体
+                for segment in split_ascii(word, style.code) {
+                    self.write_text(segment, segment_x, baseline, font_size, style, spacing.char);
+                    segment_x += self.text_width(segment, style, font_size)
+                        + spacing.char * segment.chars().count() as f32 / PT_PER_MM;
+                }
+                if word.ends_with(' ') {
+                    segment_x += spacing.space / PT_PER_MM;
+                }
+            }
+
+            let color = text_color(style);
+            let font_size_mm = font_size / PT_PER_MM;
+            if style.strike {
+                let y = baseline + font_size_mm * 0.3;
+                self.stroke_line((x, y), (x + width, y), font_size * 0.06, color.clone());
+            }
+            if let Some(url) = &style.link {
+                let y = baseline - font_size_mm * 0.12;
+                self.stroke_line((x, y), (x + width, y), font_size * 0.05, color);
+                // "#标题" 链接跳转到文档内的标题
+                if let Some(anchor) = url.strip_prefix('#') {
+                    let target = LinkTarget::Anchor(anchor.to_string());
+                    self.builder.add_internal_link((x, top - line_height), (x + width, top), target);
+                } else {
+                    self.layer.add_link_annotation(LinkAnnotation::new(
+                        printpdf::Rect::new(Mm(x), Mm(top - line_height), Mm(x + width), Mm(top)),
+                        Some(BorderArray::Solid([0.0, 0.0, 0.0])),
+                        Some(ColorArray::Transparent),
+                        Actions::uri(url.clone()),
+                        None,
+                    ));
+                }
+            }
+
+            x += width;
+        }
+    }
+
+    fn write_text(&self, text: &str, x: f32, baseline: f32, font_size: f32, style: &Style, char_spacing: f32) {
+        if text.is_empty() {
+            return;
+        }
+
+        let color = text_color(style);
+        let skew = if style.italic { ITALIC_SKEW } else { 0.0 };
+
+        self.layer.set_fill_color(color.clone());
+        self.layer.begin_text_section();
+        // 没有粗体字体,描边加粗
+        if style.bold {
+            self.layer.set_text_rendering_mode(TextRenderingMode::FillStroke);
+            self.layer.set_outline_color(color);
+            self.layer.set_outline_thickness(font_size * 0.04);
+        }
+        self.layer
+            .set_text_matrix(TextMatrix::Raw([1.0, 0.0, skew, 1.0, x * PT_PER_MM, baseline * PT_PER_MM]));
+        if char_spacing != 0.0 {
+            self.layer.set_character_spacing(char_spacing);
+        }
+        match &self.mono_font {
+            Some(mono_font) if style.code && text.is_ascii() => {
+                self.layer.set_font(mono_font, font_size);
+                self.layer.write_text(text, mono_font);
+            }
+            _ => self.fonts.write_text(&self.layer, text, font_size),
+        }
+        if char_spacing != 0.0 {
+            self.layer.set_character_spacing(0.0);
+        }
+        if style.bold {
+            self.layer.set_text_rendering_mode(TextRenderingMode::Fill);
+        }
+        self.layer.end_text_section();
+    }
+
+    fn draw_code_block(&mut self, code: &str) {
+        let font_size = self.layout.font_size * 0.9;
+        let style = Style { code: true, ..Style::default() };
+        let left = self.left();
+        let right = left + self.available_width();
+
+        self.code_padding(left, right);
+        for raw_line in code.trim_end_matches('\n').split('\n') {
+            let runs = vec![(raw_line.replace('\t', "    "), style.clone())];
+            for line in self.wrap(&runs, self.available_width() - 2.0 * CODE_PADDING_MM, font_size) {
+                let line_height = self.line_height(font_size);
+                let (top, baseline) = self.begin_line(font_size);
+                self.fill_rect((left, top - line_height), (right, top), gray(0.95));
+                let text: String = line.into_iter().map(|(text, _)| text).collect();
+                let mut x = left + CODE_PADDING_MM;
+                for segment in split_ascii(&text, true) {
+                    self.write_text(segment, x, baseline, font_size, &style, 0.0);
+                    x += self.text_width(segment, &style, font_size);
+                }
+                self.y -= line_height;
+            }
+        }
+        self.code_padding(left, right);
+    }
+
+    fn code_padding(&mut self, left: f32, right: f32) {
+        self.ensure_space(CODE_PADDING_MM);
+        self.fill_rect((left, self.y - CODE_PADDING_MM), (right, self.y), gray(0.95));
+        self.y -= CODE_PADDING_MM;
+    }
+
+    fn draw_rule(&mut self) {
+        let height = self.line_height(self.layout.font_size);
+        self.ensure_space(height);
+        let y = self.y - height / 2.0;
+        let left = self.left();
+        self.stroke_line((left, y), (left + self.available_width(), y), 0.75, gray(0.6));
+        self.y -= height;
+    }
+
+    fn draw_table(&mut self, table: &Table) {
+        let columns = table.rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+
+        let font_size = self.layout.font_size;
+        let available = self.available_width();
+        let bold = Style { bold: true, ..Style::default() };
+
+        // 各列不换行时需要的宽度
+        let natural: Vec<f32> = (0..columns)
+            .map(|col| {
+                table
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(col))
+                    .map(|cell| {
+                        cell.iter()
+                            .map(|(text, style)| {
+                                let style = if style.bold { style } else { &bold };
+                                self.text_width(text, style, font_size)
+                            })
+                            .sum::<f32>()
+                    })
+                    .fold(0.0, f32::max)
+                    + 2.0 * CELL_PADDING_MM
+            })
+            .collect();
+
+        let total: f32 = natural.iter().sum();
+        let widths: Vec<f32> = if total <= available {
+            natural
+        } else {
+            // 超出可用宽度时按比例压缩,每列至少保留平均宽度的一半
+            let min = available / columns as f32 / 2.0;
+            let extra: f32 = natural.iter().map(|w| (w - min).max(0.0)).sum();
+            let remaining = available - min * columns as f32;
+            natural.iter().map(|w| min + (w - min).max(0.0) * remaining / extra).collect()
+        };
+
+        for (i, row) in table.rows.iter().enumerate() {
+            let header = i < table.header_rows;
+            let height = self.table_row_lines(row, &widths, header).iter().map(Vec::len).max().unwrap_or(1).max(1)
+                as f32
+                * self.line_height(font_size)
+                + 2.0 * CELL_PADDING_MM;
+
+            // 跨页时在新页面重复表头
+            if self.ensure_space(height) && !header {
+                for header_row in &table.rows[..table.header_rows] {
+                    self.draw_table_row(header_row, &widths, &table.alignments, true);
+                }
+            }
+            self.draw_table_row(row, &widths, &table.alignments, header);
+        }
+    }
+
+    fn table_row_lines(&self, row: &[Vec<Run>], widths: &[f32], header: bool) -> Vec<Vec<Vec<Run>>> {
+        widths
+            .iter()
+            .enumerate()
+            .map(|(col, width)| {
+                let Some(cell) = row.get(col) else {
+                    return Vec::new();
+                };
+                let cell: Vec<Run> = cell
+                    .iter()
+                    .map(|(text, style)| (text.clone(), Style { bold: style.bold || header, ..style.clone() }))
+                    .collect();
+                self.wrap(&cell, width - 2.0 * CELL_PADDING_MM, self.layout.font_size)
+            })
+            .collect()
+    }
+
+    fn draw_table_row(&mut self, row: &[Vec<Run>], widths: &[f32], alignments: &[Alignment], header: bool) {
+        let font_size = self.layout.font_size;
+        let line_height = self.line_height(font_size);
+        let cells = self.table_row_lines(row, widths, header);
+        let lines = cells.iter().map(Vec::len).max().unwrap_or(1).max(1);
+        let height = lines as f32 * line_height + 2.0 * CELL_PADDING_MM;
+        self.ensure_space(height);
+
+        let top = self.y;
+        let font_size_mm = font_size / PT_PER_MM;
+        let ascent = (self.fonts.measurer.ascent(font_size) / PT_PER_MM).min(font_size_mm);
+        let mut x = self.left();
+
+        for (col, (cell, &width)) in cells.iter().zip(widths).enumerate() {
+            if header {
+                self.fill_rect((x, top - height), (x + width, top), gray(0.9));
+            }
+            self.layer.set_outline_color(gray(0.5));
+            self.layer.set_outline_thickness(0.5);
+            self.layer.add_rect(
+                printpdf::Rect::new(Mm(x), Mm(top - height), Mm(x + width), Mm(top)).with_mode(PaintMode::Stroke),
+            );
+
+            for (n, line) in cell.iter().enumerate() {
+                let line_width: f32 = line.iter().map(|(text, style)| self.text_width(text, style, font_size)).sum();
+                let offset = match alignments.get(col) {
+                    Some(Alignment::Center) => (width - line_width) / 2.0,
+                    Some(Alignment::Right) => width - CELL_PADDING_MM - line_width,
+                    _ => CELL_PADDING_MM,
+                };
+                let line_top = top - CELL_PADDING_MM - n as f32 * line_height;
+                let baseline = line_top - (line_height - font_size_mm) / 2.0 - ascent;
+                self.draw_pieces(line, x + offset, line_top, baseline, font_size, Spacing::default());
+            }
+
+            x += width;
+        }
+
+        self.y -= height;
+    }
+
+    fn draw_image(&mut self, url: &str, alt: &str) -> Result<(), String> {
+        let img = match self.resolve_image_path(url) {
+            Some(path) => SourceImage::open(&path.to_string_lossy(), &[]),
+            None => Err(format!("Unsupported image source: {}", url)),
+        };
+
+        let img = match img {
+            Ok(img) => img,
+            Err(e) => {
+                // 图片无法加载时保留替代文本
+                println!("  图片加载失败,使用替代文本: {}", e);
+                let text = if alt.is_empty() { url } else { alt };
+                let style = Style { italic: true, ..self.style() };
+                self.runs.push((format!("[{}]", text), style));
+                return Ok(());
+            }
+        };
+
+        // 图片独占一块,之前的文字先排版
+        self.flush()?;
+
+        let mut width = img.width() as f32 * 25.4 / IMAGE_DPI;
+        let mut height = img.height() as f32 * 25.4 / IMAGE_DPI;
+        let scale = (self.available_width() / width).min(self.layout.content_height_mm() / height).min(1.0);
+        width *= scale;
+        height *= scale;
+
+        self.ensure_space(height);
+        let (x, y) = (self.left(), self.y - height);
+        self.builder.place_image(&self.layer, &img, x, y, width, height)?;
+        self.y -= height;
+        self.space(self.layout.font_size * 0.5);
+
+        Ok(())
+    }
+
+    // 本地图片路径,相对路径基于 base_dir; 不支持网络图片
+    fn resolve_image_path(&self, url: &str) -> Option<PathBuf> {
+        if url.starts_with("http://") || url.starts_with("https://") || url.starts_with("data:") {
+            return None;
+        }
+
+        let path = Path::new(url.strip_prefix("file://").unwrap_or(url));
+        match self.base_dir {
+            Some(base_dir) if path.is_relative() => Some(base_dir.join(path)),
+            _ => Some(path.to_path_buf()),
+        }
+    }
+
+    fn stroke_line(&self, from: (f32, f32), to: (f32, f32), thickness: f32, color: Color) {
+        self.layer.set_outline_color(color);
+        self.layer.set_outline_thickness(thickness);
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(from.0), Mm(from.1)), false),
+                (Point::new(Mm(to.0), Mm(to.1)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    fn fill_rect(&self, lower_left: (f32, f32), upper_right: (f32, f32), color: Color) {
+        self.layer.set_fill_color(color);
+        self.layer.add_rect(
+            printpdf::Rect::new(Mm(lower_left.0), Mm(lower_left.1), Mm(upper_right.0), Mm(upper_right.1))
+                .with_mode(PaintMode::Fill),
+        );
+    }
+}
+
+fn gray(level: f32) -> Color {
+    Color::Rgb(Rgb::new(level, level, level, None))
+}
+
+fn text_color(style: &Style) -> Color {
+    if style.link.is_some() {
+        Color::Rgb(Rgb::new(0.0, 0.27, 0.8, None))
+    } else {
+        Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None))
+    }
+}
+
+// 代码文本按 ASCII / 非 ASCII 拆段,分别使用等宽字体和正文字体
+fn split_ascii(text: &str, code: bool) -> Vec<&str> {
+    if !code {
+        return vec![text];
+    }
+
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut ascii = None;
+    for (i, c) in text.char_indices() {
+        if ascii.is_some_and(|ascii| ascii != c.is_ascii()) {
+            segments.push(&text[start..i]);
+            start = i;
+        }
+        ascii = Some(c.is_ascii());
+    }
+    if start < text.len() {
+        segments.push(&text[start..]);
+    }
+    segments
+}
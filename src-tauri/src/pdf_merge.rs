@@ -0,0 +1,435 @@
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::pdf_generator::{self, PdfImageInput};
+use crate::pdf_security::{self, PdfEncryption};
+use crate::pdf_utils::{self, resolve, resolve_dict};
+
+// 单个来源文件在合并后文档中的信息
+struct MergedSource {
+    title: String,
+    first_page: ObjectId,
+    // 原文件书签的第一个和最后一个顶层条目
+    outline_children: Option<(ObjectId, ObjectId)>,
+}
+
+// 合并后的表单: 以第一个带表单的文件的 AcroForm 为基础,收集所有文件的域和默认资源
+#[derive(Default)]
+struct MergedForm {
+    form: Option<Dictionary>,
+    fields: Vec<Object>,
+    // 已使用的顶层域名称
+    names: HashSet<String>,
+    // 默认资源 (DR),按类别 (Font 等) 合并
+    resources: Dictionary,
+}
+
+// 按顺序合并 PDF 文件,图片文件作为单独页面插入
+pub fn merge_pdfs(
+    inputs: Vec<PdfImageInput>,
+    encryption: Option<&PdfEncryption>,
+    output_path: &str,
+) -> Result<(), String> {
+    if inputs.is_empty() {
+        return Err("No files to merge".to_string());
+    }
+
+    let mut merged = Document::with_version("1.5");
+    let pages_id = merged.new_object_id();
+
+    let mut page_ids: Vec<ObjectId> = Vec::new();
+    let mut sources: Vec<MergedSource> = Vec::new();
+    let mut dests = Dictionary::new();
+    let mut named_dests: BTreeMap<Vec<u8>, Object> = BTreeMap::new();
+    let mut form = MergedForm::default();
+
+    for (index, input) in inputs.iter().enumerate() {
+        let path = input.path();
+        println!("合并文件: {}", path);
+
+        let mut doc = if pdf_utils::is_pdf_path(path) {
+            pdf_utils::load_pdf(path)?
+        } else {
+            pdf_generator::image_to_pdf_document(input)?
+        };
+
+        if doc.version > merged.version {
+            merged.version = doc.version.clone();
+        }
+
+        // 重新编号,避免与已合并对象的 ID 冲突
+        pdf_utils::flatten_inherited_page_attributes(&mut doc);
+        doc.renumber_objects_with(merged.max_id + 1);
+
+        let source_pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+        let Some(&first_page) = source_pages.first() else {
+            println!("  跳过没有页面的文件: {}", path);
+            continue;
+        };
+
+        let catalog = doc.catalog().map_err(|e| format!("Failed to read catalog of {}: {}", path, e))?.clone();
+        let catalog_id = doc.trailer.get(b"Root").and_then(Object::as_reference).ok();
+        let info_id = doc.trailer.get(b"Info").and_then(Object::as_reference).ok();
+
+        let outline_children = catalog
+            .get(b"Outlines")
+            .ok()
+            .and_then(|outlines| resolve_dict(&doc, outlines))
+            .and_then(|outlines| {
+                let first = outlines.get(b"First").and_then(Object::as_reference).ok()?;
+                let last = outlines.get(b"Last").and_then(Object::as_reference).ok()?;
+                Some((first, last))
+            });
+        let outlines_root_id = catalog.get(b"Outlines").and_then(Object::as_reference).ok();
+
+        // 命名目标加上来源前缀,不同文件中的同名目标互不覆盖
+        let prefix = format!("f{}_", index + 1);
+        collect_dests(&doc, &catalog, prefix.as_bytes(), &mut dests, &mut named_dests);
+        for object in doc.objects.values_mut() {
+            rename_dest_references(object, prefix.as_bytes());
+        }
+        collect_form_fields(&mut doc, &catalog, index, &mut form);
+
+        // 复制对象,原目录、页面树节点和文档信息由合并后的文档重新生成
+        for (id, object) in doc.objects.into_iter() {
+            if Some(id) == catalog_id || Some(id) == info_id || Some(id) == outlines_root_id {
+                continue;
+            }
+            if let Object::Dictionary(dict) = &object {
+                if dict.type_is(b"Pages") {
+                    continue;
+                }
+            }
+            merged.objects.insert(id, object);
+        }
+        merged.max_id = merged.max_id.max(doc.max_id);
+
+        for &page_id in &source_pages {
+            if let Ok(page) = merged.get_dictionary_mut(page_id) {
+                page.set("Parent", pages_id);
+            }
+        }
+        page_ids.extend(source_pages);
+
+        sources.push(MergedSource {
+            title: pdf_utils::file_stem(path),
+            first_page,
+            outline_children,
+        });
+    }
+
+    if page_ids.is_empty() {
+        return Err("Merged document has no pages".to_string());
+    }
+
+    let pages = Dictionary::from_iter(vec![
+        ("Type", "Pages".into()),
+        ("Count", (page_ids.len() as i64).into()),
+        ("Kids", page_ids.iter().map(|&id| Object::Reference(id)).collect::<Vec<_>>().into()),
+    ]);
+    merged.objects.insert(pages_id, Object::Dictionary(pages));
+
+    let outlines_id = build_outlines(&mut merged, &sources);
+
+    let mut catalog = Dictionary::from_iter(vec![
+        ("Type", "Catalog".into()),
+        ("Pages", pages_id.into()),
+        ("Outlines", outlines_id.into()),
+        ("PageMode", "UseOutlines".into()),
+    ]);
+
+    if !dests.is_empty() {
+        catalog.set("Dests", merged.add_object(dests));
+    }
+
+    if !named_dests.is_empty() {
+        let names: Vec<Object> = named_dests
+            .into_iter()
+            .flat_map(|(name, dest)| [Object::string_literal(name), dest])
+            .collect();
+        let dests_tree = merged.add_object(Dictionary::from_iter(vec![("Names", Object::Array(names))]));
+        catalog.set("Names", Dictionary::from_iter(vec![("Dests", Object::Reference(dests_tree))]));
+    }
+
+    if let Some(mut acro_form) = form.form {
+        acro_form.set("Fields", form.fields);
+        acro_form.remove(b"DR");
+        if !form.resources.is_empty() {
+            acro_form.set("DR", form.resources);
+        }
+        catalog.set("AcroForm", merged.add_object(acro_form));
+    }
+
+    let catalog_id = merged.add_object(catalog);
+    merged.trailer.set("Root", catalog_id);
+
+    // 清理不再被引用的对象 (如原文档的结构树)
+    merged.prune_objects();
+    merged.compress();
+    if let Some(encryption) = encryption {
+        pdf_security::encrypt_document(&mut merged, encryption)?;
+    }
+
+    pdf_utils::save_pdf(&mut merged, output_path)?;
+    println!("合并完成: {} 个文件, {} 页", sources.len(), page_ids.len());
+
+    Ok(())
+}
+
+// 每个来源文件一个顶层书签,原文件书签挂在其下
+fn build_outlines(doc: &mut Document, sources: &[MergedSource]) -> ObjectId {
+    let outlines_id = doc.new_object_id();
+    let item_ids: Vec<ObjectId> = sources.iter().map(|_| doc.new_object_id()).collect();
+
+    for (i, source) in sources.iter().enumerate() {
+        let mut item = Dictionary::from_iter(vec![
+            ("Title", pdf_utils::text_string(&source.title)),
+            ("Parent", outlines_id.into()),
+            ("Dest", vec![source.first_page.into(), "Fit".into()].into()),
+        ]);
+        if i > 0 {
+            item.set("Prev", item_ids[i - 1]);
+        }
+        if i + 1 < item_ids.len() {
+            item.set("Next", item_ids[i + 1]);
+        }
+
+        if let Some((first, last)) = source.outline_children {
+            let mut children = 0i64;
+            let mut child = Some(first);
+            while let Some(child_id) = child {
+                let Ok(child_dict) = doc.get_dictionary_mut(child_id) else {
+                    break;
+                };
+                child_dict.set("Parent", item_ids[i]);
+                children += 1;
+                if child_id == last {
+                    break;
+                }
+                child = child_dict.get(b"Next").and_then(Object::as_reference).ok();
+            }
+
+            item.set("First", first);
+            item.set("Last", last);
+            // 负数表示默认折叠
+            item.set("Count", -children);
+        }
+
+        doc.objects.insert(item_ids[i], Object::Dictionary(item));
+    }
+
+    let mut outlines = Dictionary::from_iter(vec![
+        ("Type", "Outlines".into()),
+        ("Count", (item_ids.len() as i64).into()),
+    ]);
+    if let (Some(&first), Some(&last)) = (item_ids.first(), item_ids.last()) {
+        outlines.set("First", first);
+        outlines.set("Last", last);
+    }
+    doc.objects.insert(outlines_id, Object::Dictionary(outlines));
+
+    outlines_id
+}
+
+// 收集命名目标,链接使用名称跳转时依赖它们; 名称加上来源前缀
+fn collect_dests(
+    doc: &Document,
+    catalog: &Dictionary,
+    prefix: &[u8],
+    dests: &mut Dictionary,
+    named_dests: &mut BTreeMap<Vec<u8>, Object>,
+) {
+    let prefixed = |name: &[u8]| [prefix, name].concat();
+
+    if let Some(old_dests) = catalog.get(b"Dests").ok().and_then(|d| resolve_dict(doc, d)) {
+        for (name, dest) in old_dests.iter() {
+            dests.set(prefixed(name), dest.clone());
+        }
+    }
+
+    let tree = catalog
+        .get(b"Names")
+        .ok()
+        .and_then(|names| resolve_dict(doc, names))
+        .and_then(|names| names.get(b"Dests").ok())
+        .and_then(|tree| resolve_dict(doc, tree));
+    if let Some(tree) = tree {
+        let mut source_dests = BTreeMap::new();
+        pdf_utils::collect_name_tree(doc, tree, &mut source_dests, 0);
+        named_dests.extend(source_dests.into_iter().map(|(name, dest)| (prefixed(&name), dest)));
+    }
+}
+
+// 给链接、书签的 Dest 和 GoTo 动作中按名称引用的目标加上来源前缀 (显式目标数组不变)
+fn rename_dest_references(object: &mut Object, prefix: &[u8]) {
+    let dict = match object {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &mut stream.dict,
+        Object::Array(items) => {
+            items.iter_mut().for_each(|item| rename_dest_references(item, prefix));
+            return;
+        }
+        _ => return,
+    };
+
+    let is_goto = dict.get(b"S").and_then(Object::as_name).is_ok_and(|kind| kind == b"GoTo");
+    for (key, value) in dict.iter_mut() {
+        if key == b"Dest" || (is_goto && key == b"D") {
+            match value {
+                Object::Name(name) | Object::String(name, _) => *name = [prefix, name.as_slice()].concat(),
+                _ => {}
+            }
+        } else {
+            // 动作可能直接写在注释或书签字典中
+            rename_dest_references(value, prefix);
+        }
+    }
+}
+
+// 合并表单域,保留第一个表单的默认资源和外观设置;
+// 与之前文件中同名的顶层域改名,避免不同文件的域合并为同一个域而互相覆盖值
+fn collect_form_fields(doc: &mut Document, catalog: &Dictionary, index: usize, merged: &mut MergedForm) {
+    let Some(form) = catalog.get(b"AcroForm").ok().and_then(|f| resolve_dict(doc, f)).cloned() else {
+        return;
+    };
+
+    let font_renames = merge_default_resources(doc, &form, index, &mut merged.resources);
+    let source_fields: Vec<Object> = form
+        .get(b"Fields")
+        .ok()
+        .and_then(|f| resolve(doc, f))
+        .and_then(|f| f.as_array().ok())
+        .cloned()
+        .unwrap_or_default();
+
+    // 域的 DA 引用改名后的字体
+    if !font_renames.is_empty() {
+        let mut pending: Vec<ObjectId> = source_fields.iter().filter_map(|f| f.as_reference().ok()).collect();
+        let mut visited = HashSet::new();
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let Ok(field) = doc.get_dictionary_mut(id) else {
+                continue;
+            };
+            if let Ok(Object::String(da, _)) = field.get_mut(b"DA") {
+                if let Some(renamed) = rename_da_fonts(da, &font_renames) {
+                    *da = renamed;
+                }
+            }
+            if let Ok(kids) = field.get(b"Kids").and_then(Object::as_array) {
+                pending.extend(kids.iter().filter_map(|kid| kid.as_reference().ok()));
+            }
+        }
+    }
+
+    // 文件级的 DA、Q 与合并后的表单不同时,下放到没有自己设置的顶层域
+    let mut inherited: Vec<(&str, Object)> = Vec::new();
+    if let Some(base) = &merged.form {
+        let da = form.get(b"DA").and_then(Object::as_str).ok().map(|da| {
+            rename_da_fonts(da, &font_renames).unwrap_or_else(|| da.to_vec())
+        });
+        if let Some(da) = da.filter(|da| base.get(b"DA").and_then(Object::as_str).ok() != Some(da.as_slice())) {
+            inherited.push(("DA", Object::string_literal(da)));
+        }
+        let quadding = form.get(b"Q").and_then(Object::as_i64).unwrap_or(0);
+        if base.get(b"Q").and_then(Object::as_i64).unwrap_or(0) != quadding {
+            inherited.push(("Q", Object::Integer(quadding)));
+        }
+    }
+
+    let mut source_names = HashSet::new();
+    for mut field in source_fields {
+        let dict = match &mut field {
+            Object::Reference(id) => doc.get_dictionary_mut(*id).ok(),
+            Object::Dictionary(dict) => Some(dict),
+            _ => None,
+        };
+        if let Some(dict) = dict {
+            let name = dict.get(b"T").and_then(Object::as_str).map(pdf_utils::decode_text_string).unwrap_or_default();
+            let mut unique = name.clone();
+            let mut suffix = index + 1;
+            while !name.is_empty() && merged.names.contains(&unique) {
+                unique = format!("{}_{}", name, suffix);
+                suffix += 1;
+            }
+            if unique != name {
+                println!("  重命名表单域: {} -> {}", name, unique);
+                dict.set("T", pdf_utils::text_string(&unique));
+            }
+            source_names.insert(unique);
+
+            for (key, value) in &inherited {
+                if !dict.has(key.as_bytes()) {
+                    dict.set(*key, value.clone());
+                }
+            }
+        }
+        merged.fields.push(field);
+    }
+    merged.names.extend(source_names);
+
+    if merged.form.is_none() {
+        let mut base = form;
+        base.remove(b"XFA");
+        merged.form = Some(base);
+    }
+}
+
+// 把来源文件的默认资源并入合并后的 DR; 同名资源加上来源序号改名,返回字体的改名表
+fn merge_default_resources(
+    doc: &Document,
+    form: &Dictionary,
+    index: usize,
+    resources: &mut Dictionary,
+) -> BTreeMap<Vec<u8>, Vec<u8>> {
+    let mut font_renames = BTreeMap::new();
+    let Some(source) = form.get(b"DR").ok().and_then(|dr| resolve_dict(doc, dr)) else {
+        return font_renames;
+    };
+
+    for (category, entries) in source.iter() {
+        let Some(entries) = resolve_dict(doc, entries) else {
+            continue;
+        };
+        if !resources.has(category) {
+            resources.set(category.clone(), Dictionary::new());
+        }
+        let Ok(Object::Dictionary(merged)) = resources.get_mut(category) else {
+            continue;
+        };
+
+        for (name, value) in entries.iter() {
+            let mut unique = name.clone();
+            let mut suffix = index + 1;
+            while merged.has(&unique) {
+                unique = [name.as_slice(), format!("_{}", suffix).as_bytes()].concat();
+                suffix += 1;
+            }
+            if unique != *name && category == b"Font" {
+                font_renames.insert(name.clone(), unique.clone());
+            }
+            merged.set(unique, value.clone());
+        }
+    }
+    font_renames
+}
+
+// 改写 DA 中 Tf 使用的字体资源名,没有需要改写的名称时返回 None
+fn rename_da_fonts(da: &[u8], renames: &BTreeMap<Vec<u8>, Vec<u8>>) -> Option<Vec<u8>> {
+    let text = String::from_utf8_lossy(da);
+    let mut tokens: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+    let mut changed = false;
+    for i in 0..tokens.len().saturating_sub(2) {
+        if tokens[i + 2] != "Tf" {
+            continue;
+        }
+        let Some(new_name) = tokens[i].strip_prefix('/').and_then(|name| renames.get(name.as_bytes())) else {
+            continue;
+        };
+        tokens[i] = format!("/{}", String::from_utf8_lossy(new_name));
+        changed = true;
+    }
+    changed.then(|| tokens.join(" ").into_bytes())
+}
@@ -0,0 +1,746 @@
+use ::image::imageops::FilterType;
+use ::image::{DynamicImage, GrayImage, ImageFormat, RgbImage};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use printpdf::lopdf::content::Content;
+use printpdf::lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+
+use crate::image_compress;
+use crate::pdf_image;
+use crate::pdf_utils::{self, resolve, resolve_dict};
+
+// 分辨率超出目标不到这个比例时不缩小,避免为少量像素重新编码
+const DOWNSAMPLE_THRESHOLD: f32 = 1.1;
+// 单个对象流中最多打包的对象数
+const OBJECTS_PER_STREAM: usize = 100;
+// 嵌套表单 XObject 的最大深度,防止循环引用
+const MAX_FORM_DEPTH: usize = 8;
+// 超出目标大小时依次尝试的 JPEG 质量和最高 DPI
+const SIZE_STEPS: [(u8, f32); 6] = [(75, 150.0), (65, 150.0), (55, 120.0), (45, 110.0), (35, 96.0), (25, 72.0)];
+
+const IDENTITY: [f32; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PdfOptimizeOptions {
+    // 图片最高分辨率,按图片在页面上的显示尺寸计算
+    pub max_dpi: Option<f32>,
+    // 设置后可处理的图片都以该质量重新编码为 JPEG
+    pub jpeg_quality: Option<u8>,
+    // 目标文件大小,超出时逐步降低图片质量和分辨率
+    pub max_size_kb: Option<u32>,
+}
+
+impl Default for PdfOptimizeOptions {
+    fn default() -> Self {
+        Self {
+            max_dpi: Some(150.0),
+            jpeg_quality: None,
+            max_size_kb: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PdfOptimizeResult {
+    pub original_size: u64,
+    pub optimized_size: u64,
+    pub images_recompressed: usize,
+    pub unused_objects_removed: usize,
+    pub duplicate_streams_removed: usize,
+}
+
+// 单次图片处理使用的设置
+#[derive(Debug, Clone, Copy)]
+struct ImageSettings {
+    max_dpi: Option<f32>,
+    quality: Option<u8>,
+}
+
+pub fn optimize_pdf<F>(
+    path: &str,
+    output_path: &str,
+    options: &PdfOptimizeOptions,
+    mut progress_callback: F,
+) -> Result<PdfOptimizeResult, String>
+where
+    F: FnMut(u8),
+{
+    println!("开始优化 PDF: {}", path);
+    progress_callback(0);
+
+    let original_size = fs::metadata(path).map_err(|e| format!("Failed to read PDF: {}", e))?.len();
+    let mut doc = pdf_utils::load_pdf(path)?;
+    println!("原始文件大小: {:.2} KB", original_size as f64 / 1024.0);
+    progress_callback(10);
+
+    let unused_objects_removed = doc.prune_objects().len();
+    let duplicate_streams_removed = remove_duplicate_streams(&mut doc);
+    println!("删除未引用对象 {} 个,重复流 {} 个", unused_objects_removed, duplicate_streams_removed);
+    progress_callback(20);
+
+    let display_sizes = image_display_sizes(&doc);
+    progress_callback(30);
+
+    let settings = ImageSettings {
+        max_dpi: options.max_dpi,
+        quality: options.jpeg_quality,
+    };
+    let (mut optimized, mut images_recompressed) = optimize_images(&doc, &display_sizes, settings)?;
+    progress_callback(60);
+
+    // 超出目标大小时逐步降低质量和分辨率,都达不到时保留最小的结果
+    if let Some(max_size_kb) = options.max_size_kb {
+        let target_size = max_size_kb as u64 * 1024;
+        for (step, &(quality, max_dpi)) in SIZE_STEPS.iter().enumerate() {
+            if optimized.len() as u64 <= target_size {
+                break;
+            }
+
+            let settings = ImageSettings {
+                max_dpi: Some(options.max_dpi.map_or(max_dpi, |dpi| dpi.min(max_dpi))),
+                quality: Some(options.jpeg_quality.map_or(quality, |q| q.min(quality))),
+            };
+            let (data, count) = optimize_images(&doc, &display_sizes, settings)?;
+            println!(
+                "质量 {:?} / {:?} DPI: {:.2} KB",
+                settings.quality,
+                settings.max_dpi,
+                data.len() as f64 / 1024.0
+            );
+            if data.len() < optimized.len() {
+                optimized = data;
+                images_recompressed = count;
+            }
+            progress_callback(60 + (35 * (step + 1) / SIZE_STEPS.len()) as u8);
+        }
+
+        if optimized.len() as u64 > target_size {
+            println!("无法达到目标大小 {}KB,使用最小结果", max_size_kb);
+        }
+    }
+    progress_callback(95);
+
+    // 优化后反而更大时保留原文件
+    let optimized_size = optimized.len() as u64;
+    let result = if optimized_size >= original_size {
+        println!("优化后没有变小,保留原文件");
+        if path != output_path {
+            fs::copy(path, output_path).map_err(|e| format!("Failed to save PDF: {}", e))?;
+        }
+        PdfOptimizeResult {
+            original_size,
+            optimized_size: original_size,
+            images_recompressed: 0,
+            unused_objects_removed: 0,
+            duplicate_streams_removed: 0,
+        }
+    } else {
+        fs::write(output_path, &optimized).map_err(|e| format!("Failed to save PDF: {}", e))?;
+        println!(
+            "优化完成: {:.2} KB -> {:.2} KB, 减少 {:.1}%",
+            original_size as f64 / 1024.0,
+            optimized_size as f64 / 1024.0,
+            (1.0 - optimized_size as f64 / original_size as f64) * 100.0
+        );
+        PdfOptimizeResult {
+            original_size,
+            optimized_size,
+            images_recompressed,
+            unused_objects_removed,
+            duplicate_streams_removed,
+        }
+    };
+
+    progress_callback(100);
+    Ok(result)
+}
+
+// 在文档副本上重新压缩图片并写出,返回文件数据和处理的图片数
+fn optimize_images(
+    doc: &Document,
+    display_sizes: &HashMap<ObjectId, (f32, f32)>,
+    settings: ImageSettings,
+) -> Result<(Vec<u8>, usize), String> {
+    let mut doc = doc.clone();
+    let masks = mask_ids(&doc);
+
+    let mut recompressed = 0;
+    let image_ids: Vec<ObjectId> = doc
+        .objects
+        .iter()
+        .filter(|(id, object)| !masks.contains(id) && object.as_stream().is_ok_and(is_image))
+        .map(|(&id, _)| id)
+        .collect();
+    for id in image_ids {
+        let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+            continue;
+        };
+        if let Some(image) = recompress_image(&doc, stream, display_sizes.get(&id).copied(), settings) {
+            doc.objects.insert(id, Object::Stream(image));
+            recompressed += 1;
+        }
+    }
+
+    doc.compress();
+    doc.renumber_objects();
+    Ok((write_compact(&doc)?, recompressed))
+}
+
+// 合并内容完全相同的流 (字体、图片等),引用统一指向第一个
+fn remove_duplicate_streams(doc: &mut Document) -> usize {
+    let mut removed = 0;
+    // 合并后引用这些流的对象也可能变得相同,重复直到没有变化
+    loop {
+        let mut seen: HashMap<Vec<u8>, ObjectId> = HashMap::new();
+        let mut replace: HashMap<ObjectId, ObjectId> = HashMap::new();
+        for (&id, object) in &doc.objects {
+            if let Object::Stream(stream) = object {
+                let mut hasher = Sha256::new();
+                hasher.update(format!("{:?}", stream.dict).as_bytes());
+                hasher.update(&stream.content);
+                let digest = hasher.finalize().to_vec();
+                match seen.get(&digest) {
+                    Some(&first) => {
+                        replace.insert(id, first);
+                    }
+                    None => {
+                        seen.insert(digest, id);
+                    }
+                }
+            }
+        }
+
+        if replace.is_empty() {
+            return removed;
+        }
+        removed += replace.len();
+        for id in replace.keys() {
+            doc.objects.remove(id);
+        }
+        doc.traverse_objects(|object| {
+            if let Object::Reference(id) = object {
+                if let Some(&first) = replace.get(id) {
+                    *id = first;
+                }
+            }
+        });
+    }
+}
+
+fn is_image(stream: &Stream) -> bool {
+    stream.dict.get(b"Subtype").and_then(Object::as_name).is_ok_and(|name| name == b"Image")
+}
+
+// 作为其他图片 SMask 或 Mask 使用的图片,保持原样
+fn mask_ids(doc: &Document) -> HashSet<ObjectId> {
+    doc.objects
+        .values()
+        .filter_map(|object| object.as_stream().ok())
+        .filter(|stream| is_image(stream))
+        .flat_map(|stream| [b"SMask".as_slice(), b"Mask"].map(|key| stream.dict.get(key).and_then(Object::as_reference)))
+        .filter_map(Result::ok)
+        .collect()
+}
+
+// 每张图片在页面上的最大显示尺寸 (点),由内容流中的 cm 和 Do 计算
+pub(crate) fn image_display_sizes(doc: &Document) -> HashMap<ObjectId, (f32, f32)> {
+    let mut sizes = HashMap::new();
+    for page_id in doc.get_pages().into_values() {
+        let Ok(content) = doc.get_page_content(page_id) else {
+            continue;
+        };
+        let (resource_dict, resource_ids) = doc.get_page_resources(page_id);
+        let mut resources: Vec<&Dictionary> = resource_dict.into_iter().collect();
+        resources.extend(resource_ids.into_iter().filter_map(|id| doc.get_dictionary(id).ok()));
+        scan_content(doc, &content, &resources, IDENTITY, 0, &mut sizes);
+    }
+    sizes
+}
+
+fn scan_content(
+    doc: &Document,
+    data: &[u8],
+    resources: &[&Dictionary],
+    base_ctm: [f32; 6],
+    depth: usize,
+    sizes: &mut HashMap<ObjectId, (f32, f32)>,
+) {
+    let Ok(content) = Content::decode(data) else {
+        return;
+    };
+
+    let mut ctm = base_ctm;
+    let mut saved = Vec::new();
+    for operation in &content.operations {
+        match operation.operator.as_str() {
+            "q" => saved.push(ctm),
+            "Q" => ctm = saved.pop().unwrap_or(base_ctm),
+            "cm" => {
+                if let Some(matrix) = read_matrix(&operation.operands) {
+                    ctm = multiply(&matrix, &ctm);
+                }
+            }
+            "Do" => {
+                let Some(id) = operation
+                    .operands
+                    .first()
+                    .and_then(|name| name.as_name().ok())
+                    .and_then(|name| find_xobject(doc, resources, name))
+                else {
+                    continue;
+                };
+                let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+                    continue;
+                };
+
+                if is_image(stream) {
+                    // 图片绘制在单位正方形中,显示尺寸就是变换矩阵两个轴的长度
+                    let width = ctm[0].hypot(ctm[1]);
+                    let height = ctm[2].hypot(ctm[3]);
+                    let size = sizes.entry(id).or_insert((0.0, 0.0));
+                    *size = (size.0.max(width), size.1.max(height));
+                } else if depth < MAX_FORM_DEPTH {
+                    let form_content = if stream.dict.has(b"Filter") {
+                        let Ok(data) = stream.decompressed_content() else {
+                            continue;
+                        };
+                        data
+                    } else {
+                        stream.content.clone()
+                    };
+                    let matrix = stream
+                        .dict
+                        .get(b"Matrix")
+                        .ok()
+                        .and_then(|matrix| matrix.as_array().ok())
+                        .and_then(|matrix| read_matrix(matrix))
+                        .unwrap_or(IDENTITY);
+                    // 表单没有自己的资源时沿用上层资源
+                    let form_resources = match stream.dict.get(b"Resources").ok().and_then(|r| resolve_dict(doc, r)) {
+                        Some(dict) => vec![dict],
+                        None => resources.to_vec(),
+                    };
+                    scan_content(doc, &form_content, &form_resources, multiply(&matrix, &ctm), depth + 1, sizes);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn find_xobject(doc: &Document, resources: &[&Dictionary], name: &[u8]) -> Option<ObjectId> {
+    resources.iter().find_map(|dict| {
+        let xobjects = resolve_dict(doc, dict.get(b"XObject").ok()?)?;
+        xobjects.get(name).and_then(Object::as_reference).ok()
+    })
+}
+
+fn read_matrix(operands: &[Object]) -> Option<[f32; 6]> {
+    let values: Vec<f32> = operands.iter().map(|value| value.as_float().ok()).collect::<Option<_>>()?;
+    values.try_into().ok()
+}
+
+// 矩阵乘法 m × n,两者都是 PDF 的 [a b c d e f] 形式
+fn multiply(m: &[f32; 6], n: &[f32; 6]) -> [f32; 6] {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+// 重新压缩一张图片,不需要处理或结果没有变小时返回 None。
+// 只处理 8 位灰度/RGB 的 JPEG 和无预测器的 Flate 图片,其他格式保持原样
+fn recompress_image(doc: &Document, stream: &Stream, display_size: Option<(f32, f32)>, settings: ImageSettings) -> Option<Stream> {
+    let dict = &stream.dict;
+    let is_mask = dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false);
+    if is_mask || dict.has(b"Decode") || dict.get(b"BitsPerComponent").and_then(Object::as_i64).ok()? != 8 {
+        return None;
+    }
+    let width = u32::try_from(dict.get(b"Width").and_then(Object::as_i64).ok()?).ok()?;
+    let height = u32::try_from(dict.get(b"Height").and_then(Object::as_i64).ok()?).ok()?;
+
+    let target = display_size.zip(settings.max_dpi).and_then(|((display_width, display_height), max_dpi)| {
+        let target_width = (display_width / 72.0 * max_dpi).ceil().max(1.0);
+        let target_height = (display_height / 72.0 * max_dpi).ceil().max(1.0);
+        let too_large = width as f32 > target_width * DOWNSAMPLE_THRESHOLD
+            || height as f32 > target_height * DOWNSAMPLE_THRESHOLD;
+        too_large.then_some((target_width as u32, target_height as u32))
+    });
+    if target.is_none() && settings.quality.is_none() {
+        return None;
+    }
+
+    let filters = stream.filters().ok()?;
+    let (img, components, is_jpeg) = match filters.as_slice() {
+        [filter] if filter == "DCTDecode" => {
+            let components = pdf_image::jpeg_components(&stream.content)?;
+            let img = ::image::load_from_memory_with_format(&stream.content, ImageFormat::Jpeg).ok()?;
+            (img, components, true)
+        }
+        [] | [_] if filters.iter().all(|filter| filter == "FlateDecode") => {
+            let predictor = dict
+                .get(b"DecodeParms")
+                .ok()
+                .and_then(|params| resolve_dict(doc, params))
+                .and_then(|params| params.get(b"Predictor").and_then(Object::as_i64).ok())
+                .unwrap_or(1);
+            if predictor > 1 {
+                return None;
+            }
+            let components = color_components(doc, dict)?;
+            let data = if filters.is_empty() {
+                stream.content.clone()
+            } else {
+                pdf_image::inflate(&stream.content).ok()?
+            };
+            let img = if components == 1 {
+                DynamicImage::ImageLuma8(GrayImage::from_raw(width, height, data)?)
+            } else {
+                DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, data)?)
+            };
+            (img, components, false)
+        }
+        _ => return None,
+    };
+
+    let img = match target {
+        Some((target_width, target_height)) => img.resize(target_width, target_height, FilterType::Lanczos3),
+        None => img,
+    };
+
+    let mut image_dict = dict.clone();
+    image_dict.set("Width", img.width() as i64);
+    image_dict.set("Height", img.height() as i64);
+    image_dict.remove(b"DecodeParms");
+
+    let quality = settings.quality.or(is_jpeg.then_some(pdf_image::DEFAULT_JPEG_QUALITY));
+    let image = match quality {
+        Some(quality) => {
+            let data = image_compress::encode_jpeg(&img.to_rgb8(), quality).ok()?;
+            image_dict.set("Filter", Object::Name(b"DCTDecode".to_vec()));
+            if components == 1 {
+                image_dict.set("ColorSpace", Object::Name(b"DeviceRGB".to_vec()));
+            }
+            Stream::new(image_dict, data)
+        }
+        // 未指定质量的 Flate 图片缩小后仍无损压缩
+        None => {
+            image_dict.remove(b"Filter");
+            let data = if components == 1 { img.to_luma8().into_raw() } else { img.to_rgb8().into_raw() };
+            let mut image = Stream::new(image_dict, data);
+            image.compress().ok()?;
+            image
+        }
+    };
+
+    (image.content.len() < stream.content.len()).then_some(image)
+}
+
+// 颜色空间的分量数,只接受灰度和 RGB
+fn color_components(doc: &Document, dict: &Dictionary) -> Option<u8> {
+    let color_space = resolve(doc, dict.get(b"ColorSpace").ok()?)?;
+    let components = match color_space {
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceGray" => 1,
+            b"DeviceRGB" => 3,
+            _ => return None,
+        },
+        Object::Array(items) if items.first().and_then(|name| name.as_name().ok()) == Some(b"ICCBased") => {
+            let profile = resolve(doc, items.get(1)?)?.as_stream().ok()?;
+            profile.dict.get(b"N").and_then(Object::as_i64).ok()? as u8
+        }
+        _ => return None,
+    };
+    matches!(components, 1 | 3).then_some(components)
+}
+
+// 按 PDF 1.5 格式写出: 非流对象打包进压缩的对象流,交叉引用表也写成流。
+// lopdf 只能写出传统的交叉引用表,这里自己序列化
+pub(crate) fn write_compact(doc: &Document) -> Result<Vec<u8>, String> {
+    let version = if doc.version.as_str() < "1.5" { "1.5" } else { doc.version.as_str() };
+    let mut output = format!("%PDF-{}\n", version).into_bytes();
+    output.extend_from_slice(b"%\xE2\xE3\xCF\xD3\n");
+
+    // 交叉引用条目: (类型, 偏移或对象流编号, 代数或流内序号)
+    let mut entries: BTreeMap<u32, (u8, u64, u16)> = BTreeMap::new();
+    let mut packed: Vec<(ObjectId, &Object)> = Vec::new();
+    for (&id, object) in &doc.objects {
+        // 对象流中只能放代数为 0 的非流对象
+        if matches!(object, Object::Stream(_)) || id.1 != 0 {
+            entries.insert(id.0, (1, output.len() as u64, id.1));
+            write_indirect(&mut output, id, object);
+        } else {
+            packed.push((id, object));
+        }
+    }
+
+    let mut next_id = doc.objects.keys().map(|id| id.0).max().unwrap_or(0) + 1;
+    for chunk in packed.chunks(OBJECTS_PER_STREAM) {
+        let stream_id = (next_id, 0);
+        next_id += 1;
+
+        let mut header = String::new();
+        let mut body = Vec::new();
+        for (index, (id, object)) in chunk.iter().enumerate() {
+            header.push_str(&format!("{} {} ", id.0, body.len()));
+            write_object(&mut body, object);
+            body.push(b'\n');
+            entries.insert(id.0, (2, stream_id.0 as u64, index as u16));
+        }
+        header.push('\n');
+
+        let first = header.len() as i64;
+        let mut content = header.into_bytes();
+        content.extend_from_slice(&body);
+        let dict = dictionary! {
+            "Type" => "ObjStm",
+            "N" => chunk.len() as i64,
+            "First" => first,
+        };
+        entries.insert(stream_id.0, (1, output.len() as u64, 0));
+        write_indirect(&mut output, stream_id, &Object::Stream(deflate_stream(dict, &content)?));
+    }
+
+    let xref_id = (next_id, 0);
+    let xref_offset = output.len();
+    entries.insert(xref_id.0, (1, xref_offset as u64, 0));
+    if xref_offset > u32::MAX as usize {
+        return Err("PDF is too large to optimize".to_string());
+    }
+
+    let size = xref_id.0 + 1;
+    let mut rows = Vec::with_capacity(size as usize * 7);
+    for number in 0..size {
+        let (kind, field, generation) = entries
+            .get(&number)
+            .copied()
+            .unwrap_or((0, 0, if number == 0 { 65535 } else { 0 }));
+        rows.push(kind);
+        rows.extend_from_slice(&(field as u32).to_be_bytes());
+        rows.extend_from_slice(&generation.to_be_bytes());
+    }
+
+    let mut dict = dictionary! {
+        "Type" => "XRef",
+        "Size" => size as i64,
+        "W" => vec![1.into(), 4.into(), 2.into()],
+    };
+    for key in [b"Root".as_slice(), b"Info", b"ID"] {
+        if let Ok(value) = doc.trailer.get(key) {
+            dict.set(key, value.clone());
+        }
+    }
+    write_indirect(&mut output, xref_id, &Object::Stream(deflate_stream(dict, &rows)?));
+    output.extend_from_slice(format!("startxref\n{}\n%%EOF\n", xref_offset).as_bytes());
+    Ok(output)
+}
+
+fn deflate_stream(mut dict: Dictionary, data: &[u8]) -> Result<Stream, String> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(data)
+        .map_err(|e| format!("Failed to compress PDF stream: {}", e))?;
+    let compressed = encoder
+        .finish()
+        .map_err(|e| format!("Failed to compress PDF stream: {}", e))?;
+    dict.set("Filter", Object::Name(b"FlateDecode".to_vec()));
+    Ok(Stream::new(dict, compressed))
+}
+
+fn write_indirect(output: &mut Vec<u8>, id: ObjectId, object: &Object) {
+    output.extend_from_slice(format!("{} {} obj\n", id.0, id.1).as_bytes());
+    write_object(output, object);
+    output.extend_from_slice(b"\nendobj\n");
+}
+
+fn write_object(output: &mut Vec<u8>, object: &Object) {
+    match object {
+        Object::Null => output.extend_from_slice(b"null"),
+        Object::Boolean(value) => output.extend_from_slice(if *value { b"true".as_slice() } else { b"false" }),
+        Object::Integer(value) => output.extend_from_slice(value.to_string().as_bytes()),
+        // f32 的 Display 不会输出指数形式
+        Object::Real(value) => {
+            let value = if value.is_finite() { *value } else { 0.0 };
+            output.extend_from_slice(value.to_string().as_bytes());
+        }
+        Object::Name(name) => write_name(output, name),
+        Object::String(bytes, StringFormat::Literal) => {
+            output.push(b'(');
+            for &byte in bytes {
+                match byte {
+                    b'\\' | b'(' | b')' => output.extend_from_slice(&[b'\\', byte]),
+                    // 换行符在字符串中会被规范化,需要转义
+                    b'\r' => output.extend_from_slice(b"\\r"),
+                    _ => output.push(byte),
+                }
+            }
+            output.push(b')');
+        }
+        Object::String(bytes, StringFormat::Hexadecimal) => {
+            output.push(b'<');
+            for byte in bytes {
+                output.extend_from_slice(format!("{:02X}", byte).as_bytes());
+            }
+            output.push(b'>');
+        }
+        Object::Array(items) => {
+            output.push(b'[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    output.push(b' ');
+                }
+                write_object(output, item);
+            }
+            output.push(b']');
+        }
+        Object::Dictionary(dict) => write_dictionary(output, dict),
+        Object::Stream(stream) => {
+            let mut dict = stream.dict.clone();
+            dict.set("Length", stream.content.len() as i64);
+            write_dictionary(output, &dict);
+            output.extend_from_slice(b"\nstream\n");
+            output.extend_from_slice(&stream.content);
+            output.extend_from_slice(b"\nendstream");
+        }
+        Object::Reference(id) => output.extend_from_slice(format!("{} {} R", id.0, id.1).as_bytes()),
+    }
+}
+
+fn write_dictionary(output: &mut Vec<u8>, dict: &Dictionary) {
+    output.extend_from_slice(b"<<");
+    for (key, value) in dict.iter() {
+        write_name(output, key);
+        output.push(b' ');
+        write_object(output, value);
+    }
+    output.extend_from_slice(b">>");
+}
+
+// 名称中的分隔符和非打印字符写成 #xx
+fn write_name(output: &mut Vec<u8>, name: &[u8]) {
+    output.push(b'/');
+    for &byte in name {
+        if (0x21..=0x7E).contains(&byte) && !b"()<>[]{}/%#".contains(&byte) {
+            output.push(byte);
+        } else {
+            output.extend_from_slice(format!("#{:02X}", byte).as_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Object 没有实现 PartialEq,按调试输出比较; 流的 Length 由写出时补上,不参与比较
+    fn normalized(object: &Object) -> String {
+        match object {
+            Object::Stream(stream) => {
+                let mut dict = stream.dict.clone();
+                dict.remove(b"Length");
+                format!("{:?} {:?}", dict, stream.content)
+            }
+            object => format!("{:?}", object),
+        }
+    }
+
+    fn image(color: u8) -> Stream {
+        Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 2,
+                "Height" => 1,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            vec![color, color],
+        )
+    }
+
+    #[test]
+    fn compact_output_loads_with_the_same_objects() {
+        let mut doc = Document::with_version("1.4");
+        let pages_id = doc.new_object_id();
+        let content_id = doc.add_object(Stream::new(dictionary! {}, b"BT /F1 12 Tf (Hi) Tj ET".to_vec()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.5.into(), (-1).into()],
+            "Contents" => content_id,
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+
+        // 各种需要转义的字符串和名称
+        doc.add_object(dictionary! {
+            "Literal" => Object::string_literal(b"a(b)c\\d\re\nf".to_vec()),
+            "Hex" => Object::String(vec![0, 0xFF, b'('], StringFormat::Hexadecimal),
+            "Name" => Object::Name(b"A B#C/D".to_vec()),
+            "Values" => vec![true.into(), false.into(), Object::Null, 0.125.into(), (-42).into()],
+            "Nested" => dictionary! { "Inner" => vec![Object::Array(vec![]), dictionary! {}.into()] },
+        });
+        // 代数不为 0 的对象不能放进对象流
+        doc.objects.insert((doc.max_id + 1, 3), Object::string_literal("old"));
+        doc.max_id += 1;
+        // 超过一个对象流的容量
+        for i in 0..OBJECTS_PER_STREAM as i64 + 5 {
+            doc.add_object(i);
+        }
+
+        let bytes = write_compact(&doc).unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.5\n"));
+        let loaded = Document::load_mem(&bytes).unwrap();
+
+        assert_eq!(loaded.trailer.get(b"Root").unwrap().as_reference().unwrap(), catalog_id);
+        assert_eq!(loaded.get_pages().len(), 1);
+        for (id, object) in &doc.objects {
+            let reloaded = loaded.get_object(*id).unwrap_or_else(|_| panic!("missing object {:?}", id));
+            assert_eq!(normalized(reloaded), normalized(object), "object {:?}", id);
+        }
+    }
+
+    #[test]
+    fn identical_streams_are_merged() {
+        let mut doc = Document::with_version("1.5");
+        let first = doc.add_object(image(0));
+        let second = doc.add_object(image(0));
+        let other = doc.add_object(image(255));
+        let resources = doc.add_object(dictionary! {
+            "XObject" => dictionary! { "Im1" => first, "Im2" => second, "Im3" => other },
+        });
+        // traverse_objects 只遍历从 trailer 可达的对象
+        let pages_id = doc.new_object_id();
+        let page_id = doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id, "Resources" => resources });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+
+        assert_eq!(remove_duplicate_streams(&mut doc), 1);
+
+        let images = doc.objects.values().filter(|object| matches!(object, Object::Stream(_))).count();
+        assert_eq!(images, 2);
+        let xobjects = doc.get_dictionary(resources).unwrap().get(b"XObject").unwrap().as_dict().unwrap();
+        let target = |name: &[u8]| xobjects.get(name).unwrap().as_reference().unwrap();
+        assert_eq!(target(b"Im1"), target(b"Im2"));
+        assert!(doc.get_object(target(b"Im1")).is_ok());
+        assert_eq!(target(b"Im3"), other);
+    }
+}
@@ -0,0 +1,121 @@
+use printpdf::lopdf::content::Content;
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::BTreeSet;
+
+use crate::pdf_utils::{resolve, resolve_dict};
+
+// 把另一个文档 (通常由 printpdf 生成) 的每一页转为表单 XObject 复制到目标文档,按页返回 XObject;
+// 页面内容和资源原样保留,只复制资源引用到的对象
+pub fn import_pages_as_forms(target: &mut Document, mut source: Document) -> Result<Vec<ObjectId>, String> {
+    source.renumber_objects_with(target.max_id + 1);
+    target.max_id = target.max_id.max(source.max_id);
+
+    let mut forms = Vec::new();
+    let mut referenced = BTreeSet::new();
+    for page_id in source.get_pages().into_values() {
+        let page = source.get_dictionary(page_id).map_err(|e| format!("Failed to read page: {}", e))?;
+        let bbox = page.get(b"MediaBox").map_err(|e| format!("Failed to read page size: {}", e))?.clone();
+        let mut resources = page
+            .get(b"Resources")
+            .ok()
+            .and_then(|resources| resolve_dict(&source, resources))
+            .cloned()
+            .unwrap_or_default();
+        // printpdf 用可选内容 (图层) 包裹页面内容,表单中不需要
+        resources.remove(b"Properties");
+        collect_references(&source, &Object::Dictionary(resources.clone()), &mut referenced);
+
+        let data = source.get_page_content(page_id).map_err(|e| format!("Failed to read page content: {}", e))?;
+        let mut content = Content::decode(&data).map_err(|e| format!("Failed to parse page content: {}", e))?;
+        content.operations.retain(|op| !matches!(op.operator.as_str(), "BDC" | "BMC" | "EMC"));
+        let data = content.encode().map_err(|e| format!("Failed to write page content: {}", e))?;
+
+        let dict = Dictionary::from_iter(vec![
+            ("Type", "XObject".into()),
+            ("Subtype", "Form".into()),
+            ("BBox", bbox),
+            ("Resources", resources.into()),
+        ]);
+        let mut stream = Stream::new(dict, data);
+        let _ = stream.compress();
+        forms.push(Object::Stream(stream));
+    }
+
+    for id in referenced {
+        if let Some(object) = source.objects.remove(&id) {
+            target.objects.insert(id, object);
+        }
+    }
+    Ok(forms.into_iter().map(|form| target.add_object(form)).collect())
+}
+
+// 递归收集对象引用到的所有间接对象
+fn collect_references(doc: &Document, object: &Object, ids: &mut BTreeSet<ObjectId>) {
+    match object {
+        Object::Reference(id) if ids.insert(*id) => {
+            if let Ok(target) = doc.get_object(*id) {
+                collect_references(doc, target, ids);
+            }
+        }
+        Object::Array(items) => items.iter().for_each(|item| collect_references(doc, item, ids)),
+        Object::Dictionary(dict) => dict.iter().for_each(|(_, value)| collect_references(doc, value, ids)),
+        Object::Stream(stream) => stream.dict.iter().for_each(|(_, value)| collect_references(doc, value, ids)),
+        _ => {}
+    }
+}
+
+// 在页面原有内容之上绘制表单 XObject,matrix 为放置时的变换矩阵;
+// 原内容用 q/Q 包裹,避免其图形状态影响叠加的内容; 原内容末尾可能没有空白,Q 前加换行
+pub fn place_forms(doc: &mut Document, page_id: ObjectId, forms: &[(ObjectId, [f32; 6])]) -> Result<(), String> {
+    if forms.is_empty() {
+        return Ok(());
+    }
+
+    let page = doc.get_dictionary(page_id).map_err(|e| format!("Failed to read page: {}", e))?;
+    let mut resources = page
+        .get(b"Resources")
+        .ok()
+        .and_then(|resources| resolve_dict(doc, resources))
+        .cloned()
+        .unwrap_or_default();
+    let mut xobjects = resources
+        .get(b"XObject")
+        .ok()
+        .and_then(|xobjects| resolve_dict(doc, xobjects))
+        .cloned()
+        .unwrap_or_default();
+    // Contents 可能是引用到数组的间接对象,展开为流的列表
+    let mut contents: Vec<Object> = match page.get(b"Contents") {
+        Ok(contents) => match resolve(doc, contents) {
+            Some(Object::Array(items)) => items.clone(),
+            _ => vec![contents.clone()],
+        },
+        Err(_) => Vec::new(),
+    };
+
+    let mut overlay = String::new();
+    let mut index = 0;
+    for (form_id, [a, b, c, d, e, f]) in forms {
+        let name = loop {
+            index += 1;
+            let name = format!("Ov{}", index);
+            if !xobjects.has(name.as_bytes()) {
+                break name;
+            }
+        };
+        xobjects.set(name.as_str(), *form_id);
+        overlay.push_str(&format!("q {} {} {} {} {} {} cm /{} Do Q\n", a, b, c, d, e, f, name));
+    }
+    resources.set("XObject", xobjects);
+
+    let save = doc.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec()));
+    let mut overlay = Stream::new(Dictionary::new(), format!("\nQ\n{}", overlay).into_bytes());
+    let _ = overlay.compress();
+    contents.insert(0, save.into());
+    contents.push(doc.add_object(overlay).into());
+
+    let page = doc.get_dictionary_mut(page_id).map_err(|e| format!("Failed to read page: {}", e))?;
+    page.set("Resources", resources);
+    page.set("Contents", contents);
+    Ok(())
+}
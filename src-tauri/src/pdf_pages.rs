@@ -0,0 +1,641 @@
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::pdf_utils::{self, resolve, resolve_dict};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SplitMode {
+    // 按页码范围拆分,每段一个文件,如 "1-3,7,10-"
+    Ranges { ranges: String },
+    // 每 N 页一个文件
+    EveryN { pages: u32 },
+    // 按顶层书签拆分
+    Bookmarks,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageRotation {
+    // 页码范围,语法同拆分
+    pub pages: String,
+    // 顺时针角度,必须是 90 的倍数
+    pub degrees: i32,
+}
+
+// 解析页码范围 (从 1 开始): "1-3,7,10-" / "-5" / "5-3" (倒序)
+pub fn parse_page_ranges(spec: &str, page_count: u32) -> Result<Vec<Vec<u32>>, String> {
+    let mut ranges = Vec::new();
+
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let parse = |s: &str| -> Result<u32, String> {
+            s.trim().parse::<u32>().map_err(|_| format!("Invalid page number: {}", s))
+        };
+
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => {
+                let start = if start.trim().is_empty() { 1 } else { parse(start)? };
+                let end = if end.trim().is_empty() { page_count } else { parse(end)? };
+                (start, end)
+            }
+            None => {
+                let page = parse(part)?;
+                (page, page)
+            }
+        };
+
+        for page in [start, end] {
+            if page == 0 || page > page_count {
+                return Err(format!("Page {} is out of range (1-{})", page, page_count));
+            }
+        }
+
+        let range: Vec<u32> = if start <= end {
+            (start..=end).collect()
+        } else {
+            (end..=start).rev().collect()
+        };
+        ranges.push(range);
+    }
+
+    if ranges.is_empty() {
+        return Err("No pages specified".to_string());
+    }
+    Ok(ranges)
+}
+
+pub fn split_pdf(path: &str, mode: &SplitMode, output_dir: &str) -> Result<Vec<String>, String> {
+    let doc = pdf_utils::load_pdf(path)?;
+    let page_count = doc.get_pages().len() as u32;
+    let stem = pdf_utils::file_stem(path);
+
+    println!("拆分 PDF: {}, 共 {} 页, 模式: {:?}", path, page_count, mode);
+
+    // (文件名后缀, 页码列表)
+    let parts: Vec<(String, Vec<u32>)> = match mode {
+        SplitMode::Ranges { ranges } => parse_page_ranges(ranges, page_count)?
+            .into_iter()
+            .map(|pages| (range_suffix(&pages), pages))
+            .collect(),
+        SplitMode::EveryN { pages } => {
+            if *pages == 0 {
+                return Err("Pages per file must be greater than 0".to_string());
+            }
+            (1..=page_count)
+                .collect::<Vec<u32>>()
+                .chunks(*pages as usize)
+                .map(|chunk| (range_suffix(chunk), chunk.to_vec()))
+                .collect()
+        }
+        SplitMode::Bookmarks => split_by_bookmarks(&doc, page_count)?,
+    };
+
+    let mut outputs = Vec::new();
+    for (suffix, pages) in parts {
+        let output_path = Path::new(output_dir)
+            .join(format!("{}_{}.pdf", stem, suffix))
+            .to_string_lossy()
+            .to_string();
+
+        let mut part = document_with_pages(&doc, &pages)?;
+        pdf_utils::save_pdf(&mut part, &output_path)?;
+        println!("  已生成: {} ({} 页)", output_path, pages.len());
+        outputs.push(output_path);
+    }
+
+    Ok(outputs)
+}
+
+// 提取指定页面 (按给定顺序) 到新文件
+pub fn extract_pages(path: &str, pages: &str, output_path: &str) -> Result<(), String> {
+    let doc = pdf_utils::load_pdf(path)?;
+    let page_count = doc.get_pages().len() as u32;
+    let pages: Vec<u32> = parse_page_ranges(pages, page_count)?.concat();
+
+    let mut extracted = document_with_pages(&doc, &pages)?;
+    pdf_utils::save_pdf(&mut extracted, output_path)
+}
+
+// 按新顺序重排页面,未列出的页面会被删除
+pub fn reorder_pages(path: &str, order: &[u32], output_path: &str) -> Result<(), String> {
+    let doc = pdf_utils::load_pdf(path)?;
+    let page_count = doc.get_pages().len() as u32;
+
+    if let Some(&page) = order.iter().find(|&&p| p == 0 || p > page_count) {
+        return Err(format!("Page {} is out of range (1-{})", page, page_count));
+    }
+
+    let mut reordered = document_with_pages(&doc, order)?;
+    pdf_utils::save_pdf(&mut reordered, output_path)
+}
+
+pub fn delete_pages(path: &str, pages: &str, output_path: &str) -> Result<(), String> {
+    let doc = pdf_utils::load_pdf(path)?;
+    let page_count = doc.get_pages().len() as u32;
+    let deleted: HashSet<u32> = parse_page_ranges(pages, page_count)?.concat().into_iter().collect();
+
+    let remaining: Vec<u32> = (1..=page_count).filter(|p| !deleted.contains(p)).collect();
+    let mut result = document_with_pages(&doc, &remaining)?;
+    pdf_utils::save_pdf(&mut result, output_path)
+}
+
+pub fn rotate_pages(path: &str, rotations: &[PageRotation], output_path: &str) -> Result<(), String> {
+    let mut doc = pdf_utils::load_pdf(path)?;
+    pdf_utils::flatten_inherited_page_attributes(&mut doc);
+
+    let pages = doc.get_pages();
+    let page_count = pages.len() as u32;
+
+    for rotation in rotations {
+        if rotation.degrees % 90 != 0 {
+            return Err(format!("Rotation must be a multiple of 90 degrees, got {}", rotation.degrees));
+        }
+
+        for page_number in parse_page_ranges(&rotation.pages, page_count)?.concat() {
+            let page = doc
+                .get_dictionary_mut(pages[&page_number])
+                .map_err(|e| format!("Failed to read page {}: {}", page_number, e))?;
+            let current = page.get(b"Rotate").and_then(Object::as_i64).unwrap_or(0);
+            page.set("Rotate", (current + rotation.degrees as i64).rem_euclid(360));
+        }
+    }
+
+    pdf_utils::save_pdf(&mut doc, output_path)
+}
+
+fn range_suffix(pages: &[u32]) -> String {
+    match (pages.first(), pages.last()) {
+        (Some(first), Some(last)) if first != last => format!("p{}-{}", first, last),
+        (Some(first), _) => format!("p{}", first),
+        _ => "empty".to_string(),
+    }
+}
+
+// 每个顶层书签开始一个新文件,第一个书签之前的页面并入第一部分
+fn split_by_bookmarks(doc: &Document, page_count: u32) -> Result<Vec<(String, Vec<u32>)>, String> {
+    let page_numbers: HashMap<ObjectId, u32> = doc.get_pages().into_iter().map(|(n, id)| (id, n)).collect();
+
+    let mut starts: Vec<(u32, String)> = pdf_utils::outline_entries(doc)
+        .into_iter()
+        .filter(|entry| entry.level == 0)
+        .filter_map(|entry| Some((*page_numbers.get(&entry.page?)?, entry.title)))
+        .collect();
+    starts.sort_by_key(|(page, _)| *page);
+    starts.dedup_by_key(|(page, _)| *page);
+
+    if starts.is_empty() {
+        return Err("PDF has no bookmarks to split by".to_string());
+    }
+    starts[0].0 = 1;
+
+    Ok(starts
+        .iter()
+        .enumerate()
+        .map(|(i, (start, title))| {
+            let end = starts.get(i + 1).map(|(next, _)| next - 1).unwrap_or(page_count);
+            let suffix = format!("{:02}_{}", i + 1, sanitize_file_name(title));
+            (suffix, (*start..=end).collect())
+        })
+        .collect())
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_control() || "\\/:*?\"<>|".contains(c) { '_' } else { c })
+        .take(60)
+        .collect();
+    let sanitized = sanitized.trim().to_string();
+    if sanitized.is_empty() {
+        "section".to_string()
+    } else {
+        sanitized
+    }
+}
+
+// 基于原文档生成只包含指定页面的新文档,直接复用原对象,不重新渲染
+fn document_with_pages(source: &Document, page_numbers: &[u32]) -> Result<Document, String> {
+    if page_numbers.is_empty() {
+        return Err("Resulting document would have no pages".to_string());
+    }
+
+    let mut doc = source.clone();
+    pdf_utils::flatten_inherited_page_attributes(&mut doc);
+
+    let pages = doc.get_pages();
+    let named = pdf_utils::named_destinations(&doc);
+    let outline = pdf_utils::outline_entries(&doc);
+    let pages_id = doc
+        .catalog()
+        .and_then(|catalog| catalog.get(b"Pages"))
+        .and_then(Object::as_reference)
+        .map_err(|e| format!("Failed to read page tree: {}", e))?;
+
+    let mut kids = Vec::new();
+    let mut kept: HashSet<ObjectId> = HashSet::new();
+    for &number in page_numbers {
+        let page_id = *pages
+            .get(&number)
+            .ok_or_else(|| format!("Page {} is out of range (1-{})", number, pages.len()))?;
+
+        // 同一页面出现多次时复制页面字典,内容流和资源仍共享
+        let id = if kept.insert(page_id) {
+            page_id
+        } else {
+            let copy = doc.get_object(page_id).map_err(|e| format!("Failed to copy page: {}", e))?.clone();
+            doc.add_object(copy)
+        };
+        if let Ok(page) = doc.get_dictionary_mut(id) {
+            page.set("Parent", pages_id);
+        }
+        kids.push(id);
+    }
+
+    let pages_node = doc
+        .get_dictionary_mut(pages_id)
+        .map_err(|e| format!("Failed to read page tree: {}", e))?;
+    pages_node.set("Count", kids.len() as i64);
+    pages_node.set("Kids", kids.iter().map(|&id| Object::Reference(id)).collect::<Vec<_>>());
+
+    remove_dangling_links(&mut doc, &kids, &kept, &named);
+    filter_acro_form(&mut doc, &kids);
+
+    let outline: Vec<(String, usize, ObjectId)> = outline
+        .into_iter()
+        .filter_map(|entry| {
+            let page = entry.page.filter(|page| kept.contains(page))?;
+            Some((entry.title, entry.level, page))
+        })
+        .collect();
+    let outlines_id = pdf_utils::build_outline_tree(&mut doc, &outline);
+
+    let dests = filter_dests_dict(&doc, &kept, &named);
+    let named_dests: Vec<Object> = pdf_utils::dests_name_tree(&doc)
+        .iter()
+        .filter(|(_, dest)| {
+            pdf_utils::destination_page(&doc, dest, &named)
+                .map(|page| kept.contains(&page))
+                .unwrap_or(false)
+        })
+        .flat_map(|(name, dest)| [Object::string_literal(name.clone()), dest.clone()])
+        .collect();
+
+    let names = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Names").ok())
+        .and_then(|names| resolve_dict(&doc, names))
+        .cloned();
+
+    let catalog = doc.catalog_mut().map_err(|e| format!("Failed to read catalog: {}", e))?;
+    catalog.remove(b"Outlines");
+    if let Some(outlines_id) = outlines_id {
+        catalog.set("Outlines", outlines_id);
+    }
+
+    catalog.remove(b"Dests");
+    if let Some(dests) = dests {
+        catalog.set("Dests", dests);
+    }
+
+    // 名称字典中只重建 Dests,其他条目 (如嵌入文件) 原样保留
+    if let Some(mut names) = names {
+        names.remove(b"Dests");
+        if !named_dests.is_empty() {
+            names.set("Dests", Dictionary::from_iter(vec![("Names", Object::Array(named_dests))]));
+        }
+        catalog.set("Names", names);
+    }
+
+    // 删除页面后,结构树会引用已删除的页面,直接移除
+    catalog.remove(b"StructTreeRoot");
+    catalog.remove(b"MarkInfo");
+
+    doc.prune_objects();
+    Ok(doc)
+}
+
+// 移除指向已删除页面的链接注释
+fn remove_dangling_links(
+    doc: &mut Document,
+    page_ids: &[ObjectId],
+    kept: &HashSet<ObjectId>,
+    named: &BTreeMap<Vec<u8>, Object>,
+) {
+    for &page_id in page_ids {
+        let Some(annots) = doc
+            .get_dictionary(page_id)
+            .ok()
+            .and_then(|page| page.get(b"Annots").ok())
+            .and_then(|annots| resolve(doc, annots))
+            .and_then(|annots| annots.as_array().ok())
+        else {
+            continue;
+        };
+
+        let filtered: Vec<Object> = annots
+            .iter()
+            .filter(|annot| {
+                let Some(annot) = resolve_dict(doc, annot) else {
+                    return true;
+                };
+                match pdf_utils::link_target_page(doc, annot, named) {
+                    Some(target) => kept.contains(&target),
+                    None => true,
+                }
+            })
+            .cloned()
+            .collect();
+
+        if filtered.len() != annots.len() {
+            if let Ok(page) = doc.get_dictionary_mut(page_id) {
+                page.set("Annots", filtered);
+            }
+        }
+    }
+}
+
+// 表单只保留控件仍在页面上的域;否则 Fields 和控件的 P 会让已删除的页面继续留在文件中
+fn filter_acro_form(doc: &mut Document, page_ids: &[ObjectId]) {
+    let Some(acro_form) = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").ok())
+        .and_then(|acro_form| resolve_dict(doc, acro_form))
+        .cloned()
+    else {
+        return;
+    };
+    let acro_form_id = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").and_then(Object::as_reference).ok());
+
+    let widgets: HashSet<ObjectId> = page_ids
+        .iter()
+        .filter_map(|&id| doc.get_dictionary(id).ok())
+        .filter_map(|page| page.get(b"Annots").ok())
+        .filter_map(|annots| resolve(doc, annots))
+        .filter_map(|annots| annots.as_array().ok())
+        .flatten()
+        .filter_map(|annot| annot.as_reference().ok())
+        .collect();
+
+    let mut kept_fields = HashSet::new();
+    let mut kids_updates = Vec::new();
+    let fields: Vec<Object> = acro_form
+        .get(b"Fields")
+        .ok()
+        .and_then(|fields| resolve(doc, fields))
+        .and_then(|fields| fields.as_array().ok())
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|field| field.as_reference().ok())
+                .filter(|&id| keep_field(doc, id, &widgets, &mut kept_fields, &mut kids_updates, 0))
+                .map(Object::Reference)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for (id, kids) in kids_updates {
+        if let Ok(field) = doc.get_dictionary_mut(id) {
+            field.set("Kids", kids);
+        }
+    }
+
+    if fields.is_empty() {
+        if let Ok(catalog) = doc.catalog_mut() {
+            catalog.remove(b"AcroForm");
+        }
+        return;
+    }
+
+    let mut acro_form = acro_form;
+    acro_form.set("Fields", fields);
+    // 计算顺序也会引用域
+    let order: Option<Vec<Object>> = acro_form
+        .get(b"CO")
+        .ok()
+        .and_then(|order| resolve(doc, order))
+        .and_then(|order| order.as_array().ok())
+        .map(|order| {
+            order
+                .iter()
+                .filter(|field| field.as_reference().is_ok_and(|id| kept_fields.contains(&id)))
+                .cloned()
+                .collect()
+        });
+    match order {
+        Some(order) if !order.is_empty() => acro_form.set("CO", order),
+        Some(_) => {
+            acro_form.remove(b"CO");
+        }
+        None => {}
+    }
+
+    match acro_form_id {
+        Some(id) => {
+            doc.objects.insert(id, Object::Dictionary(acro_form));
+        }
+        None => {
+            if let Ok(catalog) = doc.catalog_mut() {
+                catalog.set("AcroForm", acro_form);
+            }
+        }
+    }
+}
+
+// 终端域要求自身是保留页面上的控件,非终端域至少保留一个子域;需要改写的 Kids 记入 kids_updates
+fn keep_field(
+    doc: &Document,
+    id: ObjectId,
+    widgets: &HashSet<ObjectId>,
+    kept_fields: &mut HashSet<ObjectId>,
+    kids_updates: &mut Vec<(ObjectId, Vec<Object>)>,
+    depth: usize,
+) -> bool {
+    if depth > 32 {
+        return false;
+    }
+    let Ok(field) = doc.get_dictionary(id) else {
+        return false;
+    };
+
+    let keep = match field.get(b"Kids").ok().and_then(|kids| resolve(doc, kids)).and_then(|kids| kids.as_array().ok()) {
+        Some(kids) => {
+            let remaining: Vec<Object> = kids
+                .iter()
+                .filter_map(|kid| kid.as_reference().ok())
+                .filter(|&kid| keep_field(doc, kid, widgets, kept_fields, kids_updates, depth + 1))
+                .map(Object::Reference)
+                .collect();
+            if !remaining.is_empty() && remaining.len() != kids.len() {
+                kids_updates.push((id, remaining.clone()));
+            }
+            !remaining.is_empty()
+        }
+        None => widgets.contains(&id),
+    };
+
+    if keep {
+        kept_fields.insert(id);
+    }
+    keep
+}
+
+fn filter_dests_dict(doc: &Document, kept: &HashSet<ObjectId>, named: &BTreeMap<Vec<u8>, Object>) -> Option<Dictionary> {
+    let dests = doc
+        .catalog()
+        .ok()?
+        .get(b"Dests")
+        .ok()
+        .and_then(|dests| resolve_dict(doc, dests))?;
+
+    let filtered = Dictionary::from_iter(dests.iter().filter_map(|(name, dest)| {
+        let page = pdf_utils::destination_page(doc, dest, named)?;
+        kept.contains(&page).then(|| (name.clone(), dest.clone()))
+    }));
+
+    (!filtered.is_empty()).then_some(filtered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use printpdf::lopdf::{dictionary, Stream};
+
+    #[test]
+    fn page_ranges_accept_open_ended_and_reversed_ranges() {
+        assert_eq!(parse_page_ranges("1-3,7,10-", 12).unwrap(), vec![vec![1, 2, 3], vec![7], vec![10, 11, 12]]);
+        assert_eq!(parse_page_ranges("-3", 12).unwrap(), vec![vec![1, 2, 3]]);
+        assert_eq!(parse_page_ranges("5-3", 12).unwrap(), vec![vec![5, 4, 3]]);
+        assert_eq!(parse_page_ranges("4-4", 12).unwrap(), vec![vec![4]]);
+        assert_eq!(parse_page_ranges("-", 3).unwrap(), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn page_ranges_ignore_whitespace_and_empty_items() {
+        assert_eq!(parse_page_ranges(" 2 - 4 , 6 ", 10).unwrap(), vec![vec![2, 3, 4], vec![6]]);
+        assert_eq!(parse_page_ranges("1,,3,", 10).unwrap(), vec![vec![1], vec![3]]);
+        assert_eq!(parse_page_ranges("", 10).err().as_deref(), Some("No pages specified"));
+        assert_eq!(parse_page_ranges(" , ", 10).err().as_deref(), Some("No pages specified"));
+    }
+
+    #[test]
+    fn page_ranges_reject_out_of_range_and_invalid_pages() {
+        assert_eq!(parse_page_ranges("0", 10).err().as_deref(), Some("Page 0 is out of range (1-10)"));
+        assert_eq!(parse_page_ranges("11", 10).err().as_deref(), Some("Page 11 is out of range (1-10)"));
+        assert_eq!(parse_page_ranges("3-11", 10).err().as_deref(), Some("Page 11 is out of range (1-10)"));
+        assert_eq!(parse_page_ranges("12-", 10).err().as_deref(), Some("Page 12 is out of range (1-10)"));
+        assert_eq!(parse_page_ranges("1-a", 10).err().as_deref(), Some("Invalid page number: a"));
+        assert_eq!(parse_page_ranges("1.5", 10).err().as_deref(), Some("Invalid page number: 1.5"));
+    }
+
+    fn widget(doc: &mut Document, page: ObjectId, field: Dictionary) -> ObjectId {
+        let mut widget = dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "Rect" => vec![0.into(), 0.into(), 100.into(), 20.into()],
+            "P" => page,
+        };
+        for (key, value) in field.iter() {
+            widget.set(key.clone(), value.clone());
+        }
+        doc.add_object(widget)
+    }
+
+    // 四页表单: A 在第 1 页;B 的两个控件在第 2、3 页;C 在第 2 页;第 4 页没有控件
+    fn form_document() -> (Document, Vec<ObjectId>, [ObjectId; 5]) {
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let page_ids: Vec<ObjectId> = (0..4).map(|_| doc.new_object_id()).collect();
+        let contents: Vec<ObjectId> = (1..=4)
+            .map(|number| {
+                let content = format!("BT /F1 12 Tf 72 720 Td (Page {}) Tj ET", number);
+                doc.add_object(Stream::new(dictionary! {}, content.into_bytes()))
+            })
+            .collect();
+
+        let field_a = widget(&mut doc, page_ids[0], dictionary! { "FT" => "Tx", "T" => Object::string_literal("A") });
+        let field_b = doc.new_object_id();
+        let kid_b2 = widget(&mut doc, page_ids[1], dictionary! { "Parent" => field_b });
+        let kid_b3 = widget(&mut doc, page_ids[2], dictionary! { "Parent" => field_b });
+        doc.objects.insert(
+            field_b,
+            Object::Dictionary(dictionary! {
+                "FT" => "Tx",
+                "T" => Object::string_literal("B"),
+                "Kids" => vec![kid_b2.into(), kid_b3.into()],
+            }),
+        );
+        let field_c = widget(&mut doc, page_ids[1], dictionary! { "FT" => "Tx", "T" => Object::string_literal("C") });
+
+        let annots: [Vec<Object>; 4] =
+            [vec![field_a.into()], vec![kid_b2.into(), field_c.into()], vec![kid_b3.into()], vec![]];
+        for ((&page_id, &content_id), annots) in page_ids.iter().zip(&contents).zip(annots) {
+            doc.objects.insert(
+                page_id,
+                Object::Dictionary(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+                    "Contents" => content_id,
+                    "Annots" => annots,
+                }),
+            );
+        }
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => page_ids.iter().map(|&id| Object::Reference(id)).collect::<Vec<_>>(),
+                "Count" => 4,
+            }),
+        );
+        let acro_form = doc.add_object(dictionary! {
+            "Fields" => vec![field_a.into(), field_b.into(), field_c.into()],
+            "CO" => vec![field_c.into(), field_a.into()],
+        });
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id, "AcroForm" => acro_form });
+        doc.trailer.set("Root", catalog_id);
+
+        (doc, contents, [field_a, field_b, kid_b2, kid_b3, field_c])
+    }
+
+    fn form_dict(doc: &Document) -> Option<&Dictionary> {
+        resolve_dict(doc, doc.catalog().unwrap().get(b"AcroForm").ok()?)
+    }
+
+    fn references(doc: &Document, dict: &Dictionary, key: &[u8]) -> Vec<ObjectId> {
+        let array = resolve(doc, dict.get(key).unwrap()).unwrap().as_array().unwrap();
+        array.iter().map(|item| item.as_reference().unwrap()).collect()
+    }
+
+    #[test]
+    fn extracted_pages_drop_form_fields_of_removed_pages() {
+        let (source, contents, [field_a, field_b, kid_b2, kid_b3, field_c]) = form_document();
+
+        let mut doc = document_with_pages(&source, &[1, 3]).unwrap();
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        let doc = Document::load_mem(&bytes).unwrap();
+
+        assert_eq!(doc.get_pages().len(), 2);
+        assert!(!doc.objects.contains_key(&contents[1]));
+        assert!(!doc.objects.contains_key(&contents[3]));
+        assert!(!doc.objects.contains_key(&kid_b2));
+        assert!(!doc.objects.contains_key(&field_c));
+
+        let acro_form = form_dict(&doc).unwrap();
+        assert_eq!(references(&doc, acro_form, b"Fields"), vec![field_a, field_b]);
+        assert_eq!(references(&doc, acro_form, b"CO"), vec![field_a]);
+        let field_b = doc.get_dictionary(field_b).unwrap();
+        assert_eq!(references(&doc, field_b, b"Kids"), vec![kid_b3]);
+
+        let doc = document_with_pages(&source, &[4]).unwrap();
+        assert!(form_dict(&doc).is_none());
+        assert_eq!(doc.objects.values().filter(|object| object.as_stream().is_ok()).count(), 1);
+    }
+}
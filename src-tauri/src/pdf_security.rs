@@ -0,0 +1,847 @@
+use aes::cipher::consts::U16;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, BlockSizeUser, KeyInit};
+use aes::{Aes128, Aes256, Block};
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId, ObjectStream, StringFormat};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::pdf_utils;
+
+// 加载加密文档时临时改写对象流的类型: lopdf 读取时会解析对象流,密文解析失败会把整个对象流丢掉
+const ENCRYPTED_OBJECT_STREAM: &str = "EncryptedObjStm";
+
+// 权限标志 (ISO 32000 表 22),保留位必须为 1
+const PERMISSION_RESERVED: u32 = 0xFFFF_F0C0;
+// 打印、高质量打印
+const PERMISSION_PRINT: u32 = (1 << 2) | (1 << 11);
+// 修改内容、注释、填写表单、组合页面
+const PERMISSION_MODIFY: u32 = (1 << 3) | (1 << 5) | (1 << 8) | (1 << 10);
+// 复制文字和图片
+const PERMISSION_COPY: u32 = 1 << 4;
+// 辅助功能 (读屏) 提取文字,始终允许
+const PERMISSION_ACCESSIBILITY: u32 = 1 << 9;
+
+// 密码按 UTF-8 使用,最长 127 字节
+const MAX_PASSWORD_BYTES: usize = 127;
+
+// RC4 / AES-128 加密中不足 32 字节的密码用此串补齐 (ISO 32000 算法 2)
+const PASSWORD_PADDING: [u8; 32] = [
+    0x28, 0xBF, 0x4E, 0x5E, 0x4E, 0x75, 0x8A, 0x41, 0x64, 0x00, 0x4E, 0x56, 0xFF, 0xFA, 0x01, 0x08, 0x2E, 0x2E, 0x00,
+    0xB6, 0xD0, 0x68, 0x3E, 0x80, 0x2F, 0x0C, 0xA9, 0xFE, 0x64, 0x53, 0x69, 0x7A,
+];
+
+// V1 - V4 中字符串或流使用的算法
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CryptMethod {
+    Identity,
+    Rc4,
+    Aes128,
+}
+
+// 输出 PDF 的 AES-256 加密选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfEncryption {
+    // 打开文档的密码,为空时无需密码即可打开,但仍受权限限制
+    pub user_password: String,
+    // 所有者密码,不受权限限制; 为空时使用随机密码
+    pub owner_password: String,
+    pub allow_print: bool,
+    pub allow_copy: bool,
+    pub allow_modify: bool,
+}
+
+impl Default for PdfEncryption {
+    fn default() -> Self {
+        PdfEncryption {
+            user_password: String::new(),
+            owner_password: String::new(),
+            allow_print: true,
+            allow_copy: true,
+            allow_modify: true,
+        }
+    }
+}
+
+impl PdfEncryption {
+    fn permissions(&self) -> u32 {
+        let mut permissions = PERMISSION_RESERVED | PERMISSION_ACCESSIBILITY;
+        if self.allow_print {
+            permissions |= PERMISSION_PRINT;
+        }
+        if self.allow_modify {
+            permissions |= PERMISSION_MODIFY;
+        }
+        if self.allow_copy {
+            permissions |= PERMISSION_COPY;
+        }
+        permissions
+    }
+}
+
+// 用 AES-256 (V5 / R6) 加密文档中所有字符串和流,应在保存前最后调用
+pub fn encrypt_document(doc: &mut Document, options: &PdfEncryption) -> Result<(), String> {
+    let user_password = password_bytes(&options.user_password);
+    // 所有者密码为空时任何人都能以所有者身份打开,权限形同虚设
+    let owner_password = if options.owner_password.is_empty() {
+        hex(&random_bytes(16)?).into_bytes()
+    } else {
+        password_bytes(&options.owner_password).to_vec()
+    };
+
+    let file_key = random_bytes(32)?;
+
+    // U = 哈希 + 验证盐 + 密钥盐, UE = 用用户密码派生的密钥加密的文件密钥
+    let user_salts = random_bytes(16)?;
+    let mut u = hash_r6(user_password, &user_salts[..8], &[]).to_vec();
+    u.extend_from_slice(&user_salts);
+    let ue = cbc_encrypt(&aes256(&hash_r6(user_password, &user_salts[8..], &[])), &[0; 16], &file_key);
+
+    // O / OE 同理,哈希时附加 U
+    let owner_salts = random_bytes(16)?;
+    let mut o = hash_r6(&owner_password, &owner_salts[..8], &u).to_vec();
+    o.extend_from_slice(&owner_salts);
+    let oe = cbc_encrypt(&aes256(&hash_r6(&owner_password, &owner_salts[8..], &u)), &[0; 16], &file_key);
+
+    // Perms: 权限的加密副本,防止 P 被篡改
+    let permissions = options.permissions();
+    let mut perms = [0xFFu8; 16];
+    perms[..4].copy_from_slice(&permissions.to_le_bytes());
+    perms[8] = b'T';
+    perms[9..12].copy_from_slice(b"adb");
+    perms[12..].copy_from_slice(&random_bytes(4)?);
+    let perms = cbc_encrypt(&aes256(&file_key), &[0; 16], &perms);
+
+    let cipher = aes256(&file_key);
+    for object in doc.objects.values_mut() {
+        if is_xref_stream(object) {
+            continue;
+        }
+        transform_object(object, &mut |data, _| encrypt_data(&cipher, data))?;
+    }
+
+    let crypt_filter = Dictionary::from_iter(vec![
+        ("Type", Object::Name(b"CryptFilter".to_vec())),
+        ("CFM", Object::Name(b"AESV3".to_vec())),
+        ("AuthEvent", Object::Name(b"DocOpen".to_vec())),
+        ("Length", Object::Integer(32)),
+    ]);
+    let encrypt = Dictionary::from_iter(vec![
+        ("Filter", Object::Name(b"Standard".to_vec())),
+        ("V", Object::Integer(5)),
+        ("R", Object::Integer(6)),
+        ("Length", Object::Integer(256)),
+        ("CF", Object::Dictionary(Dictionary::from_iter(vec![("StdCF", Object::Dictionary(crypt_filter))]))),
+        ("StmF", Object::Name(b"StdCF".to_vec())),
+        ("StrF", Object::Name(b"StdCF".to_vec())),
+        ("O", Object::String(o, StringFormat::Hexadecimal)),
+        ("U", Object::String(u, StringFormat::Hexadecimal)),
+        ("OE", Object::String(oe, StringFormat::Hexadecimal)),
+        ("UE", Object::String(ue, StringFormat::Hexadecimal)),
+        ("P", Object::Integer(permissions as i32 as i64)),
+        ("Perms", Object::String(perms, StringFormat::Hexadecimal)),
+        ("EncryptMetadata", Object::Boolean(true)),
+    ]);
+    let encrypt_id = doc.add_object(encrypt);
+    doc.trailer.set("Encrypt", encrypt_id);
+
+    // 加密文档必须有文件标识
+    if doc.trailer.get(b"ID").is_err() {
+        let id = Object::String(random_bytes(16)?, StringFormat::Hexadecimal);
+        doc.trailer.set("ID", vec![id.clone(), id]);
+    }
+
+    // AES-256 属于 PDF 1.7 扩展级别 8 (PDF 2.0 中的标准加密)
+    if doc.version.as_str() < "1.7" {
+        doc.version = "1.7".to_string();
+    }
+    let extensions = Dictionary::from_iter(vec![(
+        "ADBE",
+        Object::Dictionary(Dictionary::from_iter(vec![
+            ("BaseVersion", Object::Name(b"1.7".to_vec())),
+            ("ExtensionLevel", Object::Integer(8)),
+        ])),
+    )]);
+    let catalog = doc.catalog_mut().map_err(|e| format!("Failed to read catalog: {}", e))?;
+    catalog.set("Extensions", extensions);
+
+    Ok(())
+}
+
+// 用已知密码 (用户或所有者密码) 解密 PDF 并另存为不加密的文件
+pub fn decrypt_pdf(path: &str, password: &str, output_path: &str) -> Result<(), String> {
+    let mut doc = load_encrypted_pdf(path, password)?;
+    doc.compress();
+    pdf_utils::save_pdf(&mut doc, output_path)?;
+    println!("解密完成: {}", output_path);
+    Ok(())
+}
+
+// 加载加密的 PDF 并在内存中解密
+pub fn load_encrypted_pdf(path: &str, password: &str) -> Result<Document, String> {
+    let mut doc = Document::load_filtered(path, mark_object_stream)
+        .map_err(|e| format!("Failed to load PDF {}: {}", path, e))?;
+    if !doc.is_encrypted() {
+        return Err(format!("PDF {} is not encrypted", path));
+    }
+
+    decrypt_document(&mut doc, password)?;
+    Ok(doc)
+}
+
+// 加密方式的描述,如 "AES-256"、"RC4 128-bit"; 未加密时返回 None
+pub fn encryption_method(doc: &Document) -> Option<String> {
+    let encrypt = doc.get_encrypted().ok()?;
+    let filter = encrypt.get(b"Filter").and_then(Object::as_name).unwrap_or(b"");
+    if filter != b"Standard" {
+        return Some(String::from_utf8_lossy(filter).to_string());
+    }
+
+    let length = encrypt.get(b"Length").and_then(Object::as_i64).unwrap_or(40);
+    let method = match encrypt.get(b"V").and_then(Object::as_i64).unwrap_or(0) {
+        5 => "AES-256".to_string(),
+        4 => {
+            let crypt_filter = encrypt
+                .get(b"CF")
+                .and_then(Object::as_dict)
+                .and_then(|filters| filters.get(b"StdCF"))
+                .and_then(Object::as_dict)
+                .and_then(|filter| filter.get(b"CFM"))
+                .and_then(Object::as_name)
+                .unwrap_or(b"");
+            if crypt_filter == b"AESV2" {
+                "AES-128".to_string()
+            } else {
+                format!("RC4 {}-bit", length)
+            }
+        }
+        2 => format!("RC4 {}-bit", length),
+        1 => "RC4 40-bit".to_string(),
+        version => format!("Unknown (V{})", version),
+    };
+    Some(method)
+}
+
+fn decrypt_document(doc: &mut Document, password: &str) -> Result<(), String> {
+    let encrypt_id = doc.trailer.get(b"Encrypt").and_then(Object::as_reference).ok();
+    let encrypt = doc.get_encrypted().map_err(|e| format!("Failed to read encryption: {}", e))?.clone();
+
+    let filter = encrypt.get(b"Filter").and_then(Object::as_name).unwrap_or(b"");
+    if filter != b"Standard" {
+        return Err(format!("Unsupported security handler: {}", String::from_utf8_lossy(filter)));
+    }
+    let encrypt_metadata = encrypt.get(b"EncryptMetadata").and_then(Object::as_bool).unwrap_or(true);
+
+    match encrypt.get(b"V").and_then(Object::as_i64).unwrap_or(0) {
+        5 => {
+            let cipher = aes256(&aes256_file_key(&encrypt, password.as_bytes())?);
+            decrypt_objects(doc, encrypt_id, encrypt_metadata, |_, data, _| decrypt_data(&cipher, data))?;
+        }
+        // RC4 (V1 / V2) 以及 V4 的加密过滤器 (RC4 或 AES-128)
+        1 | 2 | 4 => {
+            let key = LegacyHandler::new(doc, &encrypt)?.authenticate(password.as_bytes())?;
+            let (string_method, stream_method) = crypt_methods(&encrypt)?;
+            decrypt_objects(doc, encrypt_id, encrypt_metadata, |id, data, is_stream| {
+                let method = if is_stream { stream_method } else { string_method };
+                legacy_decrypt(&key, id, method, data)
+            })?;
+        }
+        version => return Err(format!("Unsupported encryption (V{})", version)),
+    }
+
+    if let Some(encrypt_id) = encrypt_id {
+        doc.objects.remove(&encrypt_id);
+    }
+    doc.trailer.remove(b"Encrypt");
+    restore_object_streams(doc)
+}
+
+// 解密除加密字典、交叉引用流以及 (未加密的) 元数据外的所有对象
+fn decrypt_objects(
+    doc: &mut Document,
+    encrypt_id: Option<ObjectId>,
+    encrypt_metadata: bool,
+    decrypt: impl Fn(ObjectId, &[u8], bool) -> Vec<u8>,
+) -> Result<(), String> {
+    for (&id, object) in doc.objects.iter_mut() {
+        if Some(id) == encrypt_id || is_xref_stream(object) {
+            continue;
+        }
+        if !encrypt_metadata && object.as_stream().map(|s| s.dict.type_is(b"Metadata")).unwrap_or(false) {
+            continue;
+        }
+        transform_object(object, &mut |data, is_stream| Ok(decrypt(id, data, is_stream)))?;
+    }
+    Ok(())
+}
+
+// 读取时把对象流改为临时类型,解密后再展开
+fn mark_object_stream(id: ObjectId, object: &mut Object) -> Option<(ObjectId, Object)> {
+    if let Object::Stream(stream) = object {
+        if stream.dict.type_is(b"ObjStm") {
+            stream.dict.set("Type", Object::Name(ENCRYPTED_OBJECT_STREAM.as_bytes().to_vec()));
+        }
+    }
+    Some((id, object.clone()))
+}
+
+fn restore_object_streams(doc: &mut Document) -> Result<(), String> {
+    let marked: Vec<ObjectId> = doc
+        .objects
+        .iter()
+        .filter(|(_, object)| {
+            object.as_stream().map(|s| s.dict.type_is(ENCRYPTED_OBJECT_STREAM.as_bytes())).unwrap_or(false)
+        })
+        .map(|(&id, _)| id)
+        .collect();
+
+    for id in marked {
+        let Some(Object::Stream(mut stream)) = doc.objects.remove(&id) else {
+            continue;
+        };
+        stream.dict.set("Type", Object::Name(b"ObjStm".to_vec()));
+        let object_stream =
+            ObjectStream::new(&mut stream).map_err(|e| format!("Failed to read object stream: {}", e))?;
+        // 与读取时一致: 对象流中的对象不覆盖已有对象
+        for (object_id, object) in object_stream.objects {
+            doc.objects.entry(object_id).or_insert(object);
+        }
+    }
+    Ok(())
+}
+
+// 校验密码 (先按用户密码,再按所有者密码) 并解出文件密钥
+fn aes256_file_key(encrypt: &Dictionary, password: &[u8]) -> Result<Vec<u8>, String> {
+    let revision = encrypt.get(b"R").and_then(Object::as_i64).unwrap_or(0);
+    let entry = |key: &[u8], len: usize| -> Result<Vec<u8>, String> {
+        let value = encrypt.get(key).and_then(Object::as_str).map_err(|_| "Invalid encryption dictionary")?;
+        value.get(..len).map(<[u8]>::to_vec).ok_or_else(|| "Invalid encryption dictionary".to_string())
+    };
+    let u = entry(b"U", 48)?;
+    let o = entry(b"O", 48)?;
+    let password = &password[..password.len().min(MAX_PASSWORD_BYTES)];
+
+    let hash = |salt: &[u8], udata: &[u8]| -> [u8; 32] {
+        if revision >= 6 {
+            hash_r6(password, salt, udata)
+        } else {
+            Sha256::new().chain_update(password).chain_update(salt).chain_update(udata).finalize().into()
+        }
+    };
+
+    let (intermediate, encrypted_key) = if hash(&u[32..40], &[])[..] == u[..32] {
+        (hash(&u[40..48], &[]), entry(b"UE", 32)?)
+    } else if hash(&o[32..40], &u)[..] == o[..32] {
+        (hash(&o[40..48], &u), entry(b"OE", 32)?)
+    } else {
+        return Err("Incorrect password".to_string());
+    };
+    Ok(cbc_decrypt(&aes256(&intermediate), &[0; 16], &encrypted_key))
+}
+
+// V4 由 StrF / StmF 指定的加密过滤器决定字符串和流的算法,更早的版本都用 RC4
+fn crypt_methods(encrypt: &Dictionary) -> Result<(CryptMethod, CryptMethod), String> {
+    if encrypt.get(b"V").and_then(Object::as_i64).unwrap_or(0) < 4 {
+        return Ok((CryptMethod::Rc4, CryptMethod::Rc4));
+    }
+
+    let method = |key: &[u8]| -> Result<CryptMethod, String> {
+        let name = encrypt.get(key).and_then(Object::as_name).unwrap_or(b"Identity");
+        if name == b"Identity" {
+            return Ok(CryptMethod::Identity);
+        }
+        let filter = encrypt
+            .get(b"CF")
+            .and_then(Object::as_dict)
+            .and_then(|filters| filters.get(name))
+            .and_then(Object::as_dict)
+            .map_err(|_| format!("Missing crypt filter: {}", String::from_utf8_lossy(name)))?;
+        match filter.get(b"CFM").and_then(Object::as_name).unwrap_or(b"None") {
+            b"V2" => Ok(CryptMethod::Rc4),
+            b"AESV2" => Ok(CryptMethod::Aes128),
+            b"None" => Ok(CryptMethod::Identity),
+            method => Err(format!("Unsupported crypt filter method: {}", String::from_utf8_lossy(method))),
+        }
+    };
+    Ok((method(b"StrF")?, method(b"StmF")?))
+}
+
+// RC4 / AES-128 (R2 - R4) 标准安全处理程序的参数
+struct LegacyHandler<'a> {
+    revision: i64,
+    // 文件密钥字节数
+    key_len: usize,
+    o: &'a [u8],
+    u: &'a [u8],
+    permissions: u32,
+    file_id: &'a [u8],
+    encrypt_metadata: bool,
+}
+
+impl<'a> LegacyHandler<'a> {
+    fn new(doc: &'a Document, encrypt: &'a Dictionary) -> Result<Self, String> {
+        let version = encrypt.get(b"V").and_then(Object::as_i64).unwrap_or(0);
+        let revision = encrypt.get(b"R").and_then(Object::as_i64).unwrap_or(0);
+        if !(2..=4).contains(&revision) {
+            return Err(format!("Unsupported encryption revision: {}", revision));
+        }
+        // Length 以位为单位; R2 固定 40 位,V4 默认 128 位
+        let length = match (revision, version) {
+            (2, _) => 40,
+            (_, 4) => encrypt.get(b"Length").and_then(Object::as_i64).unwrap_or(128),
+            _ => encrypt.get(b"Length").and_then(Object::as_i64).unwrap_or(40),
+        };
+        if !(40..=128).contains(&length) || length % 8 != 0 {
+            return Err(format!("Invalid encryption key length: {}", length));
+        }
+
+        let entry = |key: &[u8]| -> Result<&'a [u8], String> {
+            let value = encrypt.get(key).and_then(Object::as_str).map_err(|_| "Invalid encryption dictionary")?;
+            value.get(..32).ok_or_else(|| "Invalid encryption dictionary".to_string())
+        };
+        Ok(LegacyHandler {
+            revision,
+            key_len: length as usize / 8,
+            o: entry(b"O")?,
+            u: entry(b"U")?,
+            permissions: encrypt.get(b"P").and_then(Object::as_i64).map_err(|_| "Invalid encryption dictionary")?
+                as u32,
+            file_id: doc
+                .trailer
+                .get(b"ID")
+                .and_then(Object::as_array)
+                .ok()
+                .and_then(|id| id.first())
+                .and_then(|id| id.as_str().ok())
+                .unwrap_or(b""),
+            encrypt_metadata: encrypt.get(b"EncryptMetadata").and_then(Object::as_bool).unwrap_or(true),
+        })
+    }
+
+    // 校验密码并返回文件密钥 (算法 6、7): 先按用户密码校验,
+    // 不符时当作所有者密码,用它解开 O 得到用户密码再校验
+    fn authenticate(&self, password: &[u8]) -> Result<Vec<u8>, String> {
+        let key = self.file_key(password);
+        if self.is_file_key(&key) {
+            return Ok(key);
+        }
+
+        let owner_key = self.owner_key(password);
+        let user_password = if self.revision == 2 {
+            rc4(&owner_key, self.o)
+        } else {
+            rc4_rounds(&owner_key, self.o, (0..=19).rev())
+        };
+        let key = self.file_key(&user_password);
+        if self.is_file_key(&key) {
+            return Ok(key);
+        }
+        Err("Incorrect password".to_string())
+    }
+
+    // 由用户密码计算文件密钥 (算法 2)
+    fn file_key(&self, password: &[u8]) -> Vec<u8> {
+        let mut context = md5::Context::new();
+        context.consume(padded_password(password));
+        context.consume(self.o);
+        context.consume(self.permissions.to_le_bytes());
+        context.consume(self.file_id);
+        if self.revision >= 4 && !self.encrypt_metadata {
+            context.consume([0xFF; 4]);
+        }
+        let mut key = context.compute().0[..self.key_len].to_vec();
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                key = md5::compute(&key).0[..self.key_len].to_vec();
+            }
+        }
+        key
+    }
+
+    // U 的期望值 (算法 4、5); R3 起只有前 16 字节有意义
+    fn user_hash(&self, file_key: &[u8]) -> Vec<u8> {
+        if self.revision == 2 {
+            return rc4(file_key, &PASSWORD_PADDING);
+        }
+        let mut context = md5::Context::new();
+        context.consume(PASSWORD_PADDING);
+        context.consume(self.file_id);
+        rc4_rounds(file_key, &context.compute().0, 0..=19)
+    }
+
+    fn is_file_key(&self, file_key: &[u8]) -> bool {
+        let len = if self.revision == 2 { 32 } else { 16 };
+        self.user_hash(file_key)[..len] == self.u[..len]
+    }
+
+    // 由所有者密码派生的 RC4 密钥,用于解开 O (算法 3 的前几步)
+    fn owner_key(&self, password: &[u8]) -> Vec<u8> {
+        let mut key = md5::compute(padded_password(password)).0;
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                key = md5::compute(key).0;
+            }
+        }
+        key[..self.key_len].to_vec()
+    }
+}
+
+fn padded_password(password: &[u8]) -> [u8; 32] {
+    let len = password.len().min(32);
+    let mut padded = PASSWORD_PADDING;
+    padded.copy_within(..32 - len, len);
+    padded[..len].copy_from_slice(&password[..len]);
+    padded
+}
+
+// 对象密钥 (算法 1): 文件密钥 + 对象号低 3 字节 + 代号低 2 字节,AES 另加 "sAlT"
+fn object_key(file_key: &[u8], id: ObjectId, method: CryptMethod) -> Vec<u8> {
+    let mut context = md5::Context::new();
+    context.consume(file_key);
+    context.consume(&id.0.to_le_bytes()[..3]);
+    context.consume(id.1.to_le_bytes());
+    if method == CryptMethod::Aes128 {
+        context.consume(b"sAlT");
+        return context.compute().0.to_vec();
+    }
+    context.compute().0[..(file_key.len() + 5).min(16)].to_vec()
+}
+
+fn legacy_decrypt(file_key: &[u8], id: ObjectId, method: CryptMethod, data: &[u8]) -> Vec<u8> {
+    let key = object_key(file_key, id, method);
+    match method {
+        CryptMethod::Identity => data.to_vec(),
+        CryptMethod::Rc4 => rc4(&key, data),
+        CryptMethod::Aes128 => decrypt_data(&aes128(&key), data),
+    }
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|&byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            byte ^ state[state[i as usize].wrapping_add(state[j as usize]) as usize]
+        })
+        .collect()
+}
+
+// R3 起的多轮 RC4: 每轮的密钥为原密钥逐字节异或轮次
+fn rc4_rounds(key: &[u8], data: &[u8], rounds: impl Iterator<Item = u8>) -> Vec<u8> {
+    let mut data = data.to_vec();
+    for round in rounds {
+        let round_key: Vec<u8> = key.iter().map(|b| b ^ round).collect();
+        data = rc4(&round_key, &data);
+    }
+    data
+}
+
+// R6 的密码哈希 (ISO 32000-2 算法 2.B)
+fn hash_r6(password: &[u8], salt: &[u8], udata: &[u8]) -> [u8; 32] {
+    let mut k: Vec<u8> = Sha256::new().chain_update(password).chain_update(salt).chain_update(udata).finalize().to_vec();
+
+    let mut round = 0;
+    loop {
+        let mut k1 = Vec::with_capacity(64 * (password.len() + k.len() + udata.len()));
+        for _ in 0..64 {
+            k1.extend_from_slice(password);
+            k1.extend_from_slice(&k);
+            k1.extend_from_slice(udata);
+        }
+
+        let e = cbc_encrypt(&aes128(&k[..16]), &k[16..32], &k1);
+
+        // 前 16 字节作为大整数对 3 取模,决定下一轮使用的哈希
+        k = match e[..16].iter().map(|&b| b as u32).sum::<u32>() % 3 {
+            0 => Sha256::digest(&e).to_vec(),
+            1 => Sha384::digest(&e).to_vec(),
+            _ => Sha512::digest(&e).to_vec(),
+        };
+
+        // 至少 64 轮,之后 E 的最后一字节不大于 (已完成轮数 - 32) 时结束
+        round += 1;
+        let last = *e.last().unwrap_or(&0) as i32;
+        if round >= 64 && last <= round - 32 {
+            break;
+        }
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&k[..32]);
+    hash
+}
+
+// 对象中的字符串与流内容逐个转换 (加密或解密),f 的第二个参数表示是否为流内容
+fn transform_object<F>(object: &mut Object, f: &mut F) -> Result<(), String>
+where
+    F: FnMut(&[u8], bool) -> Result<Vec<u8>, String>,
+{
+    match object {
+        Object::String(bytes, format) => {
+            *bytes = f(bytes, false)?;
+            *format = StringFormat::Hexadecimal;
+        }
+        Object::Array(items) => {
+            for item in items {
+                transform_object(item, f)?;
+            }
+        }
+        Object::Dictionary(dict) => {
+            for (_, value) in dict.iter_mut() {
+                transform_object(value, f)?;
+            }
+        }
+        Object::Stream(stream) => {
+            for (_, value) in stream.dict.iter_mut() {
+                transform_object(value, f)?;
+            }
+            let content = f(&stream.content, true)?;
+            stream.set_content(content);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn is_xref_stream(object: &Object) -> bool {
+    object.as_stream().map(|stream| stream.dict.type_is(b"XRef")).unwrap_or(false)
+}
+
+// AES-CBC: 随机 IV 放在密文前,PKCS#7 填充
+fn encrypt_data<C: BlockEncrypt + BlockSizeUser<BlockSize = U16>>(cipher: &C, data: &[u8]) -> Result<Vec<u8>, String> {
+    let iv = random_bytes(16)?;
+    let padding = 16 - data.len() % 16;
+    let mut padded = data.to_vec();
+    padded.resize(data.len() + padding, padding as u8);
+
+    let mut output = iv.clone();
+    output.extend(cbc_encrypt(cipher, &iv, &padded));
+    Ok(output)
+}
+
+// 数据不是合法的密文时原样保留
+fn decrypt_data<C: BlockDecrypt + BlockSizeUser<BlockSize = U16>>(cipher: &C, data: &[u8]) -> Vec<u8> {
+    if data.len() < 32 || !data.len().is_multiple_of(16) {
+        return data.to_vec();
+    }
+
+    let mut plain = cbc_decrypt(cipher, &data[..16], &data[16..]);
+    let padding = *plain.last().unwrap_or(&0) as usize;
+    if (1..=16).contains(&padding) && plain[plain.len() - padding..].iter().all(|&b| b as usize == padding) {
+        plain.truncate(plain.len() - padding);
+    }
+    plain
+}
+
+// 密钥均为 32 字节 (SHA-256 或文件密钥)
+fn aes256(key: &[u8]) -> Aes256 {
+    Aes256::new_from_slice(key).expect("AES-256 key is 32 bytes")
+}
+
+// 密钥为 16 字节 (对象密钥或 R6 哈希的中间值)
+fn aes128(key: &[u8]) -> Aes128 {
+    Aes128::new_from_slice(key).expect("AES-128 key is 16 bytes")
+}
+
+fn block(bytes: &[u8]) -> Block {
+    let mut block = Block::default();
+    block.copy_from_slice(bytes);
+    block
+}
+
+// 数据长度须为 16 的倍数
+fn cbc_encrypt<C: BlockEncrypt + BlockSizeUser<BlockSize = U16>>(cipher: &C, iv: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut previous = block(iv);
+    let mut output = Vec::with_capacity(data.len());
+    for chunk in data.chunks_exact(16) {
+        let mut current = block(chunk);
+        for (b, p) in current.iter_mut().zip(previous.iter()) {
+            *b ^= p;
+        }
+        cipher.encrypt_block(&mut current);
+        output.extend_from_slice(&current);
+        previous = current;
+    }
+    output
+}
+
+fn cbc_decrypt<C: BlockDecrypt + BlockSizeUser<BlockSize = U16>>(cipher: &C, iv: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut previous = block(iv);
+    let mut output = Vec::with_capacity(data.len());
+    for chunk in data.chunks_exact(16) {
+        let mut current = block(chunk);
+        cipher.decrypt_block(&mut current);
+        for (b, p) in current.iter_mut().zip(previous.iter()) {
+            *b ^= p;
+        }
+        output.extend_from_slice(&current);
+        previous.copy_from_slice(chunk);
+    }
+    output
+}
+
+fn password_bytes(password: &str) -> &[u8] {
+    let bytes = password.as_bytes();
+    &bytes[..bytes.len().min(MAX_PASSWORD_BYTES)]
+}
+
+pub(crate) fn random_bytes(len: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0u8; len];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate random bytes: {}", e))?;
+    Ok(bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use printpdf::lopdf::{dictionary, Stream};
+
+    // pdf.js crypto_spec.js 中 PDF20 算法的用户密码用例
+    #[test]
+    fn hash_r6_matches_known_answer() {
+        let salt = [83, 245, 146, 101, 198, 247, 34, 198];
+        let expected = [
+            94, 230, 205, 75, 166, 99, 250, 76, 219, 128, 17, 85, 57, 17, 33, 164, 150, 46, 103, 176, 160, 156, 187,
+            233, 166, 223, 163, 253, 147, 235, 95, 184,
+        ];
+        assert_eq!(hash_r6(b"user", &salt, &[]), expected);
+    }
+
+    const CONTENT: &[u8] = b"BT /F1 12 Tf 72 720 Td (Secret text) Tj ET";
+
+    fn test_document() -> Document {
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let content_id = doc.add_object(Stream::new(dictionary! {}, CONTENT.to_vec()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            "Contents" => content_id,
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        let info_id = doc.add_object(dictionary! { "Title" => Object::string_literal("Secret title") });
+        doc.trailer.set("Root", catalog_id);
+        doc.trailer.set("Info", info_id);
+        doc
+    }
+
+    // 保存后分别用用户密码、所有者密码和错误密码打开
+    fn assert_opens_with_either_password(doc: &mut Document, name: &str) {
+        let path = std::env::temp_dir().join(format!("pdf_security_test_{}_{}.pdf", name, std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        doc.save(&path).unwrap();
+
+        for password in ["user", "owner"] {
+            let decrypted = load_encrypted_pdf(&path, password).unwrap();
+            let page_id = decrypted.get_pages()[&1];
+            assert_eq!(decrypted.get_page_content(page_id).unwrap(), CONTENT, "{} / {}", name, password);
+            let info = decrypted.trailer.get(b"Info").and_then(Object::as_reference).unwrap();
+            let title = decrypted.get_dictionary(info).unwrap().get(b"Title").unwrap();
+            assert_eq!(title.as_str().unwrap(), b"Secret title", "{} / {}", name, password);
+        }
+        assert_eq!(load_encrypted_pdf(&path, "wrong").err().as_deref(), Some("Incorrect password"), "{}", name);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    // 按 R2 - R4 的算法加密: 用户密码 "user",所有者密码 "owner",字符串和流使用同一算法
+    fn legacy_encrypt(doc: &mut Document, version: i64, revision: i64, length: i64, method: CryptMethod) {
+        let file_id = b"0123456789abcdef".to_vec();
+        let permissions: u32 = 0xFFFF_FFFC;
+        let handler = LegacyHandler {
+            revision,
+            key_len: length as usize / 8,
+            o: &[],
+            u: &[],
+            permissions,
+            file_id: &file_id,
+            encrypt_metadata: true,
+        };
+
+        // O = 用所有者密码派生的密钥加密的用户密码 (算法 3)
+        let owner_key = handler.owner_key(b"owner");
+        let user_password = padded_password(b"user");
+        let o = if revision == 2 {
+            rc4(&owner_key, &user_password)
+        } else {
+            rc4_rounds(&owner_key, &user_password, 0..=19)
+        };
+        let handler = LegacyHandler { o: &o, ..handler };
+        let file_key = handler.file_key(b"user");
+        let mut u = handler.user_hash(&file_key);
+        u.resize(32, 0);
+
+        for (&id, object) in doc.objects.iter_mut() {
+            let key = object_key(&file_key, id, method);
+            transform_object(object, &mut |data, _| match method {
+                CryptMethod::Aes128 => encrypt_data(&aes128(&key), data),
+                _ => Ok(rc4(&key, data)),
+            })
+            .unwrap();
+        }
+
+        let mut encrypt = dictionary! {
+            "Filter" => "Standard",
+            "V" => version,
+            "R" => revision,
+            "Length" => length,
+            "O" => Object::String(o, StringFormat::Hexadecimal),
+            "U" => Object::String(u, StringFormat::Hexadecimal),
+            "P" => permissions as i32 as i64,
+        };
+        if version == 4 {
+            let method = if method == CryptMethod::Aes128 { "AESV2" } else { "V2" };
+            encrypt.set("CF", dictionary! { "StdCF" => dictionary! { "Type" => "CryptFilter", "CFM" => method } });
+            encrypt.set("StmF", "StdCF");
+            encrypt.set("StrF", "StdCF");
+        }
+        let encrypt_id = doc.add_object(encrypt);
+        doc.trailer.set("Encrypt", encrypt_id);
+        let file_id = Object::String(file_id, StringFormat::Hexadecimal);
+        doc.trailer.set("ID", vec![file_id.clone(), file_id]);
+    }
+
+    #[test]
+    fn encrypted_document_opens_with_either_password() {
+        let mut doc = test_document();
+        let options = PdfEncryption {
+            user_password: "user".to_string(),
+            owner_password: "owner".to_string(),
+            ..Default::default()
+        };
+        encrypt_document(&mut doc, &options).unwrap();
+        assert_opens_with_either_password(&mut doc, "aes256");
+    }
+
+    #[test]
+    fn legacy_encrypted_documents_open_with_either_password() {
+        let cases = [
+            ("rc4_40", 1, 2, 40, CryptMethod::Rc4),
+            ("rc4_128", 2, 3, 128, CryptMethod::Rc4),
+            ("v4_rc4", 4, 4, 128, CryptMethod::Rc4),
+            ("v4_aes128", 4, 4, 128, CryptMethod::Aes128),
+        ];
+        for (name, version, revision, length, method) in cases {
+            let mut doc = test_document();
+            legacy_encrypt(&mut doc, version, revision, length, method);
+            assert_opens_with_either_password(&mut doc, name);
+        }
+    }
+}
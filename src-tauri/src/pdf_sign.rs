@@ -0,0 +1,209 @@
+use printpdf::lopdf::{Document, Object, ObjectId};
+use printpdf::Mm;
+use serde::{Deserialize, Serialize};
+
+use crate::pdf_fonts::{self, PdfFonts};
+use crate::pdf_generator::{self, PageLayout, PdfBuilder, PdfImageInput, PdfOptions};
+use crate::pdf_image::{ImageEncoding, SourceImage};
+use crate::pdf_overlay;
+use crate::pdf_text::PT_PER_MM;
+use crate::pdf_utils::{self, resolve};
+
+// 预设位置与页边的距离 (毫米)
+const PRESET_MARGIN_MM: f32 = 15.0;
+// 签名图片与下方文字的间距 (毫米)
+const TEXT_GAP_MM: f32 = 1.5;
+// 文字行距,字号的倍数
+const STAMP_LINE_SPACING: f32 = 1.3;
+
+// 签名在页面上的位置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SignaturePosition {
+    // 最后一页右下角
+    #[default]
+    LastPageBottomRight,
+    // 指定页 (从 1 开始),签名左上角到页面左上角的距离 (毫米),按页面显示方向计算
+    Page { page: u32, x_mm: f32, y_mm: f32 },
+}
+
+// 签名选项: 签名图片和下方的文字 (姓名、日期) 至少要有一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignatureOptions {
+    // 签名图片,PNG 的透明部分保持透明
+    pub image: Option<PdfImageInput>,
+    // 签名图片宽度 (毫米),高度按比例
+    pub image_width_mm: f32,
+    pub name: Option<String>,
+    // 日期文字,{date} 替换为当天日期
+    pub date: Option<String>,
+    // 文字字号 (点)
+    pub font_size: f32,
+    // 优先使用的字体,缺字时回退到内置字体和系统字体
+    pub font_families: Vec<String>,
+    pub position: SignaturePosition,
+}
+
+impl Default for SignatureOptions {
+    fn default() -> Self {
+        SignatureOptions {
+            image: None,
+            image_width_mm: 40.0,
+            name: None,
+            date: Some("{date}".to_string()),
+            font_size: 9.0,
+            font_families: Vec::new(),
+            position: SignaturePosition::default(),
+        }
+    }
+}
+
+// 在 PDF 的指定页面上盖签名: 签名图片居上,姓名和日期逐行居中排在下方
+pub fn sign_pdf(path: &str, options: &SignatureOptions, output_path: &str) -> Result<(), String> {
+    if !(options.image_width_mm.is_finite() && options.image_width_mm > 0.0) {
+        return Err(format!("Invalid signature width: {} mm", options.image_width_mm));
+    }
+    if !(options.font_size.is_finite() && options.font_size > 0.0) {
+        return Err(format!("Invalid font size: {}", options.font_size));
+    }
+
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let lines: Vec<String> = [&options.name, &options.date]
+        .into_iter()
+        .flatten()
+        .map(|line| line.replace("{date}", &date).trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+    if options.image.is_none() && lines.is_empty() {
+        return Err("Signature needs an image or text".to_string());
+    }
+
+    let mut doc = pdf_utils::load_pdf(path)?;
+    pdf_utils::flatten_inherited_page_attributes(&mut doc);
+
+    let pages = doc.get_pages();
+    let page_number = match options.position {
+        SignaturePosition::LastPageBottomRight => pages.len() as u32,
+        SignaturePosition::Page { page, .. } => page,
+    };
+    let page_id = *pages
+        .get(&page_number)
+        .ok_or_else(|| format!("Page {} is out of range (1-{})", page_number, pages.len()))?;
+
+    let (stamp, width, height) = render_stamp(&mut doc, options, &lines)?;
+
+    // 按页面显示方向计算签名左下角,再换算到页面坐标
+    let (display, page_width, page_height) = display_transform(&doc, page_id);
+    let (u, v) = match options.position {
+        SignaturePosition::LastPageBottomRight => {
+            let margin = PRESET_MARGIN_MM * PT_PER_MM;
+            (page_width - margin - width, margin)
+        }
+        SignaturePosition::Page { x_mm, y_mm, .. } => {
+            if !x_mm.is_finite() || !y_mm.is_finite() {
+                return Err("Invalid signature position".to_string());
+            }
+            (x_mm * PT_PER_MM, page_height - y_mm * PT_PER_MM - height)
+        }
+    };
+    let [a, b, c, d, e, f] = display;
+    let matrix = [a, b, c, d, a * u + c * v + e, b * u + d * v + f];
+    pdf_overlay::place_forms(&mut doc, page_id, &[(stamp, matrix)])?;
+
+    doc.compress();
+    pdf_utils::save_pdf(&mut doc, output_path)?;
+    println!("签名 PDF: {}, 第 {} 页", path, page_number);
+    Ok(())
+}
+
+// 用 printpdf 绘制签名 (一页,大小正好容纳图片和文字),作为表单 XObject 复制到文档中;
+// 返回 XObject 和尺寸 (pt)
+fn render_stamp(doc: &mut Document, options: &SignatureOptions, lines: &[String]) -> Result<(ObjectId, f32, f32), String> {
+    let image = options
+        .image
+        .as_ref()
+        .map(|image| SourceImage::open(image.path(), image.operations()).map_err(|e| format!("{} ({})", e, image.path())))
+        .transpose()?;
+    let font_files = if lines.is_empty() {
+        Vec::new()
+    } else {
+        pdf_fonts::load_fonts(&options.font_families, &lines.concat())?
+    };
+
+    let mut builder = PdfBuilder::new("Signature", false);
+    let fonts = PdfFonts::new(&builder.doc, &font_files)?;
+    let font_size = options.font_size;
+    let line_height = font_size * STAMP_LINE_SPACING / PT_PER_MM;
+
+    let (image_width, image_height) = match &image {
+        Some(image) => {
+            let width = options.image_width_mm;
+            (width, width * image.height() as f32 / image.width().max(1) as f32)
+        }
+        None => (0.0, 0.0),
+    };
+    let text_width = lines
+        .iter()
+        .map(|line| fonts.measurer.text_width(line, font_size) / PT_PER_MM)
+        .fold(0.0, f32::max);
+    let text_height = match lines.len() {
+        0 => 0.0,
+        count => count as f32 * line_height + if image.is_some() { TEXT_GAP_MM } else { 0.0 },
+    };
+    let width = image_width.max(text_width);
+    let height = image_height + text_height;
+
+    let layout = PageLayout {
+        width_mm: width,
+        height_mm: height,
+        image_encoding: ImageEncoding::default(),
+        ..PdfOptions::default().text_layout()
+    };
+    let layer = builder.add_page(&layout);
+    if let Some(image) = &image {
+        let x = (width - image_width) / 2.0;
+        builder.place_image(&layer, image, x, text_height, image_width, image_height)?;
+    }
+
+    // 行高内上下留白相同
+    let descent = (line_height - font_size / PT_PER_MM) / 2.0 + font_size * 0.22 / PT_PER_MM;
+    for (index, line) in lines.iter().enumerate() {
+        let baseline = (lines.len() - 1 - index) as f32 * line_height + descent;
+        let x = (width - fonts.measurer.text_width(line, font_size) / PT_PER_MM) / 2.0;
+        fonts.use_text(&layer, line, font_size, Mm(x), Mm(baseline));
+    }
+    drop(fonts);
+
+    let overlay = pdf_generator::finish_document(builder)?;
+    let stamp = pdf_overlay::import_pages_as_forms(doc, overlay)?
+        .pop()
+        .ok_or("Failed to create signature")?;
+    Ok((stamp, width * PT_PER_MM, height * PT_PER_MM))
+}
+
+// 页面显示坐标 (原点在显示时的左下角) 到页面坐标的变换矩阵,以及显示时的宽高 (pt);
+// 可见区域优先取 CropBox,Rotate 为显示时顺时针旋转的角度
+fn display_transform(doc: &Document, page_id: ObjectId) -> ([f32; 6], f32, f32) {
+    let page = doc.get_dictionary(page_id).ok();
+    let bbox = page
+        .and_then(|page| page.get(b"CropBox").or_else(|_| page.get(b"MediaBox")).ok())
+        .and_then(|bbox| resolve(doc, bbox))
+        .and_then(|bbox| bbox.as_array().ok())
+        .and_then(|bbox| bbox.iter().map(|value| value.as_float().ok()).collect::<Option<Vec<f32>>>())
+        .filter(|bbox| bbox.len() == 4)
+        .unwrap_or_else(|| vec![0.0, 0.0, 595.0, 842.0]);
+    let (x1, x2) = (bbox[0].min(bbox[2]), bbox[0].max(bbox[2]));
+    let (y1, y2) = (bbox[1].min(bbox[3]), bbox[1].max(bbox[3]));
+    let rotation = page
+        .and_then(|page| page.get(b"Rotate").and_then(Object::as_i64).ok())
+        .unwrap_or(0)
+        .rem_euclid(360);
+
+    match rotation {
+        90 => ([0.0, 1.0, -1.0, 0.0, x2, y1], y2 - y1, x2 - x1),
+        180 => ([-1.0, 0.0, 0.0, -1.0, x2, y2], x2 - x1, y2 - y1),
+        270 => ([0.0, -1.0, 1.0, 0.0, x1, y2], y2 - y1, x2 - x1),
+        _ => ([1.0, 0.0, 0.0, 1.0, x1, y1], x2 - x1, y2 - y1),
+    }
+}
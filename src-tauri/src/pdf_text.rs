@@ -0,0 +1,405 @@
+use std::ops::Range;
+use ttf_parser::Face;
+
+// 点 (pt) 与毫米换算
+pub const PT_PER_MM: f32 = 72.0 / 25.4;
+
+// 基于字体字形宽度测量文本; 按顺序使用多个字体,字符取第一个包含它的字体
+pub struct TextMeasurer<'a> {
+    faces: Vec<Face<'a>>,
+}
+
+impl<'a> TextMeasurer<'a> {
+    pub fn new(fonts: &[&'a [u8]]) -> Result<Self, String> {
+        let faces = fonts
+            .iter()
+            .map(|data| Face::parse(data, 0).map_err(|e| format!("Failed to parse font: {}", e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if faces.is_empty() {
+            return Err("No font available".to_string());
+        }
+        Ok(TextMeasurer { faces })
+    }
+
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.faces.iter().any(|face| face_covers(face, c))
+    }
+
+    // 包含该字符的第一个字体的下标,都不包含时使用主字体
+    pub fn font_index(&self, c: char) -> usize {
+        self.faces.iter().position(|face| face_covers(face, c)).unwrap_or(0)
+    }
+
+    // 单个字符宽度 (pt),字体缺字时按半个字宽估算,不可见的格式字符不占宽度
+    pub fn char_width(&self, c: char, font_size: f32) -> f32 {
+        let index = self.font_index(c);
+        let face = &self.faces[index];
+        let units_per_em = face.units_per_em() as f32;
+        let advance = match face.glyph_index(c).and_then(|id| face.glyph_hor_advance(id)) {
+            Some(advance) => advance as f32,
+            None if is_ignorable(c) => 0.0,
+            None => units_per_em / 2.0,
+        };
+        advance * font_size / units_per_em
+    }
+
+    pub fn text_width(&self, text: &str, font_size: f32) -> f32 {
+        text.chars().map(|c| self.char_width(c, font_size)).sum()
+    }
+
+    // 上升高度 (pt),用于确定首行基线位置
+    pub fn ascent(&self, font_size: f32) -> f32 {
+        let face = &self.faces[0];
+        face.ascender() as f32 * font_size / face.units_per_em() as f32
+    }
+
+    // 按字体把文本拆成 (字体下标, 文本) 片段,与 char_width 使用相同的字体;
+    // 没有字体包含的格式字符 (如变体选择符) 直接丢弃
+    pub fn font_runs(&self, text: &str) -> Vec<(usize, String)> {
+        let mut runs: Vec<(usize, String)> = Vec::new();
+
+        for c in text.chars() {
+            if is_ignorable(c) && !self.has_glyph(c) {
+                continue;
+            }
+
+            let index = self.font_index(c);
+            match runs.last_mut() {
+                Some((last, run)) if *last == index => run.push(c),
+                _ => runs.push((index, c.to_string())),
+            }
+        }
+
+        runs
+    }
+}
+
+// 字体包含该字符的可见字形; 彩色表情等位图字体的字形没有轮廓,无法嵌入使用
+pub fn face_covers(face: &Face, c: char) -> bool {
+    match face.glyph_index(c) {
+        Some(id) => c.is_whitespace() || face.glyph_bounding_box(id).is_some(),
+        None => false,
+    }
+}
+
+// 不需要字形的控制字符与格式字符 (零宽字符、变体选择符等)
+pub fn is_ignorable(c: char) -> bool {
+    c.is_control()
+        || matches!(c as u32,
+            0x200B..=0x200F     // 零宽空格、连接符、方向标记
+            | 0x2060..=0x2064   // 字连接符等
+            | 0xFE00..=0xFE0F   // 变体选择符
+            | 0xFEFF            // 字节顺序标记
+            | 0xE0100..=0xE01EF // 补充变体选择符
+        )
+}
+
+// 中日韩文字,可在任意字符间断行
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x2E80..=0x9FFF     // 部首、假名、CJK 标点、统一表意文字
+        | 0xAC00..=0xD7AF   // 韩文音节
+        | 0xF900..=0xFAFF   // 兼容表意文字
+        | 0xFF00..=0xFFEF   // 全角字符
+        | 0x20000..=0x2FA1F // 扩展区
+    )
+}
+
+// 不能出现在行首的标点
+fn is_no_break_before(c: char) -> bool {
+    "，。、；：？！）」』》〉】〕”’…—·,.;:?!)]}%".contains(c)
+}
+
+// 不能出现在行尾的标点
+fn is_no_break_after(c: char) -> bool {
+    "（「『《〈【〔“‘([{".contains(c)
+}
+
+#[derive(Debug, PartialEq)]
+enum TokenKind {
+    Word,
+    Space,
+}
+
+// 切分为断行单位: 拉丁单词、空白、单个 CJK 字符,标点粘连到相邻单位
+fn tokenize(line: &str) -> Vec<(TokenKind, String)> {
+    let mut tokens: Vec<(TokenKind, String)> = Vec::new();
+    let mut glue_next = false;
+
+    for c in line.chars() {
+        let kind = if c.is_whitespace() { TokenKind::Space } else { TokenKind::Word };
+
+        let attach = match tokens.last() {
+            Some((last_kind, last)) => {
+                if glue_next && kind == TokenKind::Word {
+                    true
+                } else if kind == TokenKind::Space {
+                    *last_kind == TokenKind::Space
+                } else if *last_kind == TokenKind::Space {
+                    false
+                } else if is_no_break_before(c) {
+                    true
+                } else {
+                    // 拉丁字符连续组成单词,CJK 字符各自独立
+                    let prev = last.chars().last().unwrap_or(' ');
+                    !is_cjk(c) && !is_cjk(prev) && !is_no_break_before(prev)
+                }
+            }
+            None => false,
+        };
+
+        if attach {
+            tokens.last_mut().unwrap().1.push(c);
+        } else {
+            tokens.push((kind, c.to_string()));
+        }
+        glue_next = is_no_break_after(c);
+    }
+
+    tokens
+}
+
+// 查找文本中的网址 (http://、https://、www. 开头),返回 (字节范围, 链接地址);
+// 网址在空白或非 ASCII 字符处结束,末尾的标点和不成对的右括号不算在内
+pub fn find_urls(text: &str) -> Vec<(Range<usize>, String)> {
+    let mut urls = Vec::new();
+    let mut pos = 0;
+
+    while pos < text.len() {
+        let rest = &text[pos..];
+        let prefix = ["https://", "http://", "www."].into_iter().find(|prefix| {
+            rest.get(..prefix.len()).is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+        });
+        let at_boundary = !text[..pos].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '.' || c == '/');
+        let Some(prefix) = prefix.filter(|_| at_boundary) else {
+            pos += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+
+        let mut end = pos + rest.find(|c: char| !c.is_ascii_graphic() || "<>\"`".contains(c)).unwrap_or(rest.len());
+        loop {
+            let url = &text[pos..end];
+            let trim = match url.chars().last() {
+                Some('.' | ',' | ';' | ':' | '!' | '?' | '\'') => true,
+                Some(')') => url.matches('(').count() < url.matches(')').count(),
+                Some(']') => url.matches('[').count() < url.matches(']').count(),
+                _ => false,
+            };
+            if !trim {
+                break;
+            }
+            end -= 1;
+        }
+
+        if end > pos + prefix.len() {
+            let url = &text[pos..end];
+            let target = if prefix == "www." { format!("http://{}", url) } else { url.to_string() };
+            urls.push((pos..end, target));
+        }
+        pos = end.max(pos + prefix.len());
+    }
+
+    urls
+}
+
+// 两端对齐的额外间距 (pt): 有空格时分给空格,没有空格 (如中文) 时分给字符间隔;
+// 返回 (每个空格增加的宽度, 每个字符后增加的间距)
+pub fn justify_spacing(line: &str, free_width: f32) -> (f32, f32) {
+    if free_width <= 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let spaces = line.chars().filter(|c| *c == ' ').count();
+    if spaces > 0 {
+        return (free_width / spaces as f32, 0.0);
+    }
+
+    match line.chars().count() {
+        0 | 1 => (0.0, 0.0),
+        chars => (0.0, free_width / (chars - 1) as f32),
+    }
+}
+
+// 按宽度断行带样式的文本片段 (如粗体、代码),单词跨越样式边界时不会被断开;
+// 每行返回合并了相邻同样式字符的片段
+pub fn wrap_runs<S: Clone>(
+    runs: &[(String, S)],
+    max_width: f32,
+    char_width: impl Fn(char, &S) -> f32,
+) -> Vec<Vec<(String, S)>> {
+    // 每个字符记录所属片段的下标
+    let styled: Vec<(char, usize)> = runs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (text, _))| text.chars().map(move |c| (c, i)))
+        .collect();
+    let full: String = styled.iter().map(|(c, _)| *c).collect();
+    let width_of = |(c, i): (char, usize)| char_width(c, &runs[i].1);
+
+    let mut lines: Vec<Vec<(char, usize)>> = Vec::new();
+    let mut current: Vec<(char, usize)> = Vec::new();
+    let mut current_width = 0.0;
+    let mut offset = 0;
+
+    for (kind, token) in tokenize(&full) {
+        let count = token.chars().count();
+        let token = &styled[offset..offset + count];
+        offset += count;
+        let width: f32 = token.iter().map(|&c| width_of(c)).sum();
+
+        if current_width + width <= max_width {
+            current.extend_from_slice(token);
+            current_width += width;
+            continue;
+        }
+
+        // 空白处断行时丢弃空白
+        if kind == TokenKind::Space {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+                current_width = 0.0;
+            }
+            continue;
+        }
+
+        if !current.is_empty() {
+            lines.push(trim_end(std::mem::take(&mut current)));
+            current_width = 0.0;
+        }
+
+        if width <= max_width {
+            current = token.to_vec();
+            current_width = width;
+            continue;
+        }
+
+        // 单个单位比整行还宽,只能逐字符断开
+        for &c in token {
+            let char_width = width_of(c);
+            if current_width + char_width > max_width && !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+                current_width = 0.0;
+            }
+            current.push(c);
+            current_width += char_width;
+        }
+    }
+
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(trim_end(current));
+    }
+
+    lines
+        .into_iter()
+        .map(|line| {
+            let mut pieces: Vec<(String, usize)> = Vec::new();
+            for (c, i) in line {
+                match pieces.last_mut() {
+                    Some((text, last)) if *last == i => text.push(c),
+                    _ => pieces.push((c.to_string(), i)),
+                }
+            }
+            pieces.into_iter().map(|(text, i)| (text, runs[i].1.clone())).collect()
+        })
+        .collect()
+}
+
+fn trim_end(mut line: Vec<(char, usize)>) -> Vec<(char, usize)> {
+    while line.last().is_some_and(|(c, _)| c.is_whitespace()) {
+        line.pop();
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个字符宽 1,便于按字符数断行
+    fn wrap(text: &str, max_width: f32) -> Vec<String> {
+        wrap_runs(&[(text.to_string(), ())], max_width, |_, _| 1.0)
+            .into_iter()
+            .map(|line| line.into_iter().map(|(text, _)| text).collect())
+            .collect()
+    }
+
+    fn words(line: &str) -> Vec<String> {
+        tokenize(line).into_iter().map(|(_, token)| token).collect()
+    }
+
+    #[test]
+    fn tokenize_splits_cjk_per_character_and_latin_per_word() {
+        assert_eq!(words("你好，world foo"), ["你", "好，", "world", " ", "foo"]);
+        // 括号和引号粘连在内容两侧
+        assert_eq!(words("（注）“好”"), ["（注）", "“好”"]);
+        assert_eq!(
+            tokenize("a  b").into_iter().map(|(kind, _)| kind).collect::<Vec<_>>(),
+            [TokenKind::Word, TokenKind::Space, TokenKind::Word]
+        );
+    }
+
+    #[test]
+    fn wrap_breaks_cjk_between_characters() {
+        assert_eq!(wrap("一二三四五六", 4.0), ["一二三四", "五六"]);
+        // 行首不能是句末标点,标点随前一个字换到下一行
+        assert_eq!(wrap("一二三四。五", 4.0), ["一二三", "四。五"]);
+    }
+
+    #[test]
+    fn wrap_breaks_latin_at_spaces() {
+        assert_eq!(wrap("hello big world", 9.0), ["hello big", "world"]);
+        assert_eq!(wrap("hello big world", 12.0), ["hello big", "world"]);
+        assert_eq!(wrap("", 10.0), [""]);
+    }
+
+    #[test]
+    fn wrap_splits_words_longer_than_the_line() {
+        assert_eq!(wrap("abcdefghij xy", 4.0), ["abcd", "efgh", "ij", "xy"]);
+    }
+
+    #[test]
+    fn wrap_keeps_words_across_style_boundaries() {
+        let runs = [("bo".to_string(), 1), ("ld text".to_string(), 2)];
+        let lines = wrap_runs(&runs, 5.0, |_, _| 1.0);
+        assert_eq!(
+            lines,
+            [
+                vec![("bo".to_string(), 1), ("ld".to_string(), 2)],
+                vec![("text".to_string(), 2)],
+            ]
+        );
+    }
+
+    #[test]
+    fn find_urls_drops_trailing_punctuation() {
+        let text = "See https://example.com/a_(b), and www.rust-lang.org. (http://x.org/path)";
+        let urls = find_urls(text);
+        let found: Vec<(&str, &str)> = urls.iter().map(|(range, url)| (&text[range.clone()], url.as_str())).collect();
+        assert_eq!(
+            found,
+            [
+                ("https://example.com/a_(b)", "https://example.com/a_(b)"),
+                ("www.rust-lang.org", "http://www.rust-lang.org"),
+                ("http://x.org/path", "http://x.org/path"),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_urls_stops_at_non_ascii_and_requires_a_boundary() {
+        let text = "见 https://a.cn/x。再见";
+        let urls = find_urls(text);
+        assert_eq!(urls.len(), 1);
+        assert_eq!(&text[urls[0].0.clone()], "https://a.cn/x");
+
+        assert!(find_urls("foo.www.example.com https://").is_empty());
+    }
+
+    #[test]
+    fn justify_spacing_prefers_spaces() {
+        assert_eq!(justify_spacing("a b c", 10.0), (5.0, 0.0));
+        assert_eq!(justify_spacing("中文排版", 6.0), (0.0, 2.0));
+        assert_eq!(justify_spacing("x", 5.0), (0.0, 0.0));
+        assert_eq!(justify_spacing("a b", -1.0), (0.0, 0.0));
+    }
+}
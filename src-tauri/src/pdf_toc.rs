@@ -0,0 +1,110 @@
+use printpdf::*;
+
+use crate::pdf_fonts::PdfFonts;
+use crate::pdf_generator::{fit_caption, PageLayout};
+use crate::pdf_text::{is_cjk, PT_PER_MM};
+
+// 目录只列出前三级标题 (层级从 0 开始)
+pub const MAX_LEVEL: usize = 2;
+// 每级标题的缩进 (毫米)
+const LEVEL_INDENT_MM: f32 = 6.0;
+// 目录标题字号,正文字号的倍数
+const TITLE_SCALE: f32 = 1.6;
+
+// 目录条目,页码从 0 开始
+pub struct TocEntry {
+    pub title: String,
+    pub level: usize,
+    pub page: usize,
+}
+
+// 目录标题: 正文含中日韩文字时用中文,否则用英文 (避免只为标题加载中文字体)
+pub fn toc_title(text: &str) -> &'static str {
+    if text.chars().any(is_cjk) {
+        "目录"
+    } else {
+        "Contents"
+    }
+}
+
+// 目录标题占用的高度 (标题行加一个空行)
+fn title_height(layout: &PageLayout) -> f32 {
+    layout.line_height_mm(layout.font_size * TITLE_SCALE) + layout.line_height_mm(layout.font_size)
+}
+
+// 每页可容纳的条目数,第一页要留出目录标题
+fn rows_per_page(layout: &PageLayout, first: bool) -> usize {
+    let mut height = layout.content_height_mm();
+    if first {
+        height -= title_height(layout);
+    }
+    ((height / layout.line_height_mm(layout.font_size)).floor() as usize).max(1)
+}
+
+// 列出 entries 个条目需要的页数
+pub fn page_count(entries: usize, layout: &PageLayout) -> usize {
+    if entries == 0 {
+        return 0;
+    }
+    let first = rows_per_page(layout, true);
+    1 + entries.saturating_sub(first).div_ceil(rows_per_page(layout, false))
+}
+
+// 在预留的页面上绘制目录: 标题缩进、点引导线、右对齐的页码;
+// 返回每个条目的 (页面在 layers 中的下标, 链接区域),页面不够时其余条目不绘制
+pub fn draw_toc(
+    layers: &[PdfLayerReference],
+    title: &str,
+    entries: &[TocEntry],
+    layout: &PageLayout,
+    fonts: &PdfFonts,
+) -> Vec<(usize, [f32; 4])> {
+    let measurer = &fonts.measurer;
+    let font_size = layout.font_size;
+    let line_height = layout.line_height_mm(font_size);
+    let ascent = measurer.ascent(font_size) / PT_PER_MM;
+    let left = layout.margins.left;
+    let right = layout.width_mm - layout.margins.right;
+    let top = layout.content_top_mm();
+    // 标题与引导线、引导线与页码之间的空隙
+    let gap = font_size / 2.0 / PT_PER_MM;
+    let dot_width = measurer.char_width('.', font_size) / PT_PER_MM;
+    let min_level = entries.iter().map(|entry| entry.level).min().unwrap_or(0);
+
+    if let Some(layer) = layers.first() {
+        let title_size = font_size * TITLE_SCALE;
+        let baseline = top - measurer.ascent(title_size) / PT_PER_MM;
+        fonts.use_text(layer, title, title_size, Mm(left), Mm(baseline));
+    }
+
+    let mut rects = Vec::new();
+    let mut entries = entries.iter();
+    for (index, layer) in layers.iter().enumerate() {
+        let first_row_top = if index == 0 { top - title_height(layout) } else { top };
+
+        for (row, entry) in entries.by_ref().take(rows_per_page(layout, index == 0)).enumerate() {
+            let row_top = first_row_top - row as f32 * line_height;
+            let baseline = row_top - ascent;
+            let x = left + (entry.level - min_level) as f32 * LEVEL_INDENT_MM;
+
+            let number = (entry.page + 1).to_string();
+            let number_x = right - measurer.text_width(&number, font_size) / PT_PER_MM;
+            fonts.use_text(layer, &number, font_size, Mm(number_x), Mm(baseline));
+
+            let title = fit_caption(&entry.title, number_x - x - 2.0 * gap, font_size, measurer);
+            fonts.use_text(layer, &title, font_size, Mm(x), Mm(baseline));
+
+            // 引导线靠右对齐到页码前
+            let title_end = x + measurer.text_width(&title, font_size) / PT_PER_MM;
+            let dots = ((number_x - gap - title_end - gap) / dot_width).floor().max(0.0) as usize;
+            if dots >= 2 {
+                let dots_x = number_x - gap - dots as f32 * dot_width;
+                fonts.use_text(layer, &".".repeat(dots), font_size, Mm(dots_x), Mm(baseline));
+            }
+
+            rects.push((index, [x, row_top - line_height, right, row_top]));
+        }
+    }
+
+    rects
+}
@@ -0,0 +1,353 @@
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId, StringFormat};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// 页面可从父节点继承的属性
+const INHERITABLE_PAGE_KEYS: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+pub fn load_pdf(path: &str) -> Result<Document, String> {
+    let doc = Document::load(path).map_err(|e| format!("Failed to load PDF {}: {}", path, e))?;
+    if doc.is_encrypted() {
+        return Err(format!("PDF {} is encrypted", path));
+    }
+    Ok(doc)
+}
+
+pub fn save_pdf(doc: &mut Document, output_path: &str) -> Result<(), String> {
+    doc.save(output_path)
+        .map_err(|e| format!("Failed to save PDF: {}", e))?;
+    Ok(())
+}
+
+pub fn is_pdf_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("pdf"))
+        .unwrap_or(false)
+}
+
+pub fn file_stem(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(path)
+        .to_string()
+}
+
+// PDF 文本字符串: ASCII 直接写入,其他字符用带 BOM 的 UTF-16BE
+pub fn text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        return Object::string_literal(text);
+    }
+
+    let mut bytes = vec![0xFE, 0xFF];
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
+
+pub fn decode_text_string(bytes: &[u8]) -> String {
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        // PDFDocEncoding 与 Latin-1 在可打印范围基本一致
+        bytes.iter().map(|&b| b as char).collect()
+    }
+}
+
+// 解引用: 间接对象返回目标对象,直接对象原样返回
+pub fn resolve<'a>(doc: &'a Document, object: &'a Object) -> Option<&'a Object> {
+    doc.dereference(object).ok().map(|(_, object)| object)
+}
+
+pub fn resolve_dict<'a>(doc: &'a Document, object: &'a Object) -> Option<&'a Dictionary> {
+    resolve(doc, object).and_then(|object| object.as_dict().ok())
+}
+
+// 把从页面树继承的属性写到每个页面上,页面脱离原页面树后仍保持尺寸和资源
+pub fn flatten_inherited_page_attributes(doc: &mut Document) {
+    let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+
+    for page_id in page_ids {
+        let mut inherited: Vec<(&[u8], Object)> = Vec::new();
+
+        if let Ok(page) = doc.get_dictionary(page_id) {
+            let mut parent = page.get(b"Parent").and_then(Object::as_reference).ok();
+            // 防止循环引用
+            let mut depth = 0;
+
+            while let Some(parent_id) = parent {
+                let Ok(node) = doc.get_dictionary(parent_id) else {
+                    break;
+                };
+                for key in INHERITABLE_PAGE_KEYS {
+                    if !page.has(key) && !inherited.iter().any(|(k, _)| *k == key) {
+                        if let Ok(value) = node.get(key) {
+                            inherited.push((key, value.clone()));
+                        }
+                    }
+                }
+
+                depth += 1;
+                if depth > 64 {
+                    break;
+                }
+                parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+            }
+        }
+
+        if let Ok(page) = doc.get_dictionary_mut(page_id) {
+            for (key, value) in inherited {
+                page.set(key, value);
+            }
+        }
+    }
+}
+
+// 展开名称树 (如 Names/Dests),重名时保留先出现的
+pub fn collect_name_tree(doc: &Document, node: &Dictionary, out: &mut BTreeMap<Vec<u8>, Object>, depth: usize) {
+    if depth > 32 {
+        return;
+    }
+
+    if let Some(names) = node.get(b"Names").ok().and_then(|n| resolve(doc, n)).and_then(|n| n.as_array().ok()) {
+        for pair in names.chunks_exact(2) {
+            if let Ok(name) = pair[0].as_str() {
+                out.entry(name.to_vec()).or_insert_with(|| pair[1].clone());
+            }
+        }
+    }
+
+    if let Some(kids) = node.get(b"Kids").ok().and_then(|k| resolve(doc, k)).and_then(|k| k.as_array().ok()) {
+        for kid in kids {
+            if let Some(kid) = resolve_dict(doc, kid) {
+                collect_name_tree(doc, kid, out, depth + 1);
+            }
+        }
+    }
+}
+
+// 文档中的全部命名目标 (目录 Dests 字典 + Names/Dests 名称树)
+pub fn named_destinations(doc: &Document) -> BTreeMap<Vec<u8>, Object> {
+    let mut named = BTreeMap::new();
+    let Ok(catalog) = doc.catalog() else {
+        return named;
+    };
+
+    if let Some(dests) = catalog.get(b"Dests").ok().and_then(|d| resolve_dict(doc, d)) {
+        for (name, dest) in dests.iter() {
+            named.insert(name.clone(), dest.clone());
+        }
+    }
+
+    for (name, dest) in dests_name_tree(doc) {
+        named.entry(name).or_insert(dest);
+    }
+
+    named
+}
+
+// 只包含 Names/Dests 名称树中的命名目标
+pub fn dests_name_tree(doc: &Document) -> BTreeMap<Vec<u8>, Object> {
+    let mut named = BTreeMap::new();
+
+    let tree = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Names").ok())
+        .and_then(|names| resolve_dict(doc, names))
+        .and_then(|names| names.get(b"Dests").ok())
+        .and_then(|tree| resolve_dict(doc, tree));
+    if let Some(tree) = tree {
+        collect_name_tree(doc, tree, &mut named, 0);
+    }
+
+    named
+}
+
+// 解析跳转目标指向的页面
+pub fn destination_page(doc: &Document, dest: &Object, named: &BTreeMap<Vec<u8>, Object>) -> Option<ObjectId> {
+    destination_page_inner(doc, dest, named, 0)
+}
+
+fn destination_page_inner(
+    doc: &Document,
+    dest: &Object,
+    named: &BTreeMap<Vec<u8>, Object>,
+    depth: usize,
+) -> Option<ObjectId> {
+    if depth > 8 {
+        return None;
+    }
+
+    match resolve(doc, dest)? {
+        Object::Array(array) => array.first()?.as_reference().ok(),
+        Object::String(name, _) | Object::Name(name) => {
+            destination_page_inner(doc, named.get(name)?, named, depth + 1)
+        }
+        // 命名目标可能是带 D 键的字典
+        Object::Dictionary(dict) => destination_page_inner(doc, dict.get(b"D").ok()?, named, depth + 1),
+        _ => None,
+    }
+}
+
+// 书签或链接 (Dest 或 GoTo 动作) 指向的页面
+pub fn link_target_page(doc: &Document, item: &Dictionary, named: &BTreeMap<Vec<u8>, Object>) -> Option<ObjectId> {
+    if let Ok(dest) = item.get(b"Dest") {
+        return destination_page(doc, dest, named);
+    }
+
+    let action = resolve_dict(doc, item.get(b"A").ok()?)?;
+    if action.get(b"S").and_then(Object::as_name).ok()? != b"GoTo" {
+        return None;
+    }
+    destination_page(doc, action.get(b"D").ok()?, named)
+}
+
+pub struct OutlineEntry {
+    pub title: String,
+    pub level: usize,
+    pub page: Option<ObjectId>,
+}
+
+// 按阅读顺序展开书签树
+pub fn outline_entries(doc: &Document) -> Vec<OutlineEntry> {
+    let mut entries = Vec::new();
+    let named = named_destinations(doc);
+
+    let first = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Outlines").ok())
+        .and_then(|outlines| resolve_dict(doc, outlines))
+        .and_then(|outlines| outlines.get(b"First").and_then(Object::as_reference).ok());
+
+    if let Some(first) = first {
+        collect_outline_entries(doc, first, 0, &named, &mut entries);
+    }
+    entries
+}
+
+fn collect_outline_entries(
+    doc: &Document,
+    first: ObjectId,
+    level: usize,
+    named: &BTreeMap<Vec<u8>, Object>,
+    entries: &mut Vec<OutlineEntry>,
+) {
+    if level > 32 {
+        return;
+    }
+
+    let mut current = Some(first);
+    let mut visited = 0;
+    while let Some(id) = current {
+        let Ok(item) = doc.get_dictionary(id) else {
+            break;
+        };
+
+        entries.push(OutlineEntry {
+            title: item
+                .get(b"Title")
+                .and_then(Object::as_str)
+                .map(decode_text_string)
+                .unwrap_or_default(),
+            level,
+            page: link_target_page(doc, item, named),
+        });
+
+        if let Ok(child) = item.get(b"First").and_then(Object::as_reference) {
+            collect_outline_entries(doc, child, level + 1, named, entries);
+        }
+
+        // 防止 Next 形成环
+        visited += 1;
+        if visited > 10000 {
+            break;
+        }
+        current = item.get(b"Next").and_then(Object::as_reference).ok();
+    }
+}
+
+// 根据扁平的书签列表 (标题, 层级, 目标页) 构建书签树,返回 Outlines 根对象
+pub fn build_outline_tree(doc: &mut Document, entries: &[(String, usize, ObjectId)]) -> Option<ObjectId> {
+    if entries.is_empty() {
+        return None;
+    }
+
+    let root_id = doc.new_object_id();
+    let ids: Vec<ObjectId> = entries.iter().map(|_| doc.new_object_id()).collect();
+    let mut items: Vec<Dictionary> = entries
+        .iter()
+        .map(|(title, _, page)| {
+            Dictionary::from_iter(vec![
+                ("Title", text_string(title)),
+                ("Dest", vec![Object::Reference(*page), "Fit".into()].into()),
+            ])
+        })
+        .collect();
+
+    // 层级跳跃超过 1 时按父级 + 1 处理
+    let mut levels: Vec<usize> = Vec::with_capacity(entries.len());
+    for (i, (_, level, _)) in entries.iter().enumerate() {
+        let max_level = if i == 0 { 0 } else { levels[i - 1] + 1 };
+        levels.push((*level).min(max_level));
+    }
+
+    // 用栈确定每个条目的父条目
+    let mut root_children: Vec<usize> = Vec::new();
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); entries.len()];
+    let mut stack: Vec<usize> = Vec::new();
+    for (i, &level) in levels.iter().enumerate() {
+        stack.truncate(level);
+        match stack.last() {
+            Some(&parent) => children[parent].push(i),
+            None => root_children.push(i),
+        }
+        stack.push(i);
+    }
+
+    let link_siblings = |items: &mut Vec<Dictionary>, siblings: &[usize], parent_id: ObjectId| {
+        for (n, &i) in siblings.iter().enumerate() {
+            items[i].set("Parent", parent_id);
+            if n > 0 {
+                items[i].set("Prev", ids[siblings[n - 1]]);
+            }
+            if n + 1 < siblings.len() {
+                items[i].set("Next", ids[siblings[n + 1]]);
+            }
+        }
+    };
+
+    link_siblings(&mut items, &root_children, root_id);
+    for (i, kids) in children.iter().enumerate() {
+        if kids.is_empty() {
+            continue;
+        }
+        link_siblings(&mut items, kids, ids[i]);
+        items[i].set("First", ids[kids[0]]);
+        items[i].set("Last", ids[kids[kids.len() - 1]]);
+        // 子书签默认折叠
+        items[i].set("Count", -(kids.len() as i64));
+    }
+
+    for (id, item) in ids.iter().zip(items) {
+        doc.objects.insert(*id, Object::Dictionary(item));
+    }
+
+    let root = Dictionary::from_iter(vec![
+        ("Type", "Outlines".into()),
+        ("First", ids[root_children[0]].into()),
+        ("Last", ids[root_children[root_children.len() - 1]].into()),
+        ("Count", (root_children.len() as i64).into()),
+    ]);
+    doc.objects.insert(root_id, Object::Dictionary(root));
+
+    Some(root_id)
+}
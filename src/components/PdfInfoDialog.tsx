@@ -0,0 +1,140 @@
+import { Badge } from '@/components/ui/badge'
+import { Dialog, DialogContent, DialogDescription, DialogTitle } from '@/components/ui/dialog'
+
+export interface PdfInfo {
+  file_size: number
+  version: string
+  page_count: number
+  pages: { width_mm: number; height_mm: number; rotation: number }[]
+  metadata: Record<string, string | null>
+  fonts: { name: string; font_type: string; embedded: boolean; subset: boolean }[]
+  images: {
+    page: number
+    width: number
+    height: number
+    color_space: string
+    bits_per_component: number
+    filter: string
+    dpi: number | null
+  }[]
+  encryption: string | null
+  password_required: boolean
+  tagged: boolean
+  pdfa: string | null
+}
+
+interface PdfInfoDialogProps {
+  path: string | null
+  info: PdfInfo | null
+  onClose: () => void
+}
+
+const METADATA_LABELS: [string, string][] = [
+  ['title', '标题'],
+  ['author', '作者'],
+  ['subject', '主题'],
+  ['keywords', '关键词'],
+  ['creator', '创建工具'],
+  ['producer', 'PDF 生成器'],
+  ['creation_date', '创建时间'],
+  ['modification_date', '修改时间'],
+]
+
+const formatFileSize = (bytes: number) => {
+  if (bytes < 1024) return `${bytes} B`
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(2)} KB`
+  return `${(bytes / (1024 * 1024)).toFixed(2)} MB`
+}
+
+// 相同尺寸和旋转的页面合并显示
+const summarizePages = (pages: PdfInfo['pages']) => {
+  const groups = new Map<string, number>()
+  for (const page of pages) {
+    const size = `${page.width_mm.toFixed(0)} × ${page.height_mm.toFixed(0)} mm`
+    const key = page.rotation ? `${size}, 旋转 ${page.rotation}°` : size
+    groups.set(key, (groups.get(key) ?? 0) + 1)
+  }
+  return [...groups.entries()]
+}
+
+export function PdfInfoDialog({ path, info, onClose }: PdfInfoDialogProps) {
+  const fileName = path?.split(/[\\/]/).pop() ?? ''
+  const metadata = info
+    ? METADATA_LABELS.filter(([key]) => info.metadata[key]).map(
+        ([key, label]) => [label, info.metadata[key]] as const
+      )
+    : []
+
+  return (
+    <Dialog open={!!info} onOpenChange={open => !open && onClose()}>
+      <DialogContent className='max-h-[90vh] max-w-xl overflow-auto'>
+        <DialogTitle className='truncate'>{fileName}</DialogTitle>
+        <DialogDescription asChild>
+          {info ? (
+            <div className='space-y-4 text-sm'>
+              <div className='flex flex-wrap items-center gap-2'>
+                <Badge variant='secondary'>PDF {info.version}</Badge>
+                <Badge variant='secondary'>{info.page_count} 页</Badge>
+                <Badge variant='secondary'>{formatFileSize(info.file_size)}</Badge>
+                {info.encryption && (
+                  <Badge variant='destructive'>
+                    {info.encryption}
+                    {info.password_required ? ' · 需要密码' : ' · 权限保护'}
+                  </Badge>
+                )}
+                {info.tagged && <Badge variant='outline'>已标记</Badge>}
+                {info.pdfa && <Badge variant='default'>{info.pdfa}</Badge>}
+              </div>
+
+              {metadata.length > 0 && (
+                <dl className='grid grid-cols-[auto_1fr] gap-x-4 gap-y-1'>
+                  {metadata.map(([label, value]) => (
+                    <div key={label} className='contents'>
+                      <dt className='text-muted-foreground'>{label}</dt>
+                      <dd className='text-foreground break-all'>{value}</dd>
+                    </div>
+                  ))}
+                </dl>
+              )}
+
+              <section>
+                <h3 className='text-foreground mb-1 font-medium'>页面</h3>
+                {summarizePages(info.pages).map(([size, count]) => (
+                  <div key={size}>
+                    {size} {count > 1 && `× ${count}`}
+                  </div>
+                ))}
+              </section>
+
+              <section>
+                <h3 className='text-foreground mb-1 font-medium'>字体 ({info.fonts.length})</h3>
+                {info.fonts.map((font, index) => (
+                  <div key={index} className='flex items-center gap-2'>
+                    <span className='text-foreground truncate'>{font.name}</span>
+                    <span className='text-muted-foreground'>{font.font_type}</span>
+                    <Badge variant={font.embedded ? 'secondary' : 'outline'}>
+                      {font.embedded ? (font.subset ? '嵌入子集' : '已嵌入') : '未嵌入'}
+                    </Badge>
+                  </div>
+                ))}
+              </section>
+
+              <section>
+                <h3 className='text-foreground mb-1 font-medium'>图片 ({info.images.length})</h3>
+                {info.images.map((image, index) => (
+                  <div key={index}>
+                    第 {image.page} 页 · {image.width} × {image.height} · {image.color_space}
+                    {image.filter && ` · ${image.filter}`}
+                    {image.dpi !== null && ` · ${Math.round(image.dpi)} DPI`}
+                  </div>
+                ))}
+              </section>
+            </div>
+          ) : (
+            <div />
+          )}
+        </DialogDescription>
+      </DialogContent>
+    </Dialog>
+  )
+}
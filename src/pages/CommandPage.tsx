@@ -9,7 +9,9 @@ import {
 } from '@/components/ui/command'
 import { Calculator as CalculatorIcon, Braces, AppWindow, ImageDown, FileText } from 'lucide-react'
 import { invoke } from '@tauri-apps/api/core'
+import { getCurrentWebview } from '@tauri-apps/api/webview'
 import { useNavigate } from 'react-router-dom'
+import { PdfInfoDialog, type PdfInfo } from '@/components/PdfInfoDialog'
 
 interface AppInfo {
   name: string
@@ -21,6 +23,8 @@ export function CommandPage() {
   const navigate = useNavigate()
   const [installedApps, setInstalledApps] = useState<AppInfo[]>([])
   const [loading, setLoading] = useState(true)
+  const [pdfPath, setPdfPath] = useState<string | null>(null)
+  const [pdfInfo, setPdfInfo] = useState<PdfInfo | null>(null)
 
   useEffect(() => {
     // 组件挂载时获取已安装的应用列表
@@ -38,6 +42,27 @@ export function CommandPage() {
     fetchApps()
   }, [])
 
+  useEffect(() => {
+    // 拖入 PDF 文件时显示文档信息
+    const unlisten = getCurrentWebview().onDragDropEvent(async event => {
+      if (event.payload.type !== 'drop') return
+      const path = event.payload.paths.find(p => p.toLowerCase().endsWith('.pdf'))
+      if (!path) return
+
+      try {
+        const info = await invoke<PdfInfo>('get_pdf_info', { path })
+        setPdfPath(path)
+        setPdfInfo(info)
+      } catch (error) {
+        console.error('Failed to read PDF info:', error)
+      }
+    })
+
+    return () => {
+      unlisten.then(fn => fn())
+    }
+  }, [])
+
   const handleItemSelect = (action: () => void) => {
     action()
     invoke('toggle_window')
@@ -110,6 +135,7 @@ export function CommandPage() {
           )}
         </CommandList>
       </Command>
+      <PdfInfoDialog path={pdfPath} info={pdfInfo} onClose={() => setPdfInfo(null)} />
     </div>
   )
 }